@@ -1,5 +1,9 @@
 use std::fmt::{Display, Error, Formatter};
 
+use codegen::{escape_template, CodeGen, CodeGenOptions};
+use lexer::is_line_terminator;
+use span::Span;
+
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub enum BinOp {
     Ge,
@@ -13,6 +17,7 @@ pub enum BinOp {
 
     And,
     Or,
+    Coalesce, // ??
 
     BitOr,
     BitXor,
@@ -30,13 +35,56 @@ pub enum BinOp {
     Mod,
 
     InstanceOf,
+    In,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AssignOp {
+    Assign,             // =
+    Plus,               // +=
+    Minus,              // -=
+    Star,               // *=
+    Slash,              // /=
+    Mod,                // %=
+    Exponent,           // **=
+    ShiftLeft,          // <<=
+    ShiftRight,         // >>=
+    ShiftRightUnsigned, // >>>=
+    BitAnd,             // &=
+    BitOr,              // |=
+    BitXor,             // ^=
+    And,                // &&=
+    Or,                 // ||=
+    Coalesce,           // ??=
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DeclKind {
+    Var,
+    Let,
+    Const,
+}
+
+// Whether a function is `async`, a generator (`function*`), or both. Calling an `async` function
+// returns a promise, and calling a generator returns an iterator over the values it yields,
+// instead of running the body to completion.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FnKind {
+    Normal,
+    Async,
+    Generator,
+    AsyncGenerator,
 }
 
 #[derive(Clone, PartialEq, Eq, PartialOrd, Ord)]
 pub enum Precedence {
     Const = 110,
+    // An optional chain, which has to be grouped to be used as the object of a member access or
+    // the callee of a call outside of the chain
+    Chain = 105,
     Sign = 100,
     Inc = 90,
+    Exponent = 80,
     Mult = 70,
     Add = 60,
     Shift = 52,
@@ -47,30 +95,85 @@ pub enum Precedence {
     BitOr = 41,
     And = 40,
     Or = 30,
+    // `??` can't be mixed with `&&` or `||` without grouping; see `CodeGen::exp`
+    Coalesce = 25,
+    Conditional = 20,
+    Assign = 15,
+    Comma = 10,
+}
+
+impl FnKind {
+    pub fn new(is_async: bool, generator: bool) -> FnKind {
+        match (is_async, generator) {
+            (false, false) => FnKind::Normal,
+            (true, false) => FnKind::Async,
+            (false, true) => FnKind::Generator,
+            (true, true) => FnKind::AsyncGenerator,
+        }
+    }
+
+    pub fn is_async(&self) -> bool {
+        *self == FnKind::Async || *self == FnKind::AsyncGenerator
+    }
+
+    pub fn is_generator(&self) -> bool {
+        *self == FnKind::Generator || *self == FnKind::AsyncGenerator
+    }
 }
 
 impl BinOp {
+    /// The operator written as `op`, e.g. `>>>` or `instanceof`.
+    pub fn from_op(op: &str) -> Option<BinOp> {
+        let op = match op {
+            ">=" => BinOp::Ge,
+            ">" => BinOp::Gt,
+            "==" => BinOp::Eql,
+            "===" => BinOp::EqlStrict,
+            "!=" => BinOp::Neq,
+            "!==" => BinOp::NeqStrict,
+            "<=" => BinOp::Le,
+            "<" => BinOp::Lt,
+            "&&" => BinOp::And,
+            "||" => BinOp::Or,
+            "??" => BinOp::Coalesce,
+            "|" => BinOp::BitOr,
+            "^" => BinOp::BitXor,
+            "&" => BinOp::BitAnd,
+            "<<" => BinOp::ShiftLeft,
+            ">>" => BinOp::ShiftRight,
+            ">>>" => BinOp::ShiftRightUnsigned,
+            "-" => BinOp::Minus,
+            "+" => BinOp::Plus,
+            "/" => BinOp::Slash,
+            "*" => BinOp::Star,
+            "**" => BinOp::Exponent,
+            "%" => BinOp::Mod,
+            "instanceof" => BinOp::InstanceOf,
+            "in" => BinOp::In,
+            _ => return None,
+        };
+
+        Some(op)
+    }
+
     pub fn precedence(&self) -> Precedence {
         match *self {
             BinOp::And => Precedence::And,
-            BinOp::Ge | BinOp::Gt | BinOp::Le | BinOp::Lt | BinOp::InstanceOf => Precedence::Cmp,
+            BinOp::Ge | BinOp::Gt | BinOp::Le | BinOp::Lt | BinOp::InstanceOf | BinOp::In => {
+                Precedence::Cmp
+            }
             BinOp::Eql | BinOp::Neq | BinOp::EqlStrict | BinOp::NeqStrict => Precedence::Equality,
             BinOp::Or => Precedence::Or,
+            BinOp::Coalesce => Precedence::Coalesce,
             BinOp::BitOr => Precedence::BitOr,
             BinOp::BitXor => Precedence::BitXor,
             BinOp::BitAnd => Precedence::BitAnd,
             BinOp::Minus | BinOp::Plus => Precedence::Add,
-            BinOp::Slash | BinOp::Star | BinOp::Exponent | BinOp::Mod => Precedence::Mult,
+            BinOp::Exponent => Precedence::Exponent,
+            BinOp::Slash | BinOp::Star | BinOp::Mod => Precedence::Mult,
             BinOp::ShiftLeft | BinOp::ShiftRight | BinOp::ShiftRightUnsigned => Precedence::Shift,
         }
     }
-
-    pub fn is_commutative(&self) -> bool {
-        match *self {
-            BinOp::Minus | BinOp::Slash => false,
-            _ => true
-        }
-    }
 }
 
 impl Display for BinOp {
@@ -87,6 +190,7 @@ impl Display for BinOp {
             BinOp::Neq => write!(fmt, "!="),
             BinOp::NeqStrict => write!(fmt, "!=="),
             BinOp::Or => write!(fmt, "||"),
+            BinOp::Coalesce => write!(fmt, "??"),
             BinOp::Plus => write!(fmt, "+"),
             BinOp::Slash => write!(fmt, "/"),
             BinOp::Star => write!(fmt, "*"),
@@ -99,336 +203,603 @@ impl Display for BinOp {
             BinOp::ShiftRight => write!(fmt, ">>"),
             BinOp::ShiftRightUnsigned => write!(fmt, ">>>"),
             BinOp::InstanceOf => write!(fmt, "instanceof"),
+            BinOp::In => write!(fmt, "in"),
         }
     }
 }
 
-#[derive(Clone, Debug)]
+impl AssignOp {
+    /// The operator written as `op`, e.g. `+=`.
+    pub fn from_op(op: &str) -> Option<AssignOp> {
+        let op = match op {
+            "=" => AssignOp::Assign,
+            "+=" => AssignOp::Plus,
+            "-=" => AssignOp::Minus,
+            "*=" => AssignOp::Star,
+            "/=" => AssignOp::Slash,
+            "%=" => AssignOp::Mod,
+            "**=" => AssignOp::Exponent,
+            "<<=" => AssignOp::ShiftLeft,
+            ">>=" => AssignOp::ShiftRight,
+            ">>>=" => AssignOp::ShiftRightUnsigned,
+            "&=" => AssignOp::BitAnd,
+            "|=" => AssignOp::BitOr,
+            "^=" => AssignOp::BitXor,
+            "&&=" => AssignOp::And,
+            "||=" => AssignOp::Or,
+            "??=" => AssignOp::Coalesce,
+            _ => return None,
+        };
+
+        Some(op)
+    }
+}
+
+impl Display for AssignOp {
+    fn fmt(&self, fmt: &mut Formatter) -> Result<(), Error> {
+        let op = match *self {
+            AssignOp::Assign => "=",
+            AssignOp::Plus => "+=",
+            AssignOp::Minus => "-=",
+            AssignOp::Star => "*=",
+            AssignOp::Slash => "/=",
+            AssignOp::Mod => "%=",
+            AssignOp::Exponent => "**=",
+            AssignOp::ShiftLeft => "<<=",
+            AssignOp::ShiftRight => ">>=",
+            AssignOp::ShiftRightUnsigned => ">>>=",
+            AssignOp::BitAnd => "&=",
+            AssignOp::BitOr => "|=",
+            AssignOp::BitXor => "^=",
+            AssignOp::And => "&&=",
+            AssignOp::Or => "||=",
+            AssignOp::Coalesce => "??=",
+        };
+
+        write!(fmt, "{}", op)
+    }
+}
+
+impl Display for DeclKind {
+    fn fmt(&self, fmt: &mut Formatter) -> Result<(), Error> {
+        match *self {
+            DeclKind::Var => write!(fmt, "var"),
+            DeclKind::Let => write!(fmt, "let"),
+            DeclKind::Const => write!(fmt, "const"),
+        }
+    }
+}
+
+// A comment from the source, kept with the statement or expression next to it so that it's
+// printed again along with it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Comment {
+    // The text between the delimiters, e.g. `* Docs ` for `/** Docs */`
+    pub text: String,
+    // Whether it's a /* block */ comment rather than a // line comment
+    pub block: bool,
+    // How many line breaks there are between the comment and the code or comment before it, where
+    // 0 means it's on the same line and 2 or more means there's a blank line before it
+    pub lines_before: usize,
+}
+
+impl Comment {
+    /// Whether the comment ends a line, which any line comment does, as does a block comment with
+    /// a line break in it, as far as automatic semicolon insertion is concerned.
+    pub fn ends_line(&self) -> bool {
+        !self.block || self.text.contains(is_line_terminator)
+    }
+}
+
+// The comments around a statement or expression.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Comments {
+    // Comments before it and after any code before it
+    pub leading: Vec<Comment>,
+    // Comments after it on the same line or, after the last statement in a block, anywhere up to
+    // the end of the block
+    pub trailing: Vec<Comment>,
+    // How many line breaks there are between it and its last leading comment, or the code before
+    // it if it has none, counted like `Comment::lines_before`
+    pub lines_before: usize,
+}
+
+#[derive(Clone, Debug, PartialEq)]
 pub enum Exp {
-    Array(Vec<Box<Exp>>),
+    // [a, , ...b], where `None` is a hole
+    Array(Vec<Option<Box<Exp>>>),
+    // (params) => body, which takes `this` and `arguments` from the enclosing function and can't
+    // be called with `new`. Arrow functions can be `async`, but can't be generators.
+    Arrow(FnKind, Vec<Pattern>, ArrowBody),
+    // Assignment as an expression, e.g. `a = b = 0` or `x += 1`; see also `Stmt::Assign`.
+    AssignExp(AssignOp, Box<Pattern>, Box<Exp>),
+    // await e, which is only valid in an `async` function
+    Await(Box<Exp>),
     BitNot(Box<Exp>),
     BinExp(Box<Exp>, BinOp, Box<Exp>),
     Bool(bool),
     Call(Box<Exp>, Vec<Box<Exp>>),
-    Defun(Option<String>, Vec<String>, Vec<Stmt>),
+    // The extent of an optional chain, e.g. `a?.b.c`. If any `Optional` in the chain is null or
+    // undefined, the rest of the chain is skipped and the whole chain is `undefined`.
+    Chain(Box<Exp>),
+    // class name extends base { members }
+    Class(Box<Class>),
+    // e1, e2
+    Comma(Box<Exp>, Box<Exp>),
+    // Wraps an expression with the comments around it in the source.
+    Commented(Box<Comments>, Box<Exp>),
+    // test ? e1 : e2
+    Conditional(Box<Exp>, Box<Exp>, Box<Exp>),
+    // function name(params) { body }, or `async function`, `function*` or `async function*`
+    Defun(FnKind, Option<String>, Vec<Pattern>, Vec<Stmt>),
+    Delete(Box<Exp>),
+    // Stands in for an expression that couldn't be parsed, in the AST from a recovering parse;
+    // see `Parser::parse_program_recovering`.
+    Error,
     Float(f64),
+    // obj.name, where a private name keeps its `#`, e.g. `this.#count`
     InstanceVar(Box<Exp>, String),
     KeyAccessor(Box<Exp>, Box<Exp>),
     LogNot(Box<Exp>),
     Neg(Box<Exp>),
     Null,
     NewObject(Box<Exp>, Vec<Box<Exp>>),
-    Object(Vec<(String, Box<Exp>)>),
+    Object(Vec<Prop>),
+    // e?., as the object of a member access or the callee of a call in an optional chain, e.g.
+    // `a?.b` is `InstanceVar(Optional(a), "b")` and `f?.()` is `Call(Optional(f), [])`
+    Optional(Box<Exp>),
     Pos(Box<Exp>),
     PostDec(Box<Exp>),
     PostInc(Box<Exp>),
     PreDec(Box<Exp>),
     PreInc(Box<Exp>),
+    // /pattern/flags, where the pattern is kept as it's written in the source
+    RegExp(String, String),
+    // Wraps an expression with the location of the source it was parsed from.
+    Spanned(Span, Box<Exp>),
+    // `super`, which is only valid as the callee of a call in a constructor, e.g. `super(x)`, or
+    // as the object of a property access in a method, e.g. `super.m()`
+    Super,
+    // ...e, which is only valid as an array element or an argument
+    Spread(Box<Exp>),
+    // tag`text ${e}`, which calls `tag` with the string parts and the values of the substitutions
+    TaggedTemplate(Box<Exp>, Template),
+    // `text ${e}`
+    Template(Template),
+    This,
     TypeOf(Box<Exp>),
     Str(String),
     Undefined,
     Var(String),
+    Void(Box<Exp>),
+    // yield e, or a bare `yield`, which is only valid in a generator
+    Yield(Option<Box<Exp>>),
+    // yield* e, which yields each of the values of the iterable `e` in turn
+    YieldAll(Box<Exp>),
 }
 
 impl Exp {
     pub fn precedence(&self) -> Precedence {
         match *self {
             Exp::BinExp(_, ref o, _) => o.precedence(),
-            Exp::BitNot(_) | Exp::LogNot(_) | Exp::Neg(_) | Exp::Pos(_) => Precedence::Sign,
+            Exp::Await(_) | Exp::BitNot(_) | Exp::Delete(_) | Exp::LogNot(_) | Exp::Neg(_) |
+            Exp::Pos(_) | Exp::TypeOf(_) | Exp::Void(_) => Precedence::Sign,
+            Exp::Arrow(..) | Exp::AssignExp(..) | Exp::Yield(_) | Exp::YieldAll(_) => {
+                Precedence::Assign
+            }
+            Exp::Comma(..) => Precedence::Comma,
+            Exp::Conditional(..) => Precedence::Conditional,
+            Exp::Chain(_) => Precedence::Chain,
+            // Negative numbers are printed with a leading minus sign
+            Exp::Float(f) if f.is_sign_negative() && !f.is_nan() => Precedence::Sign,
             Exp::PostDec(_) | Exp::PostInc(_) | Exp::PreDec(_) | Exp::PreInc(_) => Precedence::Inc,
+            Exp::Commented(_, ref e) | Exp::Spanned(_, ref e) => e.precedence(),
             _ => Precedence::Const
         }
     }
-}
 
-macro_rules! group {
-    ($e:expr, $p:expr) => {
-        if $e.precedence() < $p {
-            format!("({})", $e)
-        } else {
-            format!("{}", $e)
+    /// Attaches a source location to the expression, replacing any location it already had.
+    pub fn with_span(self, span: Span) -> Exp {
+        match self {
+            Exp::Commented(comments, e) => Exp::Commented(comments, Box::new(e.with_span(span))),
+            Exp::Spanned(_, e) => Exp::Spanned(span, e),
+            e => Exp::Spanned(span, Box::new(e)),
         }
     }
-}
 
-impl Exp {
-    fn fmt_helper(&self, mut fmt: &mut Formatter, indent_level: i32) -> Result<(), Error> {
-        macro_rules! stmt_block {
-            ($stmt:expr) => {
-                for s in $stmt {
-                    try!(s.fmt_helper(&mut fmt, indent_level + 2))
-                }
-            }
+    /// The source location of the expression, if it was given one.
+    pub fn span(&self) -> Option<&Span> {
+        match *self {
+            Exp::Commented(_, ref e) => e.span(),
+            Exp::Spanned(ref span, _) => Some(span),
+            _ => None,
         }
+    }
+
+    /// Attaches comments to the expression, replacing any it already had.
+    pub fn with_comments(self, comments: Comments) -> Exp {
+        match self {
+            Exp::Commented(_, e) => Exp::Commented(Box::new(comments), e),
+            e => Exp::Commented(Box::new(comments), Box::new(e)),
+        }
+    }
 
+    /// The comments around the expression, if it was given any.
+    pub fn comments(&self) -> Option<&Comments> {
         match *self {
-            Exp::Array(ref vec) => {
-                try!(write!(fmt, "["));
+            Exp::Commented(ref comments, _) => Some(comments),
+            Exp::Spanned(_, ref e) => e.comments(),
+            _ => None,
+        }
+    }
 
-                for (i, elem) in vec.iter().enumerate() {
-                    if i != 0 {
-                        try!(write!(fmt, ", "));
-                    }
+    /// The expression itself, looking through any source location or comments attached to it.
+    pub fn node(&self) -> &Exp {
+        match *self {
+            Exp::Commented(_, ref e) | Exp::Spanned(_, ref e) => e.node(),
+            ref e => e,
+        }
+    }
+}
 
-                    try!(write!(fmt, "{}", elem));
-                }
+impl Display for Exp {
+    fn fmt(&self, fmt: &mut Formatter) -> Result<(), Error> {
+        let mut gen = CodeGen::new(CodeGenOptions::default());
+        gen.exp(self);
+        write!(fmt, "{}", gen.finish())
+    }
+}
 
-                write!(fmt, "]")
-            }
-            Exp::BinExp(ref e1, ref o, ref e2) => {
-                let prec = self.precedence();
-
-                // Put grouping parentheses if the left subexpression has a lower-precedence
-                // operator, e.g. (1 + 2) * 3
-                let left = if prec > e1.precedence() {
-                    format!("({})", e1)
-                } else {
-                    format!("{}", e1)
-                };
-
-                let right_prec = e2.precedence();
-
-                // Put grouping parentheses around the right subexpression if it has a
-                // lower-precedence operator,  __OR__ if `o` is not commutative and the precedence
-                // is the same, e.g. (1 + 2) * 3 __OR__ 1 - (2 + 3)
-                let right = if prec > right_prec || (!o.is_commutative() && prec == right_prec) {
-                    format!("({})", e2)
-                } else {
-                    format!("{}", e2)
-                };
-
-                write!(fmt, "{} {} {}", left, o, right)
-            }
-            Exp::BitNot(ref e) => write!(fmt, "~{}", group!(e, Precedence::Sign)),
-            Exp::Bool(b) => write!(fmt, "{}", b),
-            Exp::Call(ref func, ref args) => {
-                try!(write!(fmt, "{}(", func));
+// A property of an object literal.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Prop {
+    // key: value, where a key that reads back as the same number, e.g. `1`, is printed unquoted
+    Value(String, Box<Exp>),
+    // [key]: value
+    Computed(Box<Exp>, Box<Exp>),
+    // x, which is short for `x: x`
+    Shorthand(String),
+    // key(params) { body }, which can also be `async`, a generator (`*key`), or both
+    Method(FnKind, PropKey, Vec<Pattern>, Vec<Stmt>),
+    // get key() { body }
+    Get(PropKey, Vec<Stmt>),
+    // set key(param) { body }
+    Set(PropKey, Pattern, Vec<Stmt>),
+    // ...e, which copies the properties of `e`
+    Spread(Box<Exp>),
+}
 
-                for (i, arg) in args.iter().enumerate() {
-                    if i != 0 {
-                        try!(write!(fmt, ", "));
-                    }
+// The key of a method or accessor in an object literal.
+#[derive(Clone, Debug, PartialEq)]
+pub enum PropKey {
+    Name(String),
+    // [key]
+    Computed(Box<Exp>),
+}
 
-                    try!(write!(fmt, "{}", arg));
-                }
+// A class, which is a constructor function together with the methods on its prototype and the
+// static members on the constructor itself.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Class {
+    pub name: Option<String>,
+    pub extends: Option<Box<Exp>>,
+    pub members: Vec<ClassMember>,
+}
 
-                write!(fmt, ")")
+impl Class {
+    /// The parameters and body of the class's `constructor` method, if it has one.
+    pub fn constructor(&self) -> Option<(&Vec<Pattern>, &Vec<Stmt>)> {
+        self.members.iter().filter_map(|member| {
+            match (member.is_static, &member.key, &member.kind) {
+                (false, &ClassKey::Name(ref name),
+                 &MemberKind::Method(FnKind::Normal, ref params, ref body))
+                    if name == "constructor" => Some((params, body)),
+                _ => None,
             }
-            Exp::Defun(ref opt, ref params, ref body) => {
-                try!(write!(fmt, "function"));
+        }).next()
+    }
+}
 
-                if let &Some(ref func) = opt {
-                    try!(write!(fmt, " {}", func));
-                }
+// A method, accessor or field of a class, e.g. `static get count() { ... }`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ClassMember {
+    pub is_static: bool,
+    pub key: ClassKey,
+    pub kind: MemberKind,
+}
 
-                try!(write!(fmt, "("));
+#[derive(Clone, Debug, PartialEq)]
+pub enum ClassKey {
+    Name(String),
+    // #name, which is only visible inside the class body; the `#` isn't part of the string
+    Private(String),
+    // [key]
+    Computed(Box<Exp>),
+}
 
-                for (i, param) in params.iter().enumerate() {
-                    if i != 0 {
-                        try!(write!(fmt, ", "));
-                    }
+#[derive(Clone, Debug, PartialEq)]
+pub enum MemberKind {
+    // key(params) { body }, which is the constructor if it's a non-static, non-async,
+    // non-generator method named `constructor`
+    Method(FnKind, Vec<Pattern>, Vec<Stmt>),
+    // get key() { body }
+    Get(Vec<Stmt>),
+    // set key(param) { body }
+    Set(Pattern, Vec<Stmt>),
+    // key = value;
+    Field(Option<Exp>),
+}
 
-                    try!(write!(fmt, "{}", param));
-                }
+// The string parts and substitutions of a template literal, e.g. `a${b}c`. There is always one more
+// string part than there are substitutions, so a template without any has a single part.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Template {
+    pub quasis: Vec<TemplatePart>,
+    pub exps: Vec<Box<Exp>>,
+}
 
-                try!(write!(fmt, ") {{\n"));
-                stmt_block!(body);
+// One of the string parts of a template. `raw` is the text as it's written in the source, escapes
+// and all, and `cooked` is its value. In a tagged template, the text can contain escapes that
+// aren't valid in a string, e.g. `\u`, in which case `cooked` is `None`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TemplatePart {
+    pub cooked: Option<String>,
+    pub raw: String,
+}
 
-                let indent : String = (0..indent_level).map(|_| " ").collect();
+impl TemplatePart {
+    /// The part whose value is `s`, with the text escaped where it needs to be.
+    pub fn new(s: &str) -> TemplatePart {
+        TemplatePart { cooked: Some(String::from(s)), raw: escape_template(s) }
+    }
+}
 
-                write!(fmt, "{}}}", indent)
-            }
-            Exp::Float(f) => write!(fmt, "{}", f),
-            Exp::InstanceVar(ref obj, ref name) => write!(fmt, "{}.{}", obj, name),
-            Exp::KeyAccessor(ref obj, ref key) => write!(fmt, "{}[{}]", obj, key),
-            Exp::LogNot(ref e) => write!(fmt, "!{}", group!(e, Precedence::Sign)),
-            Exp::Neg(ref e) => write!(fmt, "-{}", group!(e, Precedence::Sign)),
-            Exp::NewObject(ref name, ref args) => {
-                try!(write!(fmt, "new {}(", name));
-
-                for (i, arg) in args.iter().enumerate() {
-                    if i != 0 {
-                        try!(write!(fmt, ", "));
-                    }
-
-                    try!(write!(fmt, "{}", arg))
-                }
+// The target of a declaration, parameter or assignment, which can take apart arrays and objects,
+// e.g. `{x, y: [first, ...others] = []}`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Pattern {
+    // A name being declared
+    Var(String),
+    // In an assignment, an expression being assigned to, e.g. `x` or `o.x`
+    Exp(Box<Exp>),
+    // pattern = default, which is used when the value is `undefined`
+    Default(Box<Pattern>, Exp),
+    // ...pattern, which takes the remaining elements; only valid as the last parameter or element
+    // of an array pattern
+    Rest(Box<Pattern>),
+    // [a, , b], where `None` is a hole that skips an element
+    Array(Vec<Option<Pattern>>),
+    // {key: pattern, ...rest}
+    Object(Vec<(String, Pattern)>, Option<Box<Pattern>>),
+}
 
-                write!(fmt, ")")
-            }
-            Exp::Null => write!(fmt, "null"),
-            Exp::Object(ref properties) => {
-                if properties.is_empty() {
-                    return write!(fmt, "{{}}");
-                }
+// In an assignment, any expression can be the target.
+impl From<Exp> for Pattern {
+    fn from(e: Exp) -> Pattern {
+        Pattern::Exp(Box::new(e))
+    }
+}
 
-                try!(writeln!(fmt, "{{"));
+impl<'a> From<&'a str> for Pattern {
+    fn from(v: &'a str) -> Pattern {
+        Pattern::Var(String::from(v))
+    }
+}
 
-                let indent : String = (0..indent_level + 2).map(|_| " ").collect();
+impl From<String> for Pattern {
+    fn from(v: String) -> Pattern {
+        Pattern::Var(v)
+    }
+}
 
-                for (i, &(ref name, ref prop)) in properties.iter().enumerate() {
-                    if i != 0 {
-                        try!(writeln!(fmt, ","));
-                    }
+impl Display for Pattern {
+    fn fmt(&self, fmt: &mut Formatter) -> Result<(), Error> {
+        let mut gen = CodeGen::new(CodeGenOptions::default());
+        gen.pattern(self);
+        write!(fmt, "{}", gen.finish())
+    }
+}
 
-                    try!(write!(fmt, "{}{}: ", indent, name));
-                    try!(prop.fmt_helper(&mut fmt, indent_level + 2));
-                }
+#[derive(Clone, Debug, PartialEq)]
+pub enum ArrowBody {
+    // x => x + 1
+    Exp(Box<Exp>),
+    // x => { stmts }
+    Block(Vec<Stmt>),
+}
 
-                write!(fmt, "\n}}")
-            }
-            Exp::Pos(ref e) => write!(fmt, "+{}", group!(e, Precedence::Sign)),
-            Exp::PostDec(ref e) => write!(fmt, "{}--", group!(e, Precedence::Inc)),
-            Exp::PostInc(ref e) => write!(fmt, "{}++", group!(e, Precedence::Inc)),
-            Exp::PreDec(ref e) => write!(fmt, "--{}", group!(e, Precedence::Inc)),
-            Exp::PreInc(ref e) => write!(fmt, "++{}", group!(e, Precedence::Inc)),
-            Exp::TypeOf(ref e) => write!(fmt, "typeof {}", e),
-            Exp::Str(ref s) => write!(fmt, "\"{}\"", s),
-            Exp::Undefined => write!(fmt, "undefined"),
-            Exp::Var(ref v) => write!(fmt, "{}", v),
+impl ArrowBody {
+    /// The body as a list of statements, turning an expression body into a `return`.
+    pub fn to_block(&self) -> Vec<Stmt> {
+        match *self {
+            ArrowBody::Exp(ref e) => vec![Stmt::Ret((**e).clone())],
+            ArrowBody::Block(ref block) => block.clone(),
         }
     }
 }
 
-impl Display for Exp {
-    fn fmt(&self, mut fmt: &mut Formatter) -> Result<(), Error> {
-        self.fmt_helper(&mut fmt, 0)
-    }
+// One of the names an `import` declaration binds.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ImportSpec {
+    // import local from "source"
+    Default(String),
+    // import * as local from "source"
+    Namespace(String),
+    // import { imported as local } from "source", where `imported` can be any string
+    Named(String, String),
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum Stmt {
-    Assign(Exp, Exp),
+    // The same as a `BareExp` of a plain `=` `AssignExp`.
+    Assign(Pattern, Exp),
     BareExp(Exp),
-    Break,
-    Continue,
-    Decl(String, Exp),
+    // { stmts }, which opens a new scope for `let` and `const` declarations
+    Block(Vec<Stmt>),
+    // break label;
+    Break(Option<String>),
+    Continue(Option<String>),
+    // class name extends base { members }, which declares `name` like `let` does
+    ClassDecl(Class),
+    // Wraps a statement with the comments around it in the source. An `Empty` statement with
+    // comments stands in for comments with no statement to go with, e.g. in an empty block.
+    Commented(Box<Comments>, Box<Stmt>),
+    // Declares each name, initializing those that have an initial value, e.g. `let a = 1, b;`
+    Decl(DeclKind, Vec<(Pattern, Option<Exp>)>),
+    // do { block } while (condition);
+    DoWhile(Vec<Stmt>, Exp),
     Empty,
+    // Stands in for a statement that couldn't be parsed, like `Exp::Error`.
+    Error,
+    // export declaration, where the declaration is a `Decl`, an `FnDecl` or a `ClassDecl`
+    Export(Box<Stmt>),
+    // export * from "source", or `export * as name from "source"`
+    ExportAll(Option<String>, String),
+    // export default e;
+    ExportDefault(Exp),
+    // export { local as exported }, or a re-export of the names of another module if there's a
+    // source, e.g. `export { a as b } from "source"`
+    ExportNames(Vec<(String, String)>, Option<String>),
+    // function name(params) { body }, which declares `name` for the whole of the enclosing
+    // function or script and is hoisted with its value, so it can be called before it
+    FnDecl(FnKind, String, Vec<Pattern>, Vec<Stmt>),
     If(Exp, Vec<Stmt>, Vec<Stmt>),
     // for (initialization, condition, step) { block }
     For(Box<Stmt>, Exp, Exp, Vec<Stmt>),
+    // for (left in object) { block }, where the left side is a `Decl` without an initial value
+    // or a `BareExp` to assign to
+    ForIn(Box<Stmt>, Exp, Vec<Stmt>),
+    // for (left of iterable) { block }, with the same kind of left side as `ForIn`
+    ForOf(Box<Stmt>, Exp, Vec<Stmt>),
+    // import specifiers from "source", where `import "source";` has no specifiers
+    Import(Vec<ImportSpec>, String),
+    // label: stmt
+    Labeled(String, Box<Stmt>),
     Ret(Exp),
     Seq(Box<Stmt>, Box<Stmt>),
-    // try block, catch variable, catch block, finally block
-    Try(Vec<Stmt>, String, Vec<Stmt>, Vec<Stmt>),
+    // Wraps a statement with the location of the source it was parsed from.
+    Spanned(Span, Box<Stmt>),
+    // switch (discriminant) { cases }, where each case is a test (`None` for `default`) and the
+    // statements under it. Control falls through from a case into the next one unless its
+    // statements break out of the switch.
+    Switch(Exp, Vec<(Option<Exp>, Vec<Stmt>)>),
+    // try block, catch binding (`None` for `catch { ... }`), catch block, finally block
+    Try(Vec<Stmt>, Option<Pattern>, Vec<Stmt>, Vec<Stmt>),
     Throw(Box<Exp>),
-    VarDecl(String),
     While(Exp, Vec<Stmt>),
 }
 
 impl Stmt {
-    pub fn fmt_helper(&self, mut fmt: &mut Formatter, indent_level: i32) -> Result<(), Error> {
-        macro_rules! stmt_block {
-            ($stmt:expr) => {
-                for s in $stmt {
-                    try!(s.fmt_helper(&mut fmt, indent_level + 2))
-                }
-            }
+    /// Attaches a source location to the statement, replacing any location it already had.
+    pub fn with_span(self, span: Span) -> Stmt {
+        match self {
+            Stmt::Commented(comments, s) => Stmt::Commented(comments, Box::new(s.with_span(span))),
+            Stmt::Spanned(_, s) => Stmt::Spanned(span, s),
+            s => Stmt::Spanned(span, Box::new(s)),
         }
+    }
 
-        macro_rules! indented_stmt {
-            ($s:expr) => {
-                try!($s.fmt_helper(&mut fmt, indent_level + 2))
-            }
+    /// The source location of the statement, if it was given one.
+    pub fn span(&self) -> Option<&Span> {
+        match *self {
+            Stmt::Commented(_, ref s) => s.span(),
+            Stmt::Spanned(ref span, _) => Some(span),
+            _ => None,
         }
+    }
 
-        macro_rules! exp_semi {
-            ($exp:expr) => {{
-                try!($exp.fmt_helper(&mut fmt, indent_level));
-                writeln!(fmt, ";")
-            }}
+    /// Attaches comments to the statement, replacing any it already had.
+    pub fn with_comments(self, comments: Comments) -> Stmt {
+        match self {
+            Stmt::Commented(_, s) => Stmt::Commented(Box::new(comments), s),
+            s => Stmt::Commented(Box::new(comments), Box::new(s)),
         }
+    }
 
-        let indent : String = (0..indent_level).map(|_| " ").collect();
+    /// The comments around the statement, if it was given any.
+    pub fn comments(&self) -> Option<&Comments> {
+        match *self {
+            Stmt::Commented(ref comments, _) => Some(comments),
+            Stmt::Spanned(_, ref s) => s.comments(),
+            _ => None,
+        }
+    }
 
+    /// The statement itself, looking through any source location or comments attached to it.
+    pub fn node(&self) -> &Stmt {
         match *self {
-            Stmt::Assign(ref v, ref exp) => {
-                try!(write!(fmt, "{}{} = ", indent, v));
-                exp_semi!(exp)
-            }
-            Stmt::BareExp(ref exp) => {
-                try!(write!(fmt, "{}", indent));
-                exp_semi!(exp)
-            }
-            Stmt::Break => {
-                write!(fmt, "break;")
-            }
-            Stmt::Continue => {
-                write!(fmt, "continue;")
-            }
-            Stmt::Decl(ref v, ref exp) => {
-                try!(write!(fmt, "{}var {} = ", indent, v));
-                exp_semi!(exp)
-            }
-            Stmt::Empty => Ok(()),
-            Stmt::If(ref e, ref s, ref els) => {
-                try!(write!(fmt, "{}if (", indent));
-                try!(e.fmt_helper(&mut fmt, indent_level + 2));
-                try!(writeln!(fmt, ") {{\n"));
-                stmt_block!(s);
-
-                if els.len() > 0 {
-                    stmt_block!(s);
-                }
+            Stmt::Commented(_, ref s) | Stmt::Spanned(_, ref s) => s.node(),
+            ref s => s,
+        }
+    }
 
-                //if let &Some(ref stmt) = els {
-                //    try!(write!(fmt, "{}else {{\n", indent));
-                //    indented_stmt!(stmt);
-                //    try!(write!(fmt, "{}}}\n", indent));
-                //}
+    /// Prints the statement as if it were nested `indent_level` spaces deep.
+    pub fn fmt_helper(&self, fmt: &mut Formatter, indent_level: i32) -> Result<(), Error> {
+        let mut gen = CodeGen::new(CodeGenOptions::default()).indented(indent_level as usize / 2);
+        gen.stmt(self);
+        write!(fmt, "{}", gen.finish())
+    }
+}
 
-                Ok(())
-            }
-            Stmt::For(ref init, ref cond, ref step, ref block) => {
-                try!(write!(fmt, "{}for ({}; {}; {})", indent,
-                                    init, cond, step));
-                stmt_block!(block);
-                Ok(())
-            }
-            Stmt::Ret(ref e) => {
-                try!(write!(fmt, "{}return ", indent));
-                exp_semi!(e)
-            }
-            Stmt::Seq(ref s1, ref s2) => {
-                try!(s1.fmt_helper(&mut fmt, indent_level));
-                s2.fmt_helper(&mut fmt, indent_level)
-            }
-            Stmt::Throw(ref e) => {
-                write!(fmt, "{}throw {}", indent, e)
-            }
-            Stmt::Try(ref stmt, ref catch_var, ref catch_block, ref finally_block) => {
-                try!(write!(fmt, "{}try {{\n", indent));
-                stmt_block!(stmt);
-                try!(write!(fmt, "{}}}\n", indent));
-
-                if catch_block.len() > 0 {
-                    try!(write!(fmt, "{}catch ({}) {{\n", catch_var, indent));
-                    stmt_block!(catch_block);
-                    try!(write!(fmt, "{}}}\n", indent));
-                }
+impl Display for Stmt {
+    fn fmt(&self, mut fmt: &mut Formatter) -> Result<(), Error> {
+        self.fmt_helper(&mut fmt, 0)
+    }
+}
+
+// A whole source file. Only a module can import from and export to other modules, and its code is
+// always strict.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Program {
+    Script(Vec<Stmt>),
+    Module(Vec<Stmt>),
+}
+
+impl Program {
+    pub fn stmts(&self) -> &[Stmt] {
+        match *self {
+            Program::Script(ref stmts) | Program::Module(ref stmts) => stmts,
+        }
+    }
 
-                if finally_block.len() > 0 {
-                    try!(write!(fmt, "{}finally {{\n", indent));
-                    stmt_block!(finally_block);
-                    try!(write!(fmt, "{}}}\n", indent));
+    pub fn is_module(&self) -> bool {
+        matches!(*self, Program::Module(_))
+    }
+
+    /// The sources of the modules that this one imports from or re-exports, in the order they're
+    /// first named. These are the edges out of this module in the module graph.
+    pub fn requested_modules(&self) -> Vec<&str> {
+        fn add<'a>(s: &'a Stmt, sources: &mut Vec<&'a str>) {
+            let source = match *s {
+                Stmt::Import(_, ref source) | Stmt::ExportAll(_, ref source) |
+                Stmt::ExportNames(_, Some(ref source)) => source,
+                Stmt::Seq(ref s1, ref s2) => {
+                    add(s1, sources);
+                    return add(s2, sources);
                 }
+                Stmt::Commented(_, ref s) | Stmt::Spanned(_, ref s) => return add(s, sources),
+                _ => return,
+            };
 
-                Ok(())
-            }
-            Stmt::VarDecl(ref s) => {
-                write!(fmt, "var {};", s)
-            }
-            Stmt::While(ref exp, ref stmt) => {
-                try!(write!(fmt, "{}while ({}) {{\n", indent, exp));
-                stmt_block!(stmt);
-                write!(fmt, "{}}}\n", indent)
+            if !sources.contains(&&source[..]) {
+                sources.push(source);
             }
         }
+
+        let mut sources = Vec::new();
+
+        for s in self.stmts() {
+            add(s, &mut sources);
+        }
+
+        sources
     }
 }
 
-impl Display for Stmt {
-    fn fmt(&self, mut fmt: &mut Formatter) -> Result<(), Error> {
-        self.fmt_helper(&mut fmt, 0)
+impl Display for Program {
+    fn fmt(&self, fmt: &mut Formatter) -> Result<(), Error> {
+        let mut gen = CodeGen::new(CodeGenOptions::default());
+        gen.stmts(self.stmts());
+        write!(fmt, "{}", gen.finish())
     }
 }
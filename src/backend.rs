@@ -3,7 +3,9 @@ use std::rc::Rc;
 
 use alloc_box::AllocBox;
 use gc_error::GcError;
+use js_error::{self, JsError};
 use types::binding::Binding;
+use types::js_fn::JsFnStruct;
 use types::js_var::{JsPtrEnum, JsVar};
 
 pub trait Backend {
@@ -11,4 +13,12 @@ pub trait Backend {
     fn load(&mut self, bnd: &Binding) -> Result<(JsVar, Option<JsPtrEnum>), GcError>;
     fn store(&mut self, var: JsVar, ptr: Option<JsPtrEnum>) -> Result<(), GcError>;
     fn get_alloc_box(&self) -> Rc<RefCell<AllocBox>>;
+    // Runs a JavaScript function, for code here that has to call back into the interpreter, such
+    // as the getter and setter of a `JsAccessor`. A backend that can't run functions leaves this
+    // unimplemented.
+    #[allow(unused_variables)]
+    fn call(&mut self, func: &JsFnStruct, this: Option<(JsVar, JsPtrEnum)>,
+            args: Vec<(JsVar, Option<JsPtrEnum>)>) -> js_error::Result<(JsVar, Option<JsPtrEnum>)> {
+        Err(JsError::unimplemented("calling functions from the backend"))
+    }
 }
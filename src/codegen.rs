@@ -0,0 +1,1726 @@
+// Turns an AST back into JavaScript source. The `Display` impls for `Exp` and `Stmt` use a
+// `CodeGen` with the default options; build one directly to control the layout of the output.
+
+use std::cmp;
+use std::iter;
+
+use unicode_id_start::{is_id_continue, is_id_start};
+
+use ast::{ArrowBody, BinOp, Class, ClassKey, Comment, DeclKind, Exp, FnKind, ImportSpec,
+          MemberKind, Pattern, Precedence, Prop, PropKey, Stmt, Template};
+use lexer::is_line_terminator;
+use source_map::{Mapping, SourceMap};
+use span::Span;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Semicolons {
+    // Terminate every statement with a semicolon.
+    Always,
+    // Leave out semicolons that automatic semicolon insertion makes redundant, guarding any
+    // statement that would otherwise continue the previous one with a leading `;`.
+    AsNeeded,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Quotes {
+    Double,
+    Single,
+    // Whichever quote needs fewer escapes in each string, preferring double quotes.
+    Auto,
+}
+
+#[derive(Clone, Debug)]
+pub struct CodeGenOptions {
+    // Number of spaces per indentation level; ignored when indenting with tabs.
+    pub indent_width: usize,
+    pub use_tabs: bool,
+    // Lists (arguments, parameters and array elements) that would run past this column are
+    // broken up with one item per line.
+    pub max_width: usize,
+    pub semicolons: Semicolons,
+    pub quotes: Quotes,
+    // Strip all optional whitespace and comments, putting the whole program on a single line. The
+    // layout options above have no effect on minified output.
+    pub minify: bool,
+}
+
+impl CodeGenOptions {
+    pub fn minified() -> CodeGenOptions {
+        CodeGenOptions { minify: true, ..CodeGenOptions::default() }
+    }
+}
+
+impl Default for CodeGenOptions {
+    fn default() -> CodeGenOptions {
+        CodeGenOptions {
+            indent_width: 2,
+            use_tabs: false,
+            max_width: 80,
+            semicolons: Semicolons::Always,
+            quotes: Quotes::Double,
+            minify: false,
+        }
+    }
+}
+
+pub struct CodeGen {
+    options: CodeGenOptions,
+    out: String,
+    indent_level: usize,
+    line: usize,
+    column: usize,
+    // The current column in UTF-16 code units, which is what source maps count in.
+    utf16_column: usize,
+    // Spans of the nodes being generated, innermost last, and the mappings from the output back
+    // to them.
+    spans: Vec<Span>,
+    mappings: Vec<Mapping>,
+    // The text of the original files, indexed by the `file` of the spans, and how far into each
+    // one the tokens written so far were found. Tokens come out in the order they were written
+    // in, so each is looked for after the last one.
+    sources: Vec<String>,
+    source_offsets: Vec<usize>,
+    // The last token written, if nothing (not even whitespace) has been written since; used to
+    // tell whether the next token needs to be separated from it.
+    prev_token: String,
+    at_line_start: bool,
+    // Where the line ended, if nothing has been written since the line break, so that a comment
+    // can still be put at the end of it
+    line_end: Option<(usize, usize)>,
+    // Whether the next statement is the first in its block, which never has a blank line before it
+    at_block_start: bool,
+    pending_semi: bool,
+    // Set while generating the initialization of a for loop, where an `in` operator that isn't
+    // inside parentheses would be read as a for-in loop.
+    no_in: bool,
+    // Set while generating text just to measure it, so nested lists don't measure themselves
+    // again.
+    measuring: bool,
+}
+
+impl CodeGen {
+    pub fn new(options: CodeGenOptions) -> CodeGen {
+        CodeGen {
+            options: options,
+            out: String::new(),
+            indent_level: 0,
+            line: 0,
+            column: 0,
+            utf16_column: 0,
+            spans: Vec::new(),
+            mappings: Vec::new(),
+            sources: Vec::new(),
+            source_offsets: Vec::new(),
+            prev_token: String::new(),
+            at_line_start: true,
+            line_end: None,
+            at_block_start: true,
+            pending_semi: false,
+            no_in: false,
+            measuring: false,
+        }
+    }
+
+    /// Starts the output at the given indentation level rather than at the left margin.
+    pub fn indented(mut self, level: usize) -> CodeGen {
+        self.indent_level = level;
+        self
+    }
+
+    /// Gives the text of the original files, so that the source map can point at each token
+    /// where it was written, rather than at the start of the node it's part of.
+    pub fn with_sources(mut self, sources: Vec<String>) -> CodeGen {
+        self.source_offsets = vec![0; sources.len()];
+        self.sources = sources;
+        self
+    }
+
+    /// The generated source.
+    pub fn finish(self) -> String {
+        self.out
+    }
+
+    /// The generated source, along with a source map from it back to the spans in the AST. Set
+    /// the `file` and `sources` of the map before serializing it.
+    pub fn finish_with_source_map(self) -> (String, SourceMap) {
+        (self.out, SourceMap::new(self.mappings))
+    }
+
+    pub fn exp(&mut self, e: &Exp) {
+        match *e {
+            Exp::Array(ref elems) => self.array(elems, |gen, elem| gen.assign_exp(elem)),
+            Exp::Arrow(kind, ref params, ref body) => {
+                if kind.is_async() {
+                    self.token("async");
+                    self.space();
+                }
+
+                // A single parameter that's just a name doesn't need parentheses
+                match params.first() {
+                    Some(&Pattern::Var(ref v)) if params.len() == 1 => self.token(v),
+                    _ => self.list("(", params, ")", |gen, param| gen.pattern(param)),
+                }
+
+                self.space();
+                self.token("=>");
+                self.space();
+
+                match *body {
+                    ArrowBody::Exp(ref e) => self.arrow_body(e),
+                    ArrowBody::Block(ref block) => self.block(block),
+                }
+            }
+            Exp::AssignExp(ref o, ref lhs, ref rhs) => {
+                // Assignment is right-associative, e.g. a = b = c
+                self.pattern(lhs);
+                self.space();
+                self.token(&o.to_string());
+                self.space();
+                self.assign_exp(rhs);
+            }
+            Exp::BinExp(_, BinOp::In, _) if self.no_in => self.grouped(e, true),
+            Exp::BinExp(ref e1, ref o, ref e2) => {
+                let prec = e.precedence();
+                let left_prec = e1.precedence();
+                let right_prec = e2.precedence();
+
+                let (group_left, group_right) = if *o == BinOp::Exponent {
+                    // `**` is right-associative, and its left operand can't be a unary
+                    // expression, e.g. (-2) ** 2 __OR__ (2 ** 3) ** 2
+                    (prec >= left_prec || left_prec == Precedence::Sign, prec > right_prec)
+                } else if *o == BinOp::Coalesce {
+                    // `??` has a lower precedence than `&&` and `||`, but they still can't be
+                    // mixed without grouping, e.g. (a || b) ?? c
+                    let and_or = |e: &Exp| {
+                        matches!(*e.node(),
+                                 Exp::BinExp(_, BinOp::And, _) | Exp::BinExp(_, BinOp::Or, _))
+                    };
+
+                    (prec > left_prec || and_or(e1), prec >= right_prec || and_or(e2))
+                } else {
+                    // Put grouping parentheses if the left subexpression has a lower-precedence
+                    // operator, e.g. (1 + 2) * 3; since the other operators are left-associative,
+                    // the right subexpression also needs them if the precedence is the same,
+                    // e.g. 1 - (2 + 3)
+                    (prec > left_prec, prec >= right_prec)
+                };
+
+                self.grouped(e1, group_left);
+                self.space();
+                self.token(&o.to_string());
+                self.space();
+                self.grouped(e2, group_right);
+            }
+            Exp::BitNot(ref e) => self.unary("~", e, Precedence::Sign),
+            Exp::Bool(b) => self.token(if b { "true" } else { "false" }),
+            Exp::Call(ref func, ref args) => {
+                self.grouped(func, func.precedence() < Precedence::Const);
+                self.list("(", args, ")", |gen, arg| gen.assign_exp(arg));
+            }
+            Exp::Chain(ref e) => self.exp(e),
+            Exp::Class(ref class) => self.class(class),
+            Exp::Comma(ref e1, ref e2) => {
+                self.exp(e1);
+                self.token(",");
+                self.space();
+                self.grouped(e2, e2.precedence() <= Precedence::Comma);
+            }
+            Exp::Commented(_, ref e) if self.options.minify => self.exp(e),
+            Exp::Commented(ref comments, ref e) => {
+                for comment in &comments.leading {
+                    self.comment(comment);
+
+                    if !self.at_line_start {
+                        self.space();
+                    }
+                }
+
+                self.exp(e);
+
+                for comment in &comments.trailing {
+                    self.comment(comment);
+                }
+            }
+            Exp::Conditional(ref test, ref e1, ref e2) => {
+                self.grouped(test, test.precedence() <= Precedence::Conditional);
+                self.space();
+                self.token("?");
+                self.space();
+                self.assign_exp(e1);
+                self.space();
+                self.token(":");
+                self.space();
+                self.assign_exp(e2);
+            }
+            Exp::Defun(kind, ref name, ref params, ref body) => {
+                self.function(kind, name.as_ref(), params, body)
+            }
+            // There's nothing to print for source that couldn't be parsed, but the gap is left
+            // visible.
+            Exp::Error => self.token("/* error */"),
+            Exp::Float(f) => self.token(&number_to_string(f)),
+            Exp::InstanceVar(ref obj, ref name) => {
+                self.member_obj(obj);
+
+                // The `?.` of an optional member access already separates the name
+                if !matches!(*obj.node(), Exp::Optional(_)) {
+                    self.token(".");
+                }
+
+                self.token(name);
+            }
+            Exp::KeyAccessor(ref obj, ref key) => {
+                self.member_obj(obj);
+                self.token("[");
+                self.exp(key);
+                self.token("]");
+            }
+            Exp::LogNot(ref e) => self.unary("!", e, Precedence::Sign),
+            Exp::Neg(ref e) => self.unary("-", e, Precedence::Sign),
+            Exp::NewObject(ref func, ref args) => {
+                self.token("new");
+                self.space();
+
+                // A call in the constructor expression would otherwise be taken as the argument
+                // list of the `new`, e.g. new (f())()
+                self.grouped(func, func.precedence() < Precedence::Const || is_call_chain(func));
+                self.list("(", args, ")", |gen, arg| gen.assign_exp(arg));
+            }
+            Exp::Null => self.token("null"),
+            Exp::Object(ref properties) => {
+                if properties.is_empty() {
+                    return self.token("{}");
+                }
+
+                self.token("{");
+                self.indent_level += 1;
+
+                for (i, prop) in properties.iter().enumerate() {
+                    if i != 0 {
+                        self.token(",");
+                    }
+
+                    self.newline();
+
+                    match *prop {
+                        Prop::Value(ref name, ref e) => {
+                            self.prop_name(name);
+                            self.token(":");
+                            self.space();
+                            self.assign_exp(e);
+                        }
+                        Prop::Computed(ref key, ref e) => {
+                            self.token("[");
+                            self.assign_exp(key);
+                            self.token("]");
+                            self.token(":");
+                            self.space();
+                            self.assign_exp(e);
+                        }
+                        Prop::Shorthand(ref name) => self.token(name),
+                        Prop::Method(kind, ref key, ref params, ref body) => {
+                            self.method_prefix(kind);
+                            self.prop_key(key);
+                            self.list("(", params, ")", |gen, param| gen.pattern(param));
+                            self.space();
+                            self.block(body);
+                        }
+                        Prop::Get(ref key, ref body) => {
+                            self.token("get");
+                            self.space();
+                            self.prop_key(key);
+                            self.token("()");
+                            self.space();
+                            self.block(body);
+                        }
+                        Prop::Set(ref key, ref param, ref body) => {
+                            self.token("set");
+                            self.space();
+                            self.prop_key(key);
+                            self.token("(");
+                            self.pattern(param);
+                            self.token(")");
+                            self.space();
+                            self.block(body);
+                        }
+                        Prop::Spread(ref e) => {
+                            self.token("...");
+                            self.assign_exp(e);
+                        }
+                    }
+                }
+
+                self.indent_level -= 1;
+                self.newline();
+                self.token("}");
+            }
+            Exp::Optional(ref e) => {
+                self.grouped(e, e.precedence() < Precedence::Const);
+                self.token("?.");
+            }
+            Exp::Pos(ref e) => self.unary("+", e, Precedence::Sign),
+            // There can't be a line break before a postfix operator
+            Exp::PostDec(ref e) => {
+                self.grouped(e, e.precedence() < Precedence::Inc || self.ends_with_line_break(e));
+                self.token("--");
+            }
+            Exp::PostInc(ref e) => {
+                self.grouped(e, e.precedence() < Precedence::Inc || self.ends_with_line_break(e));
+                self.token("++");
+            }
+            Exp::PreDec(ref e) => self.unary("--", e, Precedence::Inc),
+            Exp::PreInc(ref e) => self.unary("++", e, Precedence::Inc),
+            // An empty pattern would be read as the start of a comment
+            Exp::RegExp(ref pattern, ref flags) if pattern.is_empty() => {
+                self.token(&format!("/(?:)/{}", flags))
+            }
+            Exp::RegExp(ref pattern, ref flags) => self.token(&format!("/{}/{}", pattern, flags)),
+            Exp::Spanned(ref span, ref e) => {
+                self.spans.push(*span);
+                self.exp(e);
+                self.spans.pop();
+            }
+            Exp::Await(ref arg) | Exp::Delete(ref arg) | Exp::TypeOf(ref arg) |
+            Exp::Void(ref arg) => {
+                self.token(match *e {
+                    Exp::Await(_) => "await",
+                    Exp::Delete(_) => "delete",
+                    Exp::TypeOf(_) => "typeof",
+                    _ => "void",
+                });
+                self.space();
+                self.grouped(arg, arg.precedence() < Precedence::Sign);
+            }
+            Exp::Super => self.token("super"),
+            Exp::This => self.token("this"),
+            Exp::Spread(ref e) => {
+                self.token("...");
+                self.assign_exp(e);
+            }
+            Exp::TaggedTemplate(ref tag, ref template) => {
+                self.grouped(tag, tag.precedence() < Precedence::Const);
+                self.template(template);
+            }
+            Exp::Template(ref template) => self.template(template),
+            Exp::Str(ref s) => {
+                let quote = match self.options.quotes {
+                    Quotes::Double => '"',
+                    Quotes::Single => '\'',
+                    Quotes::Auto => {
+                        if s.matches('"').count() > s.matches('\'').count() { '\'' } else { '"' }
+                    }
+                };
+
+                self.token(&escape_str(s, quote))
+            }
+            Exp::Undefined => self.token("undefined"),
+            Exp::Var(ref v) => self.token(v),
+            Exp::Yield(ref arg) => {
+                self.token("yield");
+
+                if let Some(ref e) = *arg {
+                    self.space();
+                    self.restricted_exp(e, Precedence::Assign);
+                }
+            }
+            Exp::YieldAll(ref e) => {
+                self.token("yield*");
+                self.space();
+                self.assign_exp(e);
+            }
+        }
+    }
+
+    pub fn stmt(&mut self, s: &Stmt) {
+        match *s {
+            Stmt::Assign(ref p, ref exp) => {
+                // An object pattern would start a block, but it can't be grouped on its own, so
+                // the whole assignment is
+                let group = matches!(*p, Pattern::Object(..));
+
+                if group {
+                    self.token("(");
+                }
+
+                match *p {
+                    Pattern::Exp(ref e) => self.stmt_exp(e),
+                    ref p => self.pattern(p),
+                }
+
+                self.space();
+                self.token("=");
+                self.space();
+                self.assign_exp(exp);
+
+                if group {
+                    self.token(")");
+                }
+
+                self.semi();
+                self.newline();
+            }
+            Stmt::BareExp(ref exp) => {
+                self.stmt_exp(exp);
+                self.semi();
+                self.newline();
+            }
+            Stmt::Break(ref label) | Stmt::Continue(ref label) => {
+                self.token(if let Stmt::Break(_) = *s { "break" } else { "continue" });
+
+                if let Some(ref label) = *label {
+                    self.space();
+                    self.token(label);
+                }
+
+                self.semi();
+                self.newline();
+            }
+            Stmt::Block(ref block) => {
+                self.block(block);
+                self.newline();
+            }
+            Stmt::Commented(_, ref s) if self.options.minify => {
+                if !matches!(*s.node(), Stmt::Empty) {
+                    self.stmt(s);
+                }
+            }
+            Stmt::Commented(ref comments, ref s) => {
+                let mut at_block_start = self.at_block_start;
+
+                for comment in &comments.leading {
+                    self.line_breaks(comment.lines_before, at_block_start);
+                    self.comment(comment);
+                    at_block_start = false;
+                }
+
+                // An empty statement with comments is only there for the comments
+                if matches!(*s.node(), Stmt::Empty) {
+                    self.line_breaks(1, at_block_start);
+                } else {
+                    self.line_breaks(comments.lines_before, at_block_start);
+                    self.stmt(s);
+                }
+
+                for comment in &comments.trailing {
+                    // The statement has just ended its line, which the comment goes back to
+                    if comment.lines_before == 0 {
+                        self.reopen_line();
+                    } else {
+                        self.line_breaks(comment.lines_before, false);
+                    }
+
+                    self.comment(comment);
+                }
+
+                self.line_breaks(1, false);
+            }
+            Stmt::ClassDecl(ref class) => {
+                self.class(class);
+                self.newline();
+            }
+            Stmt::Decl(ref kind, ref decls) => {
+                self.decl(kind, decls);
+                self.semi();
+                self.newline();
+            }
+            Stmt::DoWhile(ref block, ref cond) => {
+                self.token("do");
+                self.space();
+                self.block(block);
+                self.space();
+                self.token("while");
+                self.space();
+                self.token("(");
+                self.exp(cond);
+                self.token(")");
+                self.semi();
+                self.newline();
+            }
+            Stmt::Empty => {
+                // The semicolon is the whole statement, so it can never be left out
+                self.token(";");
+                self.newline();
+            }
+            Stmt::Error => {
+                self.token("/* error */");
+                self.newline();
+            }
+            Stmt::Export(ref s) => {
+                self.token("export");
+                self.space();
+                self.stmt(s);
+            }
+            Stmt::ExportAll(ref name, ref source) => {
+                self.token("export");
+                self.space();
+                self.token("*");
+
+                if let Some(ref name) = *name {
+                    self.space();
+                    self.token("as");
+                    self.space();
+                    self.module_name(name);
+                }
+
+                self.from(source);
+                self.semi();
+                self.newline();
+            }
+            Stmt::ExportDefault(ref e) => {
+                self.token("export");
+                self.space();
+                self.token("default");
+                self.space();
+
+                // A function or class is printed as a declaration, which doesn't end with a
+                // semicolon, so any other expression that starts with one has to be grouped
+                let is_decl = |e: &Exp| matches!(*e, Exp::Class(_) | Exp::Defun(..));
+                let decl = is_decl(e.node());
+                self.grouped(e, (!decl && starts_with(e, &is_decl)) ||
+                                e.precedence() < Precedence::Assign);
+
+                if !decl {
+                    self.semi();
+                }
+
+                self.newline();
+            }
+            Stmt::FnDecl(kind, ref name, ref params, ref body) => {
+                self.function(kind, Some(name), params, body);
+                self.newline();
+            }
+            Stmt::ExportNames(ref names, ref source) => {
+                self.token("export");
+                self.space();
+                self.list("{", names, "}", |gen, &(ref local, ref exported)| {
+                    gen.module_name(local);
+
+                    if exported != local {
+                        gen.space();
+                        gen.token("as");
+                        gen.space();
+                        gen.module_name(exported);
+                    }
+                });
+
+                if let Some(ref source) = *source {
+                    self.from(source);
+                }
+
+                self.semi();
+                self.newline();
+            }
+            Stmt::If(..) => {
+                self.if_stmt(s);
+                self.newline();
+            }
+            Stmt::For(ref init, ref cond, ref step, ref block) => {
+                // Only expressions and declarations can go in the loop header; any other kind of
+                // statement is run just before the loop instead.
+                let header_init = match *init.node() {
+                    Stmt::Assign(..) | Stmt::BareExp(_) | Stmt::Decl(..) | Stmt::Empty => true,
+                    _ => false,
+                };
+
+                if !header_init {
+                    self.stmt(init);
+                }
+
+                self.token("for");
+                self.space();
+                self.token("(");
+                self.no_in = true;
+
+                match *init.node() {
+                    Stmt::Assign(ref p, ref exp) => self.assign(p, exp),
+                    Stmt::BareExp(ref exp) => self.exp(exp),
+                    Stmt::Decl(ref kind, ref decls) => self.decl(kind, decls),
+                    _ => (),
+                }
+
+                self.no_in = false;
+
+                self.token(";");
+                self.space();
+                self.exp(cond);
+                self.token(";");
+
+                // A missing step is represented as `undefined`
+                if !matches!(*step.node(), Exp::Undefined) {
+                    self.space();
+                    self.exp(step);
+                }
+
+                self.token(")");
+                self.space();
+                self.block(block);
+                self.newline();
+            }
+            Stmt::ForIn(ref left, ref obj, ref block) => {
+                self.for_each_head(left, "in");
+                self.exp(obj);
+                self.token(")");
+                self.space();
+                self.block(block);
+                self.newline();
+            }
+            Stmt::ForOf(ref left, ref iterable, ref block) => {
+                self.for_each_head(left, "of");
+                self.assign_exp(iterable);
+                self.token(")");
+                self.space();
+                self.block(block);
+                self.newline();
+            }
+            Stmt::Import(ref specs, ref source) => {
+                self.token("import");
+                self.space();
+
+                // The default import has to come first, and a namespace import can't be combined
+                // with named imports
+                let mut named = Vec::new();
+                let mut first = true;
+
+                for spec in specs {
+                    if let ImportSpec::Named(ref imported, ref local) = *spec {
+                        named.push((imported, local));
+                        continue;
+                    }
+
+                    if !first {
+                        self.token(",");
+                        self.space();
+                    }
+
+                    first = false;
+
+                    match *spec {
+                        ImportSpec::Default(ref local) => self.token(local),
+                        ImportSpec::Namespace(ref local) => {
+                            self.token("*");
+                            self.space();
+                            self.token("as");
+                            self.space();
+                            self.token(local);
+                        }
+                        ImportSpec::Named(..) => unreachable!(),
+                    }
+                }
+
+                if !named.is_empty() {
+                    if !first {
+                        self.token(",");
+                        self.space();
+                    }
+
+                    self.list("{", &named, "}", |gen, &(imported, local)| {
+                        if imported != local {
+                            gen.module_name(imported);
+                            gen.space();
+                            gen.token("as");
+                            gen.space();
+                        }
+
+                        gen.token(local);
+                    });
+                }
+
+                if specs.is_empty() {
+                    self.exp(&Exp::Str(source.clone()));
+                } else {
+                    self.from(source);
+                }
+
+                self.semi();
+                self.newline();
+            }
+            Stmt::Labeled(ref label, ref s) => {
+                self.token(label);
+                self.token(":");
+                self.space();
+                self.stmt(s);
+            }
+            Stmt::Ret(ref e) => {
+                self.token("return");
+
+                // `return;` is represented as returning `undefined`
+                if !matches!(*e.node(), Exp::Undefined) {
+                    self.space();
+                    self.restricted_exp(e, Precedence::Comma);
+                }
+
+                self.semi();
+                self.newline();
+            }
+            Stmt::Seq(ref s1, ref s2) => {
+                self.stmt(s1);
+                self.stmt(s2);
+            }
+            Stmt::Spanned(ref span, ref s) => {
+                self.spans.push(*span);
+                self.stmt(s);
+                self.spans.pop();
+            }
+            Stmt::Switch(ref e, ref cases) => {
+                self.token("switch");
+                self.space();
+                self.token("(");
+                self.exp(e);
+                self.token(")");
+                self.space();
+                self.token("{");
+                self.newline();
+                self.indent_level += 1;
+
+                for &(ref test, ref body) in cases {
+                    match *test {
+                        Some(ref test) => {
+                            self.token("case");
+                            self.space();
+                            self.exp(test);
+                        }
+                        None => self.token("default"),
+                    }
+
+                    self.token(":");
+                    self.newline();
+                    self.indent_level += 1;
+                    self.stmts(body);
+                    self.indent_level -= 1;
+                }
+
+                self.indent_level -= 1;
+                self.token("}");
+                self.newline();
+            }
+            Stmt::Throw(ref e) => {
+                self.token("throw");
+                self.space();
+                self.restricted_exp(e, Precedence::Comma);
+                self.semi();
+                self.newline();
+            }
+            Stmt::Try(ref stmt, ref catch_var, ref catch_block, ref finally_block) => {
+                self.token("try");
+                self.space();
+                self.block(stmt);
+
+                // A try statement needs at least one of the two clauses, so an empty catch block
+                // is still printed when there is no finally block.
+                if catch_var.is_some() || !catch_block.is_empty() || finally_block.is_empty() {
+                    self.space();
+                    self.token("catch");
+                    self.space();
+
+                    if let Some(ref catch_var) = *catch_var {
+                        self.token("(");
+                        self.pattern(catch_var);
+                        self.token(")");
+                        self.space();
+                    }
+
+                    self.block(catch_block);
+                }
+
+                if !finally_block.is_empty() {
+                    self.space();
+                    self.token("finally");
+                    self.space();
+                    self.block(finally_block);
+                }
+
+                self.newline();
+            }
+            Stmt::While(ref exp, ref stmt) => {
+                self.token("while");
+                self.space();
+                self.token("(");
+                self.exp(exp);
+                self.token(")");
+                self.space();
+                self.block(stmt);
+                self.newline();
+            }
+        }
+    }
+
+    /// Prints a list of statements, e.g. the body of a script. Empty statements are no-ops in a
+    /// statement list, so they're left out unless they have comments.
+    pub fn stmts(&mut self, block: &[Stmt]) {
+        self.at_block_start = true;
+
+        for s in block {
+            if !matches!(*s.node(), Stmt::Empty) || s.comments().is_some() {
+                self.stmt(s);
+                self.at_block_start = false;
+            }
+        }
+    }
+
+    // Prints a braced block, leaving the output just after the closing brace.
+    fn block(&mut self, block: &[Stmt]) {
+        self.token("{");
+        self.newline();
+        self.indent_level += 1;
+        self.stmts(block);
+        self.indent_level -= 1;
+        self.token("}");
+    }
+
+    // Prints an if statement without indentation or a trailing newline, so that `else if` chains
+    // can be printed on the same line as the preceding closing brace.
+    fn if_stmt(&mut self, s: &Stmt) {
+        let (e, s, els) = match *s.node() {
+            Stmt::If(ref e, ref s, ref els) => (e, s, els),
+            _ => unreachable!(),
+        };
+
+        self.token("if");
+        self.space();
+        self.token("(");
+        self.exp(e);
+        self.token(")");
+        self.space();
+        self.block(s);
+
+        if els.is_empty() {
+            return;
+        }
+
+        self.space();
+        self.token("else");
+        self.space();
+
+        if els.len() == 1 && matches!(*els[0].node(), Stmt::If(..)) {
+            self.if_stmt(&els[0]);
+        } else {
+            self.block(els);
+        }
+    }
+
+    // Prints the start of a for-in or for-of loop header, up to and including the keyword before
+    // the object being iterated over.
+    fn for_each_head(&mut self, left: &Stmt, keyword: &str) {
+        self.token("for");
+        self.space();
+        self.token("(");
+
+        match *left.node() {
+            Stmt::Decl(ref kind, ref decls) => self.decl(kind, decls),
+            Stmt::BareExp(ref e) => self.grouped(e, e.precedence() < Precedence::Const),
+            ref s => panic!("not the left side of a for-{} loop: {:?}", keyword, s),
+        }
+
+        self.space();
+        self.token(keyword);
+        self.space();
+    }
+
+    fn decl(&mut self, kind: &DeclKind, decls: &[(Pattern, Option<Exp>)]) {
+        self.token(&kind.to_string());
+        self.space();
+
+        for (i, &(ref p, ref init)) in decls.iter().enumerate() {
+            if i != 0 {
+                self.token(",");
+                self.space();
+            }
+
+            self.pattern(p);
+
+            if let Some(ref e) = *init {
+                self.space();
+                self.token("=");
+                self.space();
+                self.assign_exp(e);
+            }
+        }
+    }
+
+    // Prints an expression at the start of a statement. An expression that would start with `{`,
+    // `function` or `let [` has to be wrapped in parentheses, or it would be read back as a block
+    // or a declaration.
+    fn stmt_exp(&mut self, e: &Exp) {
+        self.grouped(e, starts_ambiguously(e));
+    }
+
+    // Prints a class with each member on its own line, leaving the output just after the closing
+    // brace.
+    fn class(&mut self, class: &Class) {
+        self.token("class");
+
+        if let Some(ref name) = class.name {
+            self.space();
+            self.token(name);
+        }
+
+        if let Some(ref base) = class.extends {
+            self.space();
+            self.token("extends");
+            self.space();
+            self.grouped(base, base.precedence() < Precedence::Const);
+        }
+
+        self.space();
+
+        if class.members.is_empty() {
+            return self.token("{}");
+        }
+
+        self.token("{");
+        self.newline();
+        self.indent_level += 1;
+
+        for member in &class.members {
+            if member.is_static {
+                self.token("static");
+                self.space();
+            }
+
+            match member.kind {
+                MemberKind::Method(kind, ref params, ref body) => {
+                    self.method_prefix(kind);
+                    self.class_key(&member.key);
+                    self.list("(", params, ")", |gen, param| gen.pattern(param));
+                    self.space();
+                    self.block(body);
+                }
+                MemberKind::Get(ref body) => {
+                    self.token("get");
+                    self.space();
+                    self.class_key(&member.key);
+                    self.token("()");
+                    self.space();
+                    self.block(body);
+                }
+                MemberKind::Set(ref param, ref body) => {
+                    self.token("set");
+                    self.space();
+                    self.class_key(&member.key);
+                    self.token("(");
+                    self.pattern(param);
+                    self.token(")");
+                    self.space();
+                    self.block(body);
+                }
+                // The semicolon is always printed, since a field followed by a computed key
+                // would otherwise run together, e.g. `a = b\n[c]() {}`
+                MemberKind::Field(ref value) => {
+                    self.class_key(&member.key);
+
+                    if let Some(ref e) = *value {
+                        self.space();
+                        self.token("=");
+                        self.space();
+                        self.assign_exp(e);
+                    }
+
+                    self.token(";");
+                }
+            }
+
+            self.newline();
+        }
+
+        self.indent_level -= 1;
+        self.token("}");
+    }
+
+    fn class_key(&mut self, key: &ClassKey) {
+        match *key {
+            ClassKey::Name(ref name) => self.prop_name(name),
+            ClassKey::Private(ref name) => self.token(&format!("#{}", name)),
+            ClassKey::Computed(ref e) => {
+                self.token("[");
+                self.assign_exp(e);
+                self.token("]");
+            }
+        }
+    }
+
+    fn prop_key(&mut self, key: &PropKey) {
+        match *key {
+            PropKey::Name(ref name) => self.prop_name(name),
+            PropKey::Computed(ref e) => {
+                self.token("[");
+                self.assign_exp(e);
+                self.token("]");
+            }
+        }
+    }
+
+    // Prints the `async` and `*` that come before the name of an async or generator method.
+    fn method_prefix(&mut self, kind: FnKind) {
+        if kind.is_async() {
+            self.token("async");
+            self.space();
+        }
+
+        if kind.is_generator() {
+            self.token("*");
+        }
+    }
+
+    // Prints the name of an import or export, which has to be quoted if it isn't an identifier.
+    fn module_name(&mut self, name: &str) {
+        if is_identifier_name(name) {
+            self.token(name);
+        } else {
+            self.exp(&Exp::Str(String::from(name)));
+        }
+    }
+
+    // Prints the `from "source"` of an import or a re-export.
+    fn from(&mut self, source: &str) {
+        self.space();
+        self.token("from");
+        self.space();
+        self.exp(&Exp::Str(String::from(source)));
+    }
+
+    // Prints a plain `=` assignment.
+    fn assign(&mut self, p: &Pattern, e: &Exp) {
+        self.pattern(p);
+        self.space();
+        self.token("=");
+        self.space();
+        self.assign_exp(e);
+    }
+
+    // Prints the expression body of an arrow function, which can't be a comma expression or start
+    // with `{`, since that would be read as a block body.
+    fn arrow_body(&mut self, e: &Exp) {
+        let group = e.precedence() < Precedence::Assign ||
+                    starts_with(e, &|e| matches!(*e, Exp::Object(_)));
+
+        self.grouped(e, group);
+    }
+
+    pub fn pattern(&mut self, p: &Pattern) {
+        match *p {
+            Pattern::Var(ref v) => self.token(v),
+            Pattern::Exp(ref e) => self.grouped(e, e.precedence() < Precedence::Const),
+            Pattern::Default(ref p, ref e) => {
+                self.pattern(p);
+                self.space();
+                self.token("=");
+                self.space();
+                self.assign_exp(e);
+            }
+            Pattern::Rest(ref p) => {
+                self.token("...");
+                self.pattern(p);
+            }
+            Pattern::Array(ref elems) => self.array(elems, |gen, p| gen.pattern(p)),
+            Pattern::Object(ref props, ref rest) => {
+                let mut items: Vec<_> = props.iter()
+                    .map(|&(ref key, ref p)| (Some(key), p))
+                    .collect();
+                items.extend(rest.iter().map(|rest| (None, &**rest)));
+
+                self.list("{", &items, "}", |gen, &(key, p)| {
+                    let key = match key {
+                        Some(key) => key,
+                        None => {
+                            gen.token("...");
+                            return gen.pattern(p);
+                        }
+                    };
+
+                    // `{x}` and `{x = 1}` are short for `{x: x}` and `{x: x = 1}`
+                    let target = match *p {
+                        Pattern::Default(ref p, _) => p,
+                        ref p => p,
+                    };
+
+                    let shorthand = match *target {
+                        Pattern::Var(ref v) => v == key,
+                        Pattern::Exp(ref e) => matches!(*e.node(), Exp::Var(ref v) if v == key),
+                        _ => false,
+                    };
+
+                    if !shorthand {
+                        gen.prop_name(key);
+                        gen.token(":");
+                        gen.space();
+                    }
+
+                    gen.pattern(p);
+                });
+            }
+        }
+    }
+
+    fn grouped(&mut self, e: &Exp, group: bool) {
+        if group {
+            let no_in = self.no_in;
+            self.no_in = false;
+            self.token("(");
+            self.exp(e);
+            self.token(")");
+            self.no_in = no_in;
+        } else {
+            self.exp(e);
+        }
+    }
+
+    // Prints an expression where a single value is expected, such as an argument or an array
+    // element, so a comma expression has to be grouped.
+    fn assign_exp(&mut self, e: &Exp) {
+        self.grouped(e, e.precedence() < Precedence::Assign);
+    }
+
+    // Prints a function expression or declaration; `name` is left out for anonymous functions.
+    fn function(&mut self, kind: FnKind, name: Option<&String>, params: &[Pattern], body: &[Stmt]) {
+        if kind.is_async() {
+            self.token("async");
+            self.space();
+        }
+
+        self.token(if kind.is_generator() { "function*" } else { "function" });
+
+        if let Some(name) = name {
+            self.space();
+            self.token(name);
+        }
+
+        self.list("(", params, ")", |gen, param| gen.pattern(param));
+        self.space();
+        self.block(body);
+    }
+
+    // Prints the argument of a `return`, `throw` or `yield`, which has to start on the same line,
+    // so one that starts with a comment that ends the line is grouped, as is one with an operator
+    // of a lower precedence than `min`.
+    fn restricted_exp(&mut self, e: &Exp, min: Precedence) {
+        let line_break = !self.options.minify && starts_with(e, &|e| {
+            e.comments().map_or(false, |c| c.leading.iter().any(Comment::ends_line))
+        });
+
+        self.grouped(e, e.precedence() < min || line_break);
+    }
+
+    // Whether a comment after the expression ends the line, where a line break isn't allowed.
+    fn ends_with_line_break(&self, e: &Exp) -> bool {
+        !self.options.minify &&
+        e.comments().map_or(false, |c| c.trailing.iter().any(Comment::ends_line))
+    }
+
+    fn unary(&mut self, op: &str, e: &Exp, prec: Precedence) {
+        self.token(op);
+        self.grouped(e, e.precedence() < prec);
+    }
+
+    // Groups the object of a member access. Number literals are grouped as well, since a `.`
+    // straight after one would be read as a decimal point.
+    fn member_obj(&mut self, obj: &Exp) {
+        let group = obj.precedence() < Precedence::Const || matches!(*obj.node(), Exp::Float(_));
+        self.grouped(obj, group);
+    }
+
+    // Property names that aren't valid identifiers have to be quoted, unless they're numbers that
+    // are written the same way when converted back to a string.
+    fn prop_name(&mut self, name: &str) {
+        if is_identifier_name(name) || is_numeric_key(name) {
+            self.token(name);
+        } else {
+            self.exp(&Exp::Str(String::from(name)));
+        }
+    }
+
+    // Prints the string parts of a template as they were written, with the substitutions between
+    // them, e.g. `a${b}c`.
+    fn template(&mut self, template: &Template) {
+        let mut open = "`";
+
+        for (i, part) in template.quasis.iter().enumerate() {
+            match template.exps.get(i) {
+                Some(e) => {
+                    self.token(&format!("{}{}${{", open, part.raw));
+                    self.exp(e);
+                }
+                None => self.token(&format!("{}{}`", open, part.raw)),
+            }
+
+            open = "}";
+        }
+    }
+
+    // Prints the elements of an array literal or pattern, where `None` is a hole. A trailing comma
+    // is ignored, so a hole at the end needs a comma of its own.
+    fn array<T, F>(&mut self, elems: &[Option<T>], f: F) where F: Fn(&mut CodeGen, &T) {
+        let last = elems.len().saturating_sub(1);
+        let elems: Vec<_> = elems.iter().enumerate().collect();
+
+        self.list("[", &elems, "]", |gen, &(i, elem)| {
+            match *elem {
+                Some(ref e) => f(gen, e),
+                None if i == last => gen.token(","),
+                None => (),
+            }
+        });
+    }
+
+    // Prints a comma-separated list between `open` and `close`. If the list doesn't fit on the
+    // current line, each item goes on its own line instead.
+    fn list<T, F>(&mut self, open: &str, items: &[T], close: &str, f: F)
+        where F: Fn(&mut CodeGen, &T)
+    {
+        let flat = |gen: &mut CodeGen| {
+            gen.token(open);
+
+            for (i, item) in items.iter().enumerate() {
+                if i != 0 {
+                    gen.token(",");
+                    gen.space();
+                }
+
+                f(gen, item);
+            }
+
+            gen.token(close);
+        };
+
+        if self.options.minify || self.measuring || items.is_empty() {
+            return flat(self);
+        }
+
+        // Lists with items that span several lines themselves are left as they are
+        let text = self.measure(&flat);
+        let too_wide = self.column + text.chars().count() > self.options.max_width;
+
+        if !too_wide || text.contains('\n') {
+            return flat(self);
+        }
+
+        self.token(open);
+        self.indent_level += 1;
+
+        for (i, item) in items.iter().enumerate() {
+            if i != 0 {
+                self.token(",");
+            }
+
+            self.newline();
+            f(self, item);
+        }
+
+        self.indent_level -= 1;
+        self.newline();
+        self.token(close);
+    }
+
+    // Generates text with `f` without adding it to the output.
+    fn measure<F>(&self, f: &F) -> String where F: Fn(&mut CodeGen) {
+        let mut gen = CodeGen::new(self.options.clone()).indented(self.indent_level);
+        gen.column = self.column;
+        gen.at_line_start = false;
+        gen.measuring = true;
+
+        f(&mut gen);
+        gen.finish()
+    }
+
+    // Writes a token, indenting it first if it starts a line.
+    fn token(&mut self, s: &str) {
+        // The semicolon can only be left out before a closing brace or at the end of a line, and
+        // then only if the next line can't be read as a continuation of this one.
+        let continues = s.starts_with(|c| "([+-/`".contains(c));
+        let semi = self.pending_semi && !s.starts_with('}') && (!self.at_line_start || continues);
+        self.pending_semi = false;
+        self.indent();
+
+        if semi {
+            self.write(";");
+        }
+
+        if needs_separator(&self.prev_token, s) {
+            self.write(" ");
+        }
+
+        self.add_mapping(s);
+        self.write(s);
+        self.prev_token = String::from(s);
+        self.at_line_start = false;
+    }
+
+    // Maps the current position to where `token` is in the innermost node with a span, unless
+    // the last mapping on this line already does so.
+    fn add_mapping(&mut self, token: &str) {
+        let span = self.spans.iter().rev().find(|span| span.line > 0).cloned();
+        let original = span.map(|span| self.original_position(&span, token));
+
+        let same_as_last = match self.mappings.last() {
+            Some(last) => last.generated_line == self.line && last.original == original,
+            // Output before any spanned node doesn't need to be marked as unmapped
+            None => original.is_none(),
+        };
+
+        if !same_as_last {
+            self.mappings.push(Mapping {
+                generated_line: self.line,
+                generated_column: self.utf16_column,
+                original: original,
+            });
+        }
+    }
+
+    // The 0-based line and UTF-16 column of `token` in the original source, looking for it in
+    // `span` after the tokens found so far. A token that isn't there, e.g. a semicolon that was
+    // left out, is put at the start of the span, as is every token if there's no source text
+    // (the span's column is already in UTF-16 code units).
+    fn original_position(&mut self, span: &Span, token: &str) -> (usize, usize, usize) {
+        let source = match self.sources.get(span.file) {
+            Some(source) => source,
+            None => return (span.file, span.line - 1, span.column.saturating_sub(1)),
+        };
+
+        let start = cmp::max(span.offset, self.source_offsets[span.file]);
+        let end = cmp::min(span.end(), source.len());
+
+        let offset = match source.get(start..end).and_then(|text| text.find(token)) {
+            Some(i) => {
+                self.source_offsets[span.file] = start + i + token.len();
+                start + i
+            }
+            None => span.offset,
+        };
+
+        // Count from the start of the span, whose line is already known
+        let (mut line, mut column) = (span.line - 1, None);
+
+        match source.get(span.offset..offset) {
+            Some(text) => {
+                let mut chars = text.chars().peekable();
+
+                while let Some(c) = chars.next() {
+                    if is_line_terminator(c) && !(c == '\r' && chars.peek() == Some(&'\n')) {
+                        line += 1;
+                        column = Some(0);
+                    } else if let Some(ref mut column) = column {
+                        *column += c.len_utf16();
+                    }
+                }
+            }
+            None => return (span.file, span.line - 1, span.column.saturating_sub(1)),
+        }
+
+        // If the token is on the span's first line, the rest of the line before the span counts
+        // too
+        let column = match column {
+            Some(column) => column,
+            None => {
+                source[..offset].chars()
+                    .rev()
+                    .take_while(|c| !is_line_terminator(*c))
+                    .map(char::len_utf16)
+                    .sum()
+            }
+        };
+
+        (span.file, line, column)
+    }
+
+    fn indent(&mut self) {
+        if self.at_line_start && !self.options.minify {
+            let indent: String = if self.options.use_tabs {
+                iter::repeat('\t').take(self.indent_level).collect()
+            } else {
+                iter::repeat(' ').take(self.indent_level * self.options.indent_width).collect()
+            };
+
+            self.write(&indent);
+        }
+    }
+
+    // Writes a comment, separated from anything before it on the line and ending the line after
+    // it if it's a line comment.
+    fn comment(&mut self, comment: &Comment) {
+        if self.at_line_start {
+            self.indent();
+        } else if !self.out.ends_with(|c| " ([".contains(c)) {
+            self.write(" ");
+        }
+
+        if comment.block {
+            self.write(&format!("/*{}*/", comment.text));
+        } else {
+            self.write(&format!("//{}", comment.text));
+        }
+
+        self.prev_token.clear();
+        self.at_line_start = false;
+
+        if !comment.block {
+            self.newline();
+        }
+    }
+
+    // Goes to the start of a new line for something that was `lines_before` lines below what came
+    // before it, keeping a blank line unless it's at the start of a block, or just separates it
+    // if it was on the same line.
+    fn line_breaks(&mut self, lines_before: usize, at_block_start: bool) {
+        if lines_before == 0 {
+            if !self.at_line_start {
+                self.space();
+            }
+
+            return;
+        }
+
+        if !self.at_line_start {
+            self.newline();
+        }
+
+        if lines_before > 1 && !at_block_start {
+            self.newline();
+        }
+    }
+
+    // Takes back the line break that was just written, so a comment can go at the end of the line.
+    fn reopen_line(&mut self) {
+        if let Some((column, utf16_column)) = self.line_end.take() {
+            self.out.pop();
+            self.line -= 1;
+            self.column = column;
+            self.utf16_column = utf16_column;
+            self.at_line_start = false;
+        }
+    }
+
+    fn semi(&mut self) {
+        match self.options.semicolons {
+            Semicolons::Always => self.token(";"),
+            Semicolons::AsNeeded => self.pending_semi = true,
+        }
+    }
+
+    fn space(&mut self) {
+        if !self.options.minify {
+            self.write(" ");
+            self.prev_token.clear();
+        }
+    }
+
+    fn newline(&mut self) {
+        if !self.options.minify {
+            let line_end = (self.column, self.utf16_column);
+            self.write("\n");
+            self.line_end = Some(line_end);
+            self.prev_token.clear();
+            self.at_line_start = true;
+        }
+    }
+
+    fn write(&mut self, s: &str) {
+        self.line_end = None;
+
+        for c in s.chars() {
+            if c == '\n' {
+                self.line += 1;
+                self.column = 0;
+                self.utf16_column = 0;
+            } else {
+                self.column += 1;
+                self.utf16_column += c.len_utf16();
+            }
+        }
+
+        self.out.push_str(s);
+    }
+}
+
+// Whether two adjacent tokens would run together into something else if printed without
+// whitespace between them, e.g. `typeof x` or `a - -b`.
+fn needs_separator(prev: &str, next: &str) -> bool {
+    fn is_word_char(c: char) -> bool {
+        c == '$' || c == '_' || c == '\\' || c.is_alphanumeric()
+    }
+
+    let (last, first) = match (prev.chars().last(), next.chars().next()) {
+        (Some(last), Some(first)) => (last, first),
+        _ => return false,
+    };
+
+    // Besides `/` and `/=`, the only tokens starting with a slash are regex literals, and the word
+    // after one would be read as its flags
+    let is_regex = prev.starts_with('/') && prev != "/" && prev != "/=";
+
+    (is_word_char(last) && is_word_char(first)) ||
+        (is_regex && is_word_char(first)) ||
+        ((prev == "+" || prev == "-") && first == last) ||
+        (prev == "/" && (first == '/' || first == '*'))
+}
+
+// Whether `e` has to be grouped at the start of a statement; see `CodeGen::stmt_exp`.
+fn starts_ambiguously(e: &Exp) -> bool {
+    starts_with(e, &|e| {
+        match *e {
+            Exp::Class(_) | Exp::Defun(..) | Exp::Object(_) => true,
+            Exp::KeyAccessor(ref obj, _) => matches!(*obj.node(), Exp::Var(ref v) if v == "let"),
+            _ => false,
+        }
+    })
+}
+
+// Whether the first thing printed for `e` would be an expression matching `pred`.
+fn starts_with<F>(e: &Exp, pred: &F) -> bool where F: Fn(&Exp) -> bool {
+    if pred(e) || pred(e.node()) {
+        return true;
+    }
+
+    match *e.node() {
+        // An object pattern starts with `{`, just like an object literal
+        Exp::AssignExp(_, ref p, _) => {
+            match **p {
+                Pattern::Exp(ref e) => starts_with(e, pred),
+                Pattern::Object(..) => pred(&Exp::Object(Vec::new())),
+                _ => false,
+            }
+        }
+        Exp::BinExp(ref e, _, _) | Exp::Call(ref e, _) | Exp::Chain(ref e) |
+        Exp::Optional(ref e) | Exp::TaggedTemplate(ref e, _) |
+        Exp::Comma(ref e, _) | Exp::Conditional(ref e, _, _) | Exp::InstanceVar(ref e, _) |
+        Exp::KeyAccessor(ref e, _) | Exp::PostDec(ref e) | Exp::PostInc(ref e) => {
+            starts_with(e, pred)
+        }
+        _ => false,
+    }
+}
+
+fn is_call_chain(e: &Exp) -> bool {
+    match *e.node() {
+        Exp::Call(..) => true,
+        Exp::InstanceVar(ref obj, _) | Exp::KeyAccessor(ref obj, _) |
+        Exp::TaggedTemplate(ref obj, _) => is_call_chain(obj),
+        _ => false,
+    }
+}
+
+/// Escapes `s` for use as the text of a template literal. Besides backslashes and backticks, `${`
+/// is escaped so it doesn't start a substitution, and carriage returns are escaped since they'd be
+/// read back as line feeds.
+pub fn escape_template(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    let mut chars = s.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '`' => escaped.push_str("\\`"),
+            '$' if chars.peek() == Some(&'{') => escaped.push_str("\\$"),
+            '\r' => escaped.push_str("\\r"),
+            c => escaped.push(c),
+        }
+    }
+
+    escaped
+}
+
+/// Quotes `s` as a JavaScript string literal using `quote` as the delimiter. Besides the quote and
+/// backslash, control characters and line terminators are escaped, as are characters outside the
+/// Basic Multilingual Plane (as a surrogate pair), so the literal is plain printable text.
+pub fn escape_str(s: &str, quote: char) -> String {
+    let mut escaped = String::with_capacity(s.len() + 2);
+    escaped.push(quote);
+
+    let mut chars = s.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            '\u{8}' => escaped.push_str("\\b"),
+            '\u{b}' => escaped.push_str("\\v"),
+            '\u{c}' => escaped.push_str("\\f"),
+            // `\0` followed by a digit would be read as a legacy octal escape
+            '\0' => match chars.peek() {
+                Some(&('0'..='9')) => escaped.push_str("\\x00"),
+                _ => escaped.push_str("\\0"),
+            },
+            c if c == quote => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            '\u{0}'..='\u{1f}' | '\u{7f}'..='\u{9f}' => {
+                escaped.push_str(&format!("\\x{:02X}", c as u32))
+            }
+            '\u{2028}' | '\u{2029}' | '\u{feff}' => {
+                escaped.push_str(&format!("\\u{:04X}", c as u32))
+            }
+            c if c as u32 > 0xffff => {
+                let n = c as u32 - 0x10000;
+                escaped.push_str(&format!("\\u{:04X}\\u{:04X}", 0xd800 + (n >> 10), 0xdc00 + (n & 0x3ff)));
+            }
+            c => escaped.push(c),
+        }
+    }
+
+    escaped.push(quote);
+    escaped
+}
+
+/// Formats `f` the way JavaScript's `Number.prototype.toString` does: the shortest digits that
+/// read back as `f`, in exponent form for magnitudes of at least 1e21 or below 1e-6. Unlike
+/// JavaScript, the sign of `-0` is kept.
+pub fn number_to_string(f: f64) -> String {
+    if f.is_nan() {
+        return String::from("NaN");
+    } else if f.is_sign_negative() {
+        return format!("-{}", number_to_string(-f));
+    } else if f.is_infinite() {
+        return String::from("Infinity");
+    } else if f == 0.0 {
+        return String::from("0");
+    }
+
+    // Rust's exponent form already has the shortest round-trip digits, e.g. `1.2345e-7`
+    let sci = format!("{:e}", f);
+    let (mantissa, exponent) = sci.split_at(sci.find('e').unwrap());
+    let digits = mantissa.replace('.', "");
+    let k = digits.len() as i32;
+    // The decimal point goes after the first `n` digits
+    let n = exponent[1..].parse::<i32>().unwrap() + 1;
+
+    if k <= n && n <= 21 {
+        format!("{}{}", digits, "0".repeat((n - k) as usize))
+    } else if 0 < n && n <= 21 {
+        let (int, frac) = digits.split_at(n as usize);
+        format!("{}.{}", int, frac)
+    } else if -6 < n && n <= 0 {
+        format!("0.{}{}", "0".repeat(-n as usize), digits)
+    } else {
+        let sign = if n > 0 { "+" } else { "-" };
+        let (first, rest) = digits.split_at(1);
+        let point = if rest.is_empty() { "" } else { "." };
+        format!("{}{}{}e{}{}", first, point, rest, sign, (n - 1).abs())
+    }
+}
+
+/// Whether `s` is the string form of a non-negative integer, e.g. `1` but not `01` or `1.0`, so
+/// that it can be written unquoted as a property name. Only integers below 1e21 qualify, since
+/// JavaScript turns larger numbers and fractions like `0.0000001` into exponent form.
+pub fn is_numeric_key(s: &str) -> bool {
+    if s.is_empty() || !s.bytes().all(|b| b.is_ascii_digit()) {
+        return false;
+    }
+
+    match s.parse::<f64>() {
+        Ok(f) => number_to_string(f) == s,
+        Err(_) => false,
+    }
+}
+
+/// Whether `s` can be written as a bare identifier, e.g. as a property name in an object literal.
+pub fn is_identifier_name(s: &str) -> bool {
+    let mut chars = s.chars();
+
+    match chars.next() {
+        Some(c) if c == '$' || c == '_' || is_id_start(c) => (),
+        _ => return false,
+    }
+
+    chars.all(|c| c == '$' || c == '_' || c == '\u{200c}' || c == '\u{200d}' || is_id_continue(c))
+}
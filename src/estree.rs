@@ -0,0 +1,1686 @@
+// Conversion between the AST and ESTree (https://github.com/estree/estree), the JSON format for
+// JavaScript syntax trees shared by most JavaScript tooling.
+//
+// Converting a tree to ESTree and back gives the same tree, with a few exceptions for things
+// ESTree has no separate representation for: the statements of a `Seq` are spliced into the
+// enclosing list of statements (or written as a block, if there isn't one), and a statement of
+// just a plain `=` assignment comes back as a `Stmt::Assign`. Constants that ESTree has no
+// literal for are written the way they'd be in source, with `constant` set on the node so they
+// don't come back as what they look like: `Float(NaN)`, `Float(Infinity)` and `Undefined` as the
+// identifiers `NaN`, `Infinity` and `undefined`, and a negative `Float` as `-` applied to a
+// positive one. Spans are written as `range` and `loc.start`, with `loc.file` added for files
+// other than the first. The placeholders for source that couldn't be parsed are written as an
+// identifier named `✖` (as acorn-loose does), and a statement of just that placeholder comes back
+// as `Stmt::Error`. Comments are written as `leadingComments` and
+// `trailingComments`, as Babel does; blank lines are lost, so comments come back with leading
+// comments of statements each on their own line and other comments on the same line as what
+// they're next to (unless they follow a line comment).
+
+use serde_json::Value;
+
+use ast::{ArrowBody, AssignOp, BinOp, Class, ClassKey, ClassMember, Comment, Comments, DeclKind,
+          Exp, FnKind, ImportSpec, MemberKind, Pattern, Program, Prop, PropKey, Stmt, Template,
+          TemplatePart};
+use codegen::{is_identifier_name, is_numeric_key};
+use js_error::{JsError, Result};
+use span::Span;
+
+// The name of the identifier that `Exp::Error` is written as.
+const ERROR_NAME: &'static str = "✖";
+
+pub fn exp_to_estree(e: &Exp) -> Value {
+    match *e {
+        Exp::Array(ref elems) => {
+            let elems: Vec<_> = elems.iter()
+                .map(|elem| elem.as_ref().map(|e| exp_to_estree(e)))
+                .collect();
+
+            json!({ "type": "ArrayExpression", "elements": elems })
+        }
+        Exp::Arrow(kind, ref params, ref body) => {
+            let (body, expression) = match *body {
+                ArrowBody::Exp(ref e) => (exp_to_estree(e), true),
+                ArrowBody::Block(ref block) => (block_to_estree(block), false),
+            };
+
+            json!({
+                "type": "ArrowFunctionExpression",
+                "id": null,
+                "params": params.iter().map(pattern_to_estree).collect::<Vec<_>>(),
+                "body": body,
+                "expression": expression,
+                "generator": false,
+                "async": kind.is_async(),
+            })
+        }
+        Exp::AssignExp(ref o, ref lhs, ref rhs) => {
+            json!({
+                "type": "AssignmentExpression",
+                "operator": format!("{}", o),
+                "left": pattern_to_estree(lhs),
+                "right": exp_to_estree(rhs),
+            })
+        }
+        Exp::BinExp(ref e1, ref o, ref e2) => {
+            let typ = match *o {
+                BinOp::And | BinOp::Coalesce | BinOp::Or => "LogicalExpression",
+                _ => "BinaryExpression",
+            };
+
+            json!({
+                "type": typ,
+                "operator": format!("{}", o),
+                "left": exp_to_estree(e1),
+                "right": exp_to_estree(e2),
+            })
+        }
+        Exp::Await(ref e) => json!({ "type": "AwaitExpression", "argument": exp_to_estree(e) }),
+        Exp::BitNot(ref e) => unary_to_estree("~", e),
+        Exp::Bool(b) => literal(Value::from(b)),
+        Exp::Call(ref func, ref args) => {
+            let (callee, optional) = optional_to_estree(func);
+
+            json!({
+                "type": "CallExpression",
+                "callee": callee,
+                "arguments": boxes_to_estree(args),
+                "optional": optional,
+            })
+        }
+        Exp::Chain(ref e) => json!({ "type": "ChainExpression", "expression": exp_to_estree(e) }),
+        Exp::Class(ref class) => class_to_estree("ClassExpression", class),
+        Exp::Comma(..) => {
+            // Nested comma expressions are flattened, except where grouping made them nest to
+            // the right
+            let mut expressions = Vec::new();
+            let mut e = e;
+
+            while let Exp::Comma(ref e1, ref e2) = *e {
+                expressions.push(exp_to_estree(e2));
+                e = e1;
+            }
+
+            expressions.push(exp_to_estree(e));
+            expressions.reverse();
+            json!({ "type": "SequenceExpression", "expressions": expressions })
+        }
+        Exp::Conditional(ref test, ref e1, ref e2) => {
+            json!({
+                "type": "ConditionalExpression",
+                "test": exp_to_estree(test),
+                "consequent": exp_to_estree(e1),
+                "alternate": exp_to_estree(e2),
+            })
+        }
+        Exp::Defun(kind, ref name, ref params, ref body) => {
+            function_to_estree("FunctionExpression", kind, name.as_ref(), params, body)
+        }
+        Exp::Delete(ref e) => unary_to_estree("delete", e),
+        Exp::Error => identifier(ERROR_NAME),
+        Exp::Float(f) if f.is_nan() => constant(identifier("NaN")),
+        Exp::Float(f) if f.is_sign_negative() => constant(unary_to_estree("-", &Exp::Float(-f))),
+        Exp::Float(f) if f.is_infinite() => constant(identifier("Infinity")),
+        Exp::Float(f) => literal(Value::from(f)),
+        Exp::InstanceVar(ref obj, ref name) => {
+            let (obj, optional) = optional_to_estree(obj);
+
+            json!({
+                "type": "MemberExpression",
+                "object": obj,
+                "property": name_to_estree(name),
+                "computed": false,
+                "optional": optional,
+            })
+        }
+        Exp::KeyAccessor(ref obj, ref key) => {
+            let (obj, optional) = optional_to_estree(obj);
+
+            json!({
+                "type": "MemberExpression",
+                "object": obj,
+                "property": exp_to_estree(key),
+                "computed": true,
+                "optional": optional,
+            })
+        }
+        Exp::LogNot(ref e) => unary_to_estree("!", e),
+        Exp::Neg(ref e) => unary_to_estree("-", e),
+        Exp::NewObject(ref func, ref args) => {
+            json!({
+                "type": "NewExpression",
+                "callee": exp_to_estree(func),
+                "arguments": boxes_to_estree(args),
+            })
+        }
+        Exp::Null => literal(Value::Null),
+        Exp::Object(ref properties) => {
+            let properties: Vec<_> = properties.iter().map(|prop| {
+                match *prop {
+                    Prop::Value(ref name, ref value) => {
+                        property(prop_key_to_estree(name), exp_to_estree(value), "init", false)
+                    }
+                    Prop::Computed(ref key, ref value) => {
+                        let mut prop = property(exp_to_estree(key), exp_to_estree(value), "init",
+                                                false);
+                        prop["computed"] = Value::from(true);
+                        prop
+                    }
+                    Prop::Shorthand(ref name) => {
+                        let mut prop = property(identifier(name), identifier(name), "init", false);
+                        prop["shorthand"] = Value::from(true);
+                        prop
+                    }
+                    Prop::Method(kind, ref key, ref params, ref body) => {
+                        let value = function_to_estree("FunctionExpression", kind, None, params,
+                                                       body);
+                        method_to_estree(key, value, "init", true)
+                    }
+                    Prop::Get(ref key, ref body) => {
+                        let value = function_to_estree("FunctionExpression", FnKind::Normal, None,
+                                                       &[], body);
+                        method_to_estree(key, value, "get", false)
+                    }
+                    Prop::Set(ref key, ref param, ref body) => {
+                        let value = function_to_estree("FunctionExpression", FnKind::Normal, None,
+                                                       &[param.clone()], body);
+                        method_to_estree(key, value, "set", false)
+                    }
+                    Prop::Spread(ref e) => spread_to_estree(e),
+                }
+            }).collect();
+
+            json!({ "type": "ObjectExpression", "properties": properties })
+        }
+        // ESTree only has a flag on the member access or call that the `?.` belongs to, so one
+        // anywhere else is dropped
+        Exp::Optional(ref e) => exp_to_estree(e),
+        Exp::Pos(ref e) => unary_to_estree("+", e),
+        Exp::PostDec(ref e) => update_to_estree("--", false, e),
+        Exp::PostInc(ref e) => update_to_estree("++", false, e),
+        Exp::PreDec(ref e) => update_to_estree("--", true, e),
+        Exp::PreInc(ref e) => update_to_estree("++", true, e),
+        Exp::RegExp(ref pattern, ref flags) => {
+            json!({
+                "type": "Literal",
+                "value": Value::Null,
+                "regex": { "pattern": pattern, "flags": flags },
+            })
+        }
+        Exp::Spanned(ref span, ref e) => add_span(exp_to_estree(e), span),
+        Exp::Commented(ref comments, ref e) => add_comments(exp_to_estree(e), comments),
+        Exp::Spread(ref e) => spread_to_estree(e),
+        Exp::Super => json!({ "type": "Super" }),
+        Exp::Str(ref s) => literal(Value::from(s.clone())),
+        Exp::TaggedTemplate(ref tag, ref template) => {
+            json!({
+                "type": "TaggedTemplateExpression",
+                "tag": exp_to_estree(tag),
+                "quasi": template_to_estree(template),
+            })
+        }
+        Exp::Template(ref template) => template_to_estree(template),
+        Exp::This => json!({ "type": "ThisExpression" }),
+        Exp::TypeOf(ref e) => unary_to_estree("typeof", e),
+        Exp::Undefined => constant(identifier("undefined")),
+        Exp::Var(ref v) => name_to_estree(v),
+        Exp::Void(ref e) => unary_to_estree("void", e),
+        Exp::Yield(ref arg) => {
+            json!({
+                "type": "YieldExpression",
+                "argument": arg.as_ref().map(|e| exp_to_estree(e)),
+                "delegate": false,
+            })
+        }
+        Exp::YieldAll(ref e) => {
+            json!({ "type": "YieldExpression", "argument": exp_to_estree(e), "delegate": true })
+        }
+    }
+}
+
+pub fn stmt_to_estree(s: &Stmt) -> Value {
+    match *s {
+        Stmt::Assign(ref lhs, ref rhs) => {
+            expression_stmt(json!({
+                "type": "AssignmentExpression",
+                "operator": "=",
+                "left": pattern_to_estree(lhs),
+                "right": exp_to_estree(rhs),
+            }))
+        }
+        Stmt::BareExp(ref e) => expression_stmt(exp_to_estree(e)),
+        Stmt::Block(ref block) => block_to_estree(block),
+        Stmt::Break(ref label) => {
+            json!({ "type": "BreakStatement", "label": label.as_ref().map(|l| identifier(l)) })
+        }
+        Stmt::ClassDecl(ref class) => class_to_estree("ClassDeclaration", class),
+        Stmt::Continue(ref label) => {
+            json!({ "type": "ContinueStatement", "label": label.as_ref().map(|l| identifier(l)) })
+        }
+        Stmt::Decl(ref kind, ref decls) => {
+            let declarations: Vec<_> = decls.iter().map(|&(ref p, ref init)| {
+                let init = init.as_ref().map(exp_to_estree);
+                json!({ "type": "VariableDeclarator", "id": pattern_to_estree(p), "init": init })
+            }).collect();
+
+            json!({
+                "type": "VariableDeclaration",
+                "kind": kind.to_string(),
+                "declarations": declarations,
+            })
+        }
+        Stmt::DoWhile(ref body, ref cond) => {
+            json!({
+                "type": "DoWhileStatement",
+                "body": block_to_estree(body),
+                "test": exp_to_estree(cond),
+            })
+        }
+        Stmt::Empty => json!({ "type": "EmptyStatement" }),
+        Stmt::Error => expression_stmt(identifier(ERROR_NAME)),
+        Stmt::FnDecl(kind, ref name, ref params, ref body) => {
+            function_to_estree("FunctionDeclaration", kind, Some(name), params, body)
+        }
+        Stmt::Export(ref s) => {
+            json!({
+                "type": "ExportNamedDeclaration",
+                "declaration": stmt_to_estree(s),
+                "specifiers": [],
+                "source": null,
+            })
+        }
+        Stmt::ExportAll(ref name, ref source) => {
+            json!({
+                "type": "ExportAllDeclaration",
+                "exported": name.as_ref().map(|n| module_name_to_estree(n)),
+                "source": literal(Value::from(&source[..])),
+            })
+        }
+        Stmt::ExportDefault(ref e) => {
+            // A function or class is exported as a declaration
+            let mut declaration = exp_to_estree(e);
+            let typ = match *e.node() {
+                Exp::Class(_) => Some("ClassDeclaration"),
+                Exp::Defun(..) => Some("FunctionDeclaration"),
+                _ => None,
+            };
+
+            if let Some(typ) = typ {
+                declaration["type"] = Value::from(typ);
+            }
+
+            json!({ "type": "ExportDefaultDeclaration", "declaration": declaration })
+        }
+        Stmt::ExportNames(ref names, ref source) => {
+            let specifiers: Vec<_> = names.iter().map(|&(ref local, ref exported)| {
+                json!({
+                    "type": "ExportSpecifier",
+                    "local": module_name_to_estree(local),
+                    "exported": module_name_to_estree(exported),
+                })
+            }).collect();
+
+            json!({
+                "type": "ExportNamedDeclaration",
+                "declaration": null,
+                "specifiers": specifiers,
+                "source": source.as_ref().map(|s| literal(Value::from(&s[..]))),
+            })
+        }
+        Stmt::If(ref cond, ref then_block, ref else_block) => {
+            json!({
+                "type": "IfStatement",
+                "test": exp_to_estree(cond),
+                "consequent": block_to_estree(then_block),
+                "alternate": opt_block_to_estree(else_block),
+            })
+        }
+        Stmt::For(ref init, _, _, _) if !is_for_init(init) => {
+            json!({ "type": "BlockStatement", "body": stmts_to_estree(&[s.clone()]) })
+        }
+        Stmt::For(..) => for_to_estree(s),
+        Stmt::ForIn(ref left, ref obj, ref body) => {
+            json!({
+                "type": "ForInStatement",
+                "left": for_init_to_estree(left),
+                "right": exp_to_estree(obj),
+                "body": block_to_estree(body),
+            })
+        }
+        Stmt::ForOf(ref left, ref iterable, ref body) => {
+            json!({
+                "type": "ForOfStatement",
+                "await": false,
+                "left": for_init_to_estree(left),
+                "right": exp_to_estree(iterable),
+                "body": block_to_estree(body),
+            })
+        }
+        Stmt::Import(ref specs, ref source) => {
+            let specifiers: Vec<_> = specs.iter().map(|spec| {
+                match *spec {
+                    ImportSpec::Default(ref local) => {
+                        json!({ "type": "ImportDefaultSpecifier", "local": identifier(local) })
+                    }
+                    ImportSpec::Namespace(ref local) => {
+                        json!({ "type": "ImportNamespaceSpecifier", "local": identifier(local) })
+                    }
+                    ImportSpec::Named(ref imported, ref local) => {
+                        json!({
+                            "type": "ImportSpecifier",
+                            "imported": module_name_to_estree(imported),
+                            "local": identifier(local),
+                        })
+                    }
+                }
+            }).collect();
+
+            json!({
+                "type": "ImportDeclaration",
+                "specifiers": specifiers,
+                "source": literal(Value::from(&source[..])),
+                "attributes": [],
+            })
+        }
+        Stmt::Labeled(ref label, ref body) => {
+            json!({ "type": "LabeledStatement", "label": identifier(label),
+                    "body": stmt_to_estree(body) })
+        }
+        Stmt::Ret(ref e) => {
+            let argument = match *e {
+                Exp::Undefined => Value::Null,
+                ref e => exp_to_estree(e),
+            };
+
+            json!({ "type": "ReturnStatement", "argument": argument })
+        }
+        Stmt::Seq(..) => json!({ "type": "BlockStatement", "body": stmts_to_estree(&[s.clone()]) }),
+        Stmt::Spanned(ref span, ref s) => add_span(stmt_to_estree(s), span),
+        Stmt::Commented(ref comments, ref s) => add_comments(stmt_to_estree(s), comments),
+        Stmt::Switch(ref e, ref cases) => {
+            let cases: Vec<_> = cases.iter().map(|&(ref test, ref body)| {
+                json!({
+                    "type": "SwitchCase",
+                    "test": test.as_ref().map(exp_to_estree),
+                    "consequent": stmts_to_estree(body),
+                })
+            }).collect();
+
+            json!({ "type": "SwitchStatement", "discriminant": exp_to_estree(e), "cases": cases })
+        }
+        Stmt::Throw(ref e) => json!({ "type": "ThrowStatement", "argument": exp_to_estree(e) }),
+        Stmt::Try(ref try_block, ref catch_var, ref catch_block, ref finally_block) => {
+            // As when printing, an empty catch clause is only kept if there's no finally block
+            let handler = if catch_var.is_some() || !catch_block.is_empty() ||
+                             finally_block.is_empty() {
+                let param = catch_var.as_ref().map_or(Value::Null, pattern_to_estree);
+                json!({
+                    "type": "CatchClause",
+                    "param": param,
+                    "body": block_to_estree(catch_block),
+                })
+            } else {
+                Value::Null
+            };
+
+            json!({
+                "type": "TryStatement",
+                "block": block_to_estree(try_block),
+                "handler": handler,
+                "finalizer": opt_block_to_estree(finally_block),
+            })
+        }
+        Stmt::While(ref cond, ref body) => {
+            json!({
+                "type": "WhileStatement",
+                "test": exp_to_estree(cond),
+                "body": block_to_estree(body),
+            })
+        }
+    }
+}
+
+fn function_to_estree(typ: &str, kind: FnKind, name: Option<&String>, params: &[Pattern],
+                      body: &[Stmt]) -> Value {
+    json!({
+        "type": typ,
+        "id": name.map(|n| identifier(n)),
+        "params": params.iter().map(pattern_to_estree).collect::<Vec<_>>(),
+        "body": block_to_estree(body),
+        "generator": kind.is_generator(),
+        "async": kind.is_async(),
+    })
+}
+
+pub fn pattern_to_estree(p: &Pattern) -> Value {
+    match *p {
+        Pattern::Var(ref v) => identifier(v),
+        Pattern::Exp(ref e) => exp_to_estree(e),
+        Pattern::Default(ref p, ref e) => {
+            json!({
+                "type": "AssignmentPattern",
+                "left": pattern_to_estree(p),
+                "right": exp_to_estree(e),
+            })
+        }
+        Pattern::Rest(ref p) => json!({ "type": "RestElement", "argument": pattern_to_estree(p) }),
+        Pattern::Array(ref elems) => {
+            let elems: Vec<_> = elems.iter()
+                .map(|elem| elem.as_ref().map(pattern_to_estree))
+                .collect();
+
+            json!({ "type": "ArrayPattern", "elements": elems })
+        }
+        Pattern::Object(ref props, ref rest) => {
+            let mut properties: Vec<_> = props.iter().map(|&(ref name, ref p)| {
+                let value = pattern_to_estree(p);
+                let shorthand = value["type"] == "Identifier" && value["name"] == **name;
+
+                json!({
+                    "type": "Property",
+                    "key": prop_key_to_estree(name),
+                    "value": value,
+                    "kind": "init",
+                    "computed": false,
+                    "method": false,
+                    "shorthand": shorthand,
+                })
+            }).collect();
+
+            if let Some(ref rest) = *rest {
+                let argument = pattern_to_estree(rest);
+                properties.push(json!({ "type": "RestElement", "argument": argument }));
+            }
+
+            json!({ "type": "ObjectPattern", "properties": properties })
+        }
+    }
+}
+
+/// Converts a whole script or module to an ESTree `Program`.
+pub fn program_to_estree(program: &Program) -> Value {
+    json!({
+        "type": "Program",
+        "sourceType": if program.is_module() { "module" } else { "script" },
+        "body": stmts_to_estree(program.stmts()),
+    })
+}
+
+// Converts a list of statements, splicing in the statements of any `Seq`s. Like the code
+// generator, the initialization of a for loop that can't go in the loop header is moved before
+// the loop.
+fn stmts_to_estree(stmts: &[Stmt]) -> Vec<Value> {
+    fn flatten(s: &Stmt, out: &mut Vec<Value>) {
+        match *s {
+            Stmt::Seq(ref s1, ref s2) => {
+                flatten(s1, out);
+                flatten(s2, out);
+            }
+            Stmt::For(ref init, _, _, _) if !is_for_init(init) => {
+                flatten(init, out);
+                out.push(for_to_estree(s));
+            }
+            _ => out.push(stmt_to_estree(s)),
+        }
+    }
+
+    let mut out = Vec::new();
+
+    for s in stmts {
+        flatten(s, &mut out);
+    }
+
+    out
+}
+
+fn identifier(name: &str) -> Value {
+    json!({ "type": "Identifier", "name": name })
+}
+
+// Marks a node written for a constant that ESTree has no literal for, e.g. `Float(NaN)` as `NaN`,
+// so that it isn't read back as the variable or expression it looks like.
+fn constant(mut node: Value) -> Value {
+    node["constant"] = Value::Bool(true);
+    node
+}
+
+fn is_constant(node: &Value) -> bool {
+    node.get("constant") == Some(&Value::Bool(true))
+}
+
+fn literal(value: Value) -> Value {
+    json!({ "type": "Literal", "value": value })
+}
+
+// The name of an import or export, which is a string literal if it isn't an identifier.
+fn module_name_to_estree(name: &str) -> Value {
+    if is_identifier_name(name) { identifier(name) } else { literal(Value::from(name)) }
+}
+
+fn prop_key_to_estree(name: &str) -> Value {
+    if is_identifier_name(name) {
+        identifier(name)
+    } else if is_numeric_key(name) {
+        literal(Value::from(name.parse::<f64>().unwrap_or(0.0)))
+    } else {
+        literal(Value::from(name))
+    }
+}
+
+// A property of an object literal, which isn't computed or shorthand.
+fn property(key: Value, value: Value, kind: &str, method: bool) -> Value {
+    json!({
+        "type": "Property",
+        "key": key,
+        "value": value,
+        "kind": kind,
+        "computed": false,
+        "method": method,
+        "shorthand": false,
+    })
+}
+
+// A method or accessor of an object literal.
+fn method_to_estree(key: &PropKey, value: Value, kind: &str, method: bool) -> Value {
+    match *key {
+        PropKey::Name(ref name) => property(prop_key_to_estree(name), value, kind, method),
+        PropKey::Computed(ref e) => {
+            let mut prop = property(exp_to_estree(e), value, kind, method);
+            prop["computed"] = Value::from(true);
+            prop
+        }
+    }
+}
+
+fn spread_to_estree(e: &Exp) -> Value {
+    json!({ "type": "SpreadElement", "argument": exp_to_estree(e) })
+}
+
+// The object of a member access or the callee of a call, and whether it's followed by `?.`.
+fn optional_to_estree(e: &Exp) -> (Value, bool) {
+    match *e {
+        Exp::Optional(ref e) => (exp_to_estree(e), true),
+        ref e => (exp_to_estree(e), false),
+    }
+}
+
+// An identifier, or a private name such as `#x` in `this.#x` or `#x in obj`.
+fn name_to_estree(name: &str) -> Value {
+    if name.starts_with('#') {
+        json!({ "type": "PrivateIdentifier", "name": &name[1..] })
+    } else {
+        identifier(name)
+    }
+}
+
+fn class_to_estree(typ: &str, class: &Class) -> Value {
+    let members: Vec<_> = class.members.iter().map(|member| {
+        let (key, computed) = match member.key {
+            ClassKey::Name(ref name) => (prop_key_to_estree(name), false),
+            ClassKey::Private(ref name) => (name_to_estree(&format!("#{}", name)), false),
+            ClassKey::Computed(ref e) => (exp_to_estree(e), true),
+        };
+
+        let (kind, fn_kind, params, body) = match member.kind {
+            MemberKind::Method(fn_kind, ref params, ref body) => {
+                let is_constructor = !member.is_static && fn_kind == FnKind::Normal &&
+                                     member.key == ClassKey::Name(String::from("constructor"));
+                let kind = if is_constructor { "constructor" } else { "method" };
+                (kind, fn_kind, params.clone(), body)
+            }
+            MemberKind::Get(ref body) => ("get", FnKind::Normal, vec![], body),
+            MemberKind::Set(ref param, ref body) => {
+                ("set", FnKind::Normal, vec![param.clone()], body)
+            }
+            MemberKind::Field(ref value) => {
+                return json!({
+                    "type": "PropertyDefinition",
+                    "key": key,
+                    "computed": computed,
+                    "value": value.as_ref().map(exp_to_estree),
+                    "static": member.is_static,
+                });
+            }
+        };
+
+        json!({
+            "type": "MethodDefinition",
+            "key": key,
+            "computed": computed,
+            "value": exp_to_estree(&Exp::Defun(fn_kind, None, params, body.clone())),
+            "kind": kind,
+            "static": member.is_static,
+        })
+    }).collect();
+
+    json!({
+        "type": typ,
+        "id": class.name.as_ref().map(|n| identifier(n)),
+        "superClass": class.extends.as_ref().map(|e| exp_to_estree(e)),
+        "body": { "type": "ClassBody", "body": members },
+    })
+}
+
+fn template_to_estree(template: &Template) -> Value {
+    let last = template.quasis.len().saturating_sub(1);
+    let quasis: Vec<_> = template.quasis.iter().enumerate().map(|(i, part)| {
+        json!({
+            "type": "TemplateElement",
+            "value": { "cooked": part.cooked, "raw": part.raw },
+            "tail": i == last,
+        })
+    }).collect();
+
+    json!({
+        "type": "TemplateLiteral",
+        "quasis": quasis,
+        "expressions": boxes_to_estree(&template.exps),
+    })
+}
+
+fn expression_stmt(e: Value) -> Value {
+    json!({ "type": "ExpressionStatement", "expression": e })
+}
+
+fn boxes_to_estree(exps: &[Box<Exp>]) -> Vec<Value> {
+    exps.iter().map(|e| exp_to_estree(e)).collect()
+}
+
+fn unary_to_estree(op: &str, e: &Exp) -> Value {
+    json!({
+        "type": "UnaryExpression",
+        "operator": op,
+        "prefix": true,
+        "argument": exp_to_estree(e),
+    })
+}
+
+fn update_to_estree(op: &str, prefix: bool, e: &Exp) -> Value {
+    json!({
+        "type": "UpdateExpression",
+        "operator": op,
+        "prefix": prefix,
+        "argument": exp_to_estree(e),
+    })
+}
+
+fn block_to_estree(block: &[Stmt]) -> Value {
+    json!({ "type": "BlockStatement", "body": stmts_to_estree(block) })
+}
+
+fn opt_block_to_estree(block: &[Stmt]) -> Value {
+    if block.is_empty() { Value::Null } else { block_to_estree(block) }
+}
+
+// A for loop, leaving out the initialization if it can't go in the loop header.
+fn for_to_estree(s: &Stmt) -> Value {
+    let (init, cond, step, body) = match *s {
+        Stmt::For(ref init, ref cond, ref step, ref body) => (init, cond, step, body),
+        _ => unreachable!(),
+    };
+
+    let step = match *step {
+        Exp::Undefined => Value::Null,
+        ref e => exp_to_estree(e),
+    };
+
+    json!({
+        "type": "ForStatement",
+        "init": for_init_to_estree(init),
+        "test": exp_to_estree(cond),
+        "update": step,
+        "body": block_to_estree(body),
+    })
+}
+
+fn is_for_init(s: &Stmt) -> bool {
+    match *s.node() {
+        Stmt::Assign(..) | Stmt::BareExp(_) | Stmt::Decl(..) | Stmt::Empty => true,
+        _ => false,
+    }
+}
+
+fn for_init_to_estree(s: &Stmt) -> Value {
+    match *s {
+        Stmt::Assign(..) | Stmt::BareExp(_) => stmt_to_estree(s)["expression"].clone(),
+        Stmt::Decl(..) => stmt_to_estree(s),
+        Stmt::Spanned(ref span, ref s) if !matches!(*s.node(), Stmt::Empty) => {
+            add_span(for_init_to_estree(s), span)
+        }
+        Stmt::Commented(ref comments, ref s) if !matches!(*s.node(), Stmt::Empty) => {
+            add_comments(for_init_to_estree(s), comments)
+        }
+        _ => Value::Null,
+    }
+}
+
+fn add_span(mut node: Value, span: &Span) -> Value {
+    if let Value::Object(ref mut map) = node {
+        let mut loc = json!({
+            "start": { "line": span.line, "column": span.column.saturating_sub(1) },
+        });
+
+        if span.file != 0 {
+            loc["file"] = Value::from(span.file);
+        }
+
+        map.insert(String::from("range"), json!([span.offset, span.end()]));
+        map.insert(String::from("loc"), loc);
+    }
+
+    node
+}
+
+fn add_comments(mut node: Value, comments: &Comments) -> Value {
+    let to_estree = |comments: &[Comment]| -> Vec<_> {
+        comments.iter().map(|c| {
+            let typ = if c.block { "CommentBlock" } else { "CommentLine" };
+            json!({ "type": typ, "value": c.text })
+        }).collect()
+    };
+
+    if let Value::Object(ref mut map) = node {
+        if !comments.leading.is_empty() {
+            map.insert(String::from("leadingComments"), Value::from(to_estree(&comments.leading)));
+        }
+
+        if !comments.trailing.is_empty() {
+            let trailing = to_estree(&comments.trailing);
+            map.insert(String::from("trailingComments"), Value::from(trailing));
+        }
+    }
+
+    node
+}
+
+pub fn exp_from_estree(node: &Value) -> Result<Exp> {
+    let e = match try!(node_type(node)) {
+        "ArrayExpression" => {
+            let mut elems = Vec::new();
+
+            for elem in try!(array_field(node, "elements")) {
+                if elem.is_null() {
+                    elems.push(None);
+                } else {
+                    elems.push(Some(Box::new(try!(exp_from_estree(elem)))));
+                }
+            }
+
+            Exp::Array(elems)
+        }
+        "AssignmentExpression" => {
+            let op = try!(str_field(node, "operator"));
+            let op = match AssignOp::from_op(op) {
+                Some(op) => op,
+                None => {
+                    return Err(JsError::unimplemented(&format!("ESTree operator `{}`", op)))
+                }
+            };
+
+            let lhs = try!(pattern_from_estree(try!(field(node, "left")), false));
+            let rhs = try!(exp_from_estree(try!(field(node, "right"))));
+            Exp::AssignExp(op, Box::new(lhs), Box::new(rhs))
+        }
+        "AwaitExpression" => {
+            Exp::Await(Box::new(try!(exp_from_estree(try!(field(node, "argument"))))))
+        }
+        "BinaryExpression" | "LogicalExpression" => {
+            let op = try!(str_field(node, "operator"));
+            let op = match BinOp::from_op(op) {
+                Some(op) => op,
+                None => {
+                    return Err(JsError::unimplemented(&format!("ESTree operator `{}`", op)))
+                }
+            };
+
+            let left = try!(exp_from_estree(try!(field(node, "left"))));
+            let right = try!(exp_from_estree(try!(field(node, "right"))));
+            Exp::BinExp(Box::new(left), op, Box::new(right))
+        }
+        "CallExpression" | "NewExpression" => {
+            let callee = Box::new(try!(optional_from_estree(node, "callee")));
+            let args = try!(boxes_from_estree(node, "arguments"));
+
+            if try!(node_type(node)) == "CallExpression" {
+                Exp::Call(callee, args)
+            } else {
+                Exp::NewObject(callee, args)
+            }
+        }
+        "ChainExpression" => {
+            Exp::Chain(Box::new(try!(exp_from_estree(try!(field(node, "expression"))))))
+        }
+        "ClassExpression" => Exp::Class(Box::new(try!(class_from_estree(node)))),
+        "ConditionalExpression" => {
+            let test = try!(exp_from_estree(try!(field(node, "test"))));
+            let e1 = try!(exp_from_estree(try!(field(node, "consequent"))));
+            let e2 = try!(exp_from_estree(try!(field(node, "alternate"))));
+            Exp::Conditional(Box::new(test), Box::new(e1), Box::new(e2))
+        }
+        "ArrowFunctionExpression" | "FunctionExpression" => try!(function_from_estree(node)),
+        "Identifier" => {
+            match try!(str_field(node, "name")) {
+                "Infinity" if is_constant(node) => Exp::Float(::std::f64::INFINITY),
+                "NaN" if is_constant(node) => Exp::Float(::std::f64::NAN),
+                "undefined" if is_constant(node) => Exp::Undefined,
+                name if name == ERROR_NAME => Exp::Error,
+                name => Exp::Var(String::from(name)),
+            }
+        }
+        "Literal" => {
+            if let Some(regex) = node.get("regex") {
+                let pattern = String::from(try!(str_field(regex, "pattern")));
+                Exp::RegExp(pattern, String::from(try!(str_field(regex, "flags"))))
+            } else {
+                match *try!(field(node, "value")) {
+                    Value::Bool(b) => Exp::Bool(b),
+                    Value::Null => Exp::Null,
+                    Value::Number(ref n) => Exp::Float(n.as_f64().unwrap_or(::std::f64::NAN)),
+                    Value::String(ref s) => Exp::Str(s.clone()),
+                    _ => return Err(malformed(node, "value")),
+                }
+            }
+        }
+        "MemberExpression" => {
+            let obj = Box::new(try!(optional_from_estree(node, "object")));
+            let property = try!(field(node, "property"));
+
+            if try!(field(node, "computed")).as_bool().unwrap_or(false) {
+                Exp::KeyAccessor(obj, Box::new(try!(exp_from_estree(property))))
+            } else {
+                Exp::InstanceVar(obj, try!(name_from_estree(property)))
+            }
+        }
+        "PrivateIdentifier" => Exp::Var(try!(name_from_estree(node))),
+        "ObjectExpression" => {
+            let mut properties = Vec::new();
+
+            for prop in try!(array_field(node, "properties")) {
+                let typ = try!(node_type(prop));
+
+                if typ == "SpreadElement" {
+                    let e = try!(exp_from_estree(try!(field(prop, "argument"))));
+                    properties.push(Prop::Spread(Box::new(e)));
+                    continue;
+                }
+
+                if typ != "Property" {
+                    return Err(JsError::unimplemented(&format!("ESTree {}", typ)));
+                }
+
+                let kind = prop.get("kind").and_then(Value::as_str).unwrap_or("init");
+                let flag = |name| prop.get(name).and_then(Value::as_bool).unwrap_or(false);
+                let value = try!(field(prop, "value"));
+
+                let key = if flag("computed") {
+                    PropKey::Computed(Box::new(try!(exp_from_estree(try!(field(prop, "key"))))))
+                } else {
+                    PropKey::Name(try!(prop_key_from_estree(prop)))
+                };
+
+                let entry = match (kind, key) {
+                    ("get", key) | ("set", key) => {
+                        let (fn_kind, mut params, body) = try!(method_from_estree(value));
+
+                        match (kind, fn_kind, params.len()) {
+                            ("get", FnKind::Normal, 0) => Prop::Get(key, body),
+                            ("set", FnKind::Normal, 1) => Prop::Set(key, params.remove(0), body),
+                            _ => return Err(malformed(prop, "value")),
+                        }
+                    }
+                    ("init", key) if flag("method") => {
+                        let (fn_kind, params, body) = try!(method_from_estree(value));
+                        Prop::Method(fn_kind, key, params, body)
+                    }
+                    ("init", PropKey::Computed(key)) => {
+                        Prop::Computed(key, Box::new(try!(exp_from_estree(value))))
+                    }
+                    ("init", PropKey::Name(name)) => {
+                        let value = try!(exp_from_estree(value));
+
+                        if flag("shorthand") && *value.node() == Exp::Var(name.clone()) {
+                            Prop::Shorthand(name)
+                        } else {
+                            Prop::Value(name, Box::new(value))
+                        }
+                    }
+                    _ => return Err(malformed(prop, "kind")),
+                };
+
+                properties.push(entry);
+            }
+
+            Exp::Object(properties)
+        }
+        "SequenceExpression" => {
+            let mut exps = try!(boxes_from_estree(node, "expressions"));
+
+            if exps.is_empty() {
+                return Err(malformed(node, "expressions"));
+            }
+
+            let first = *exps.remove(0);
+            exps.into_iter().fold(first, |e1, e2| Exp::Comma(Box::new(e1), e2))
+        }
+        "Super" => Exp::Super,
+        "SpreadElement" => {
+            Exp::Spread(Box::new(try!(exp_from_estree(try!(field(node, "argument"))))))
+        }
+        "TaggedTemplateExpression" => {
+            let tag = Box::new(try!(exp_from_estree(try!(field(node, "tag")))));
+            Exp::TaggedTemplate(tag, try!(template_from_estree(try!(field(node, "quasi")))))
+        }
+        "TemplateLiteral" => Exp::Template(try!(template_from_estree(node))),
+        "ThisExpression" => Exp::This,
+        "UnaryExpression" => {
+            let argument = try!(field(node, "argument"));
+
+            match try!(str_field(node, "operator")) {
+                // A negative number
+                "-" if is_constant(node) => {
+                    match try!(exp_from_estree(argument)) {
+                        Exp::Float(f) => Exp::Float(-f),
+                        _ => return Err(malformed(node, "argument")),
+                    }
+                }
+                op => {
+                    let e = Box::new(try!(exp_from_estree(argument)));
+
+                    match op {
+                        "!" => Exp::LogNot(e),
+                        "+" => Exp::Pos(e),
+                        "-" => Exp::Neg(e),
+                        "~" => Exp::BitNot(e),
+                        "delete" => Exp::Delete(e),
+                        "typeof" => Exp::TypeOf(e),
+                        "void" => Exp::Void(e),
+                        op => {
+                            return Err(JsError::unimplemented(&format!("ESTree operator `{}`", op)))
+                        }
+                    }
+                }
+            }
+        }
+        "UpdateExpression" => {
+            let e = Box::new(try!(exp_from_estree(try!(field(node, "argument")))));
+            let prefix = try!(field(node, "prefix")).as_bool().unwrap_or(false);
+
+            match (try!(str_field(node, "operator")), prefix) {
+                ("++", true) => Exp::PreInc(e),
+                ("++", false) => Exp::PostInc(e),
+                ("--", true) => Exp::PreDec(e),
+                ("--", false) => Exp::PostDec(e),
+                _ => return Err(malformed(node, "operator")),
+            }
+        }
+        "YieldExpression" => {
+            let arg = match opt_field(node, "argument") {
+                Some(arg) => Some(Box::new(try!(exp_from_estree(arg)))),
+                None => None,
+            };
+
+            match (arg, node.get("delegate").and_then(Value::as_bool).unwrap_or(false)) {
+                (Some(e), true) => Exp::YieldAll(e),
+                (None, true) => return Err(malformed(node, "argument")),
+                (arg, false) => Exp::Yield(arg),
+            }
+        }
+        typ => return Err(JsError::unimplemented(&format!("ESTree {}", typ))),
+    };
+
+    let e = with_span(node, e, Exp::with_span);
+
+    match try!(comments_from_estree(node, false)) {
+        Some(comments) => Ok(e.with_comments(comments)),
+        None => Ok(e),
+    }
+}
+
+pub fn stmt_from_estree(node: &Value) -> Result<Stmt> {
+    let s = match try!(node_type(node)) {
+        "BlockStatement" => Stmt::Block(try!(block_from_estree(node))),
+        "BreakStatement" | "ContinueStatement" => {
+            let label = match opt_field(node, "label") {
+                Some(label) => Some(try!(identifier_from_estree(label))),
+                None => None,
+            };
+
+            if try!(node_type(node)) == "BreakStatement" {
+                Stmt::Break(label)
+            } else {
+                Stmt::Continue(label)
+            }
+        }
+        "ClassDeclaration" => Stmt::ClassDecl(try!(class_from_estree(node))),
+        "DoWhileStatement" => {
+            let body = try!(body_from_estree(try!(field(node, "body"))));
+            Stmt::DoWhile(body, try!(exp_from_estree(try!(field(node, "test")))))
+        }
+        "EmptyStatement" => Stmt::Empty,
+        "ExportAllDeclaration" => {
+            let name = match opt_field(node, "exported") {
+                Some(name) => Some(try!(module_name_from_estree(name))),
+                None => None,
+            };
+
+            Stmt::ExportAll(name, try!(source_from_estree(node)))
+        }
+        "ExportDefaultDeclaration" => {
+            let declaration = try!(field(node, "declaration"));
+
+            Stmt::ExportDefault(match try!(node_type(declaration)) {
+                "ClassDeclaration" => {
+                    let class = Exp::Class(Box::new(try!(class_from_estree(declaration))));
+                    with_span(declaration, class, Exp::with_span)
+                }
+                "FunctionDeclaration" => {
+                    let f = try!(function_from_estree(declaration));
+                    with_span(declaration, f, Exp::with_span)
+                }
+                _ => try!(exp_from_estree(declaration)),
+            })
+        }
+        "ExportNamedDeclaration" => {
+            if let Some(declaration) = opt_field(node, "declaration") {
+                Stmt::Export(Box::new(try!(stmt_from_estree(declaration))))
+            } else {
+                let mut names = Vec::new();
+
+                for spec in try!(array_field(node, "specifiers")) {
+                    let local = try!(module_name_from_estree(try!(field(spec, "local"))));
+                    let exported = try!(module_name_from_estree(try!(field(spec, "exported"))));
+                    names.push((local, exported));
+                }
+
+                let source = match opt_field(node, "source") {
+                    Some(_) => Some(try!(source_from_estree(node))),
+                    None => None,
+                };
+
+                Stmt::ExportNames(names, source)
+            }
+        }
+        "ExpressionStatement" => {
+            let e = try!(field(node, "expression"));
+
+            if is_plain_assign(e) {
+                try!(assign_from_estree(e))
+            } else {
+                match try!(exp_from_estree(e)) {
+                    ref e if matches!(*e.node(), Exp::Error) => Stmt::Error,
+                    e => Stmt::BareExp(e),
+                }
+            }
+        }
+        "ForStatement" => {
+            let init = match opt_field(node, "init") {
+                Some(init) => try!(for_init_from_estree(init)),
+                None => Stmt::Empty,
+            };
+
+            let cond = match opt_field(node, "test") {
+                Some(test) => try!(exp_from_estree(test)),
+                None => Exp::Bool(true),
+            };
+
+            let step = match opt_field(node, "update") {
+                Some(update) => try!(exp_from_estree(update)),
+                None => Exp::Undefined,
+            };
+
+            let body = try!(body_from_estree(try!(field(node, "body"))));
+            Stmt::For(Box::new(init), cond, step, body)
+        }
+        "ForInStatement" | "ForOfStatement" => {
+            let left = Box::new(try!(for_init_from_estree(try!(field(node, "left")))));
+            let right = try!(exp_from_estree(try!(field(node, "right"))));
+            let body = try!(body_from_estree(try!(field(node, "body"))));
+
+            if try!(node_type(node)) == "ForInStatement" {
+                Stmt::ForIn(left, right, body)
+            } else if node.get("await").and_then(Value::as_bool).unwrap_or(false) {
+                return Err(JsError::unimplemented("ESTree for await"));
+            } else {
+                Stmt::ForOf(left, right, body)
+            }
+        }
+        "FunctionDeclaration" => {
+            match try!(function_from_estree(node)) {
+                Exp::Defun(kind, Some(name), params, body) => {
+                    Stmt::FnDecl(kind, name, params, body)
+                }
+                _ => return Err(malformed(node, "id")),
+            }
+        }
+        "IfStatement" => {
+            let cond = try!(exp_from_estree(try!(field(node, "test"))));
+            let then_block = try!(body_from_estree(try!(field(node, "consequent"))));
+            let else_block = match opt_field(node, "alternate") {
+                Some(alternate) => try!(body_from_estree(alternate)),
+                None => Vec::new(),
+            };
+
+            Stmt::If(cond, then_block, else_block)
+        }
+        "ImportDeclaration" => {
+            let has_attributes = node.get("attributes")
+                                     .and_then(Value::as_array)
+                                     .map_or(false, |attributes| !attributes.is_empty());
+
+            if has_attributes {
+                return Err(JsError::unimplemented("ESTree import attributes"));
+            }
+
+            let mut specs = Vec::new();
+
+            for spec in try!(array_field(node, "specifiers")) {
+                let local = try!(identifier_from_estree(try!(field(spec, "local"))));
+
+                specs.push(match try!(node_type(spec)) {
+                    "ImportDefaultSpecifier" => ImportSpec::Default(local),
+                    "ImportNamespaceSpecifier" => ImportSpec::Namespace(local),
+                    "ImportSpecifier" => {
+                        let imported = try!(field(spec, "imported"));
+                        ImportSpec::Named(try!(module_name_from_estree(imported)), local)
+                    }
+                    _ => return Err(malformed(node, "specifiers")),
+                });
+            }
+
+            Stmt::Import(specs, try!(source_from_estree(node)))
+        }
+        "LabeledStatement" => {
+            let label = try!(identifier_from_estree(try!(field(node, "label"))));
+            Stmt::Labeled(label, Box::new(try!(stmt_from_estree(try!(field(node, "body"))))))
+        }
+        "ReturnStatement" => {
+            match opt_field(node, "argument") {
+                Some(argument) => Stmt::Ret(try!(exp_from_estree(argument))),
+                None => Stmt::Ret(Exp::Undefined),
+            }
+        }
+        "SwitchStatement" => {
+            let e = try!(exp_from_estree(try!(field(node, "discriminant"))));
+            let mut cases = Vec::new();
+
+            for case in try!(array_field(node, "cases")) {
+                let test = match opt_field(case, "test") {
+                    Some(test) => Some(try!(exp_from_estree(test))),
+                    None => None,
+                };
+
+                let consequent = try!(array_field(case, "consequent"));
+                let body = try!(consequent.iter().map(stmt_from_estree).collect::<Result<_>>());
+
+                cases.push((test, body));
+            }
+
+            Stmt::Switch(e, cases)
+        }
+        "ThrowStatement" => {
+            Stmt::Throw(Box::new(try!(exp_from_estree(try!(field(node, "argument"))))))
+        }
+        "TryStatement" => {
+            let try_block = try!(block_from_estree(try!(field(node, "block"))));
+            let (catch_var, catch_block) = match opt_field(node, "handler") {
+                Some(handler) => {
+                    let catch_var = match opt_field(handler, "param") {
+                        Some(param) => Some(try!(pattern_from_estree(param, true))),
+                        None => None,
+                    };
+
+                    (catch_var, try!(block_from_estree(try!(field(handler, "body")))))
+                }
+                None => (None, Vec::new()),
+            };
+
+            let finally_block = match opt_field(node, "finalizer") {
+                Some(finalizer) => try!(block_from_estree(finalizer)),
+                None => Vec::new(),
+            };
+
+            Stmt::Try(try_block, catch_var, catch_block, finally_block)
+        }
+        "VariableDeclaration" => {
+            let kind = match try!(str_field(node, "kind")) {
+                "var" => DeclKind::Var,
+                "let" => DeclKind::Let,
+                "const" => DeclKind::Const,
+                kind => return Err(JsError::unimplemented(&format!("ESTree {} declaration", kind))),
+            };
+
+            let mut decls = Vec::new();
+
+            for declarator in try!(array_field(node, "declarations")) {
+                let p = try!(pattern_from_estree(try!(field(declarator, "id")), true));
+                let init = match opt_field(declarator, "init") {
+                    Some(init) => Some(try!(exp_from_estree(init))),
+                    None => None,
+                };
+
+                decls.push((p, init));
+            }
+
+            Stmt::Decl(kind, decls)
+        }
+        "WhileStatement" => {
+            let cond = try!(exp_from_estree(try!(field(node, "test"))));
+            Stmt::While(cond, try!(body_from_estree(try!(field(node, "body")))))
+        }
+        typ => return Err(JsError::unimplemented(&format!("ESTree {}", typ))),
+    };
+
+    let s = with_span(node, s, Stmt::with_span);
+
+    match try!(comments_from_estree(node, true)) {
+        Some(comments) => Ok(s.with_comments(comments)),
+        None => Ok(s),
+    }
+}
+
+/// Converts a pattern. In a declaration or a parameter list (a `binding`) identifiers are names
+/// being declared, while the target of an assignment can be any expression.
+pub fn pattern_from_estree(node: &Value, binding: bool) -> Result<Pattern> {
+    let p = match try!(node_type(node)) {
+        "Identifier" if binding => Pattern::Var(try!(identifier_from_estree(node))),
+        "ArrayPattern" => {
+            let mut elems = Vec::new();
+
+            for elem in try!(array_field(node, "elements")) {
+                if elem.is_null() {
+                    elems.push(None);
+                } else {
+                    elems.push(Some(try!(pattern_from_estree(elem, binding))));
+                }
+            }
+
+            Pattern::Array(elems)
+        }
+        "AssignmentPattern" => {
+            let p = try!(pattern_from_estree(try!(field(node, "left")), binding));
+            Pattern::Default(Box::new(p), try!(exp_from_estree(try!(field(node, "right")))))
+        }
+        "ObjectPattern" => {
+            let mut props = Vec::new();
+            let mut rest = None;
+
+            for prop in try!(array_field(node, "properties")) {
+                if try!(node_type(prop)) == "RestElement" {
+                    let argument = try!(field(prop, "argument"));
+                    rest = Some(Box::new(try!(pattern_from_estree(argument, binding))));
+                    continue;
+                }
+
+                if prop.get("computed").and_then(Value::as_bool).unwrap_or(false) {
+                    return Err(JsError::unimplemented("ESTree computed property"));
+                }
+
+                let key = try!(prop_key_from_estree(prop));
+                props.push((key, try!(pattern_from_estree(try!(field(prop, "value")), binding))));
+            }
+
+            Pattern::Object(props, rest)
+        }
+        "RestElement" => {
+            let argument = try!(field(node, "argument"));
+            Pattern::Rest(Box::new(try!(pattern_from_estree(argument, binding))))
+        }
+        typ if binding => {
+            return Err(JsError::unimplemented(&format!("ESTree {} in place of a pattern", typ)))
+        }
+        _ => Pattern::from(try!(exp_from_estree(node))),
+    };
+
+    Ok(p)
+}
+
+/// Reads an ESTree `Program`, which is a module if its `sourceType` says so and a script otherwise.
+pub fn program_from_estree(node: &Value) -> Result<Program> {
+    match try!(node_type(node)) {
+        "Program" => {
+            let body = try!(array_field(node, "body"));
+            let stmts = try!(body.iter().map(stmt_from_estree).collect());
+
+            if node.get("sourceType") == Some(&Value::from("module")) {
+                Ok(Program::Module(stmts))
+            } else {
+                Ok(Program::Script(stmts))
+            }
+        }
+        typ => Err(JsError::ParseError(format!("expected an ESTree Program, found {}", typ))),
+    }
+}
+
+fn node_type(node: &Value) -> Result<&str> {
+    match node.get("type").and_then(Value::as_str) {
+        Some(typ) => Ok(typ),
+        None => Err(JsError::ParseError(format!("ESTree node has no type: {}", node))),
+    }
+}
+
+fn malformed(node: &Value, name: &str) -> JsError {
+    let typ = node.get("type").and_then(Value::as_str).unwrap_or("node");
+    JsError::ParseError(format!("ESTree {} has a missing or invalid `{}`", typ, name))
+}
+
+fn field<'a>(node: &'a Value, name: &str) -> Result<&'a Value> {
+    node.get(name).ok_or_else(|| malformed(node, name))
+}
+
+// A field that may be left out or set to `null`.
+fn opt_field<'a>(node: &'a Value, name: &str) -> Option<&'a Value> {
+    node.get(name).and_then(|v| if v.is_null() { None } else { Some(v) })
+}
+
+fn str_field<'a>(node: &'a Value, name: &str) -> Result<&'a str> {
+    try!(field(node, name)).as_str().ok_or_else(|| malformed(node, name))
+}
+
+fn array_field<'a>(node: &'a Value, name: &str) -> Result<&'a Vec<Value>> {
+    try!(field(node, name)).as_array().ok_or_else(|| malformed(node, name))
+}
+
+fn with_span<T, F>(node: &Value, t: T, f: F) -> T where F: Fn(T, Span) -> T {
+    let start = match node.get("loc").and_then(|loc| loc.get("start")) {
+        Some(start) => start,
+        None => return t,
+    };
+
+    let number = |v: Option<&Value>| v.and_then(Value::as_u64).map(|n| n as usize);
+    let (line, column) = match (number(start.get("line")), number(start.get("column"))) {
+        (Some(line), Some(column)) => (line, column + 1),
+        _ => return t,
+    };
+
+    // Tools that don't add `range` put the offsets directly on the node
+    let range = node.get("range").and_then(Value::as_array);
+    let offset = number(range.and_then(|r| r.get(0)).or(node.get("start"))).unwrap_or(0);
+    let end = number(range.and_then(|r| r.get(1)).or(node.get("end"))).unwrap_or(offset);
+    let file = number(node["loc"].get("file")).unwrap_or(0);
+
+    f(t, Span::new(file, offset, line, column, end.saturating_sub(offset)))
+}
+
+// The comments on a node, laid out as described at the top of this file.
+fn comments_from_estree(node: &Value, statement: bool) -> Result<Option<Comments>> {
+    let lines_before = if statement { 1 } else { 0 };
+    let mut comments = Comments { lines_before: lines_before, ..Comments::default() };
+    let mut after_line_comment = false;
+
+    for &(name, is_leading) in &[("leadingComments", true), ("trailingComments", false)] {
+        let list = match node.get(name) {
+            Some(list) if !list.is_null() => list,
+            _ => continue,
+        };
+
+        let list = try!(list.as_array().ok_or_else(|| malformed(node, name)));
+
+        for c in list {
+            let block = match try!(node_type(c)) {
+                "CommentBlock" | "Block" => true,
+                "CommentLine" | "Line" => false,
+                typ => return Err(JsError::unimplemented(&format!("ESTree {} comment", typ))),
+            };
+
+            let lines_before = if (is_leading && statement) || after_line_comment { 1 } else { 0 };
+            after_line_comment = !block;
+
+            let comment = Comment {
+                text: String::from(try!(str_field(c, "value"))),
+                block: block,
+                lines_before: lines_before,
+            };
+
+            if is_leading {
+                comments.leading.push(comment);
+            } else {
+                comments.trailing.push(comment);
+            }
+        }
+
+        if is_leading && after_line_comment {
+            comments.lines_before = 1;
+            after_line_comment = false;
+        }
+    }
+
+    if comments.leading.is_empty() && comments.trailing.is_empty() {
+        Ok(None)
+    } else {
+        Ok(Some(comments))
+    }
+}
+
+fn identifier_from_estree(node: &Value) -> Result<String> {
+    match try!(node_type(node)) {
+        "Identifier" => str_field(node, "name").map(String::from),
+        typ => Err(JsError::unimplemented(&format!("ESTree {} in place of an identifier", typ))),
+    }
+}
+
+// The object or callee `name` of a member access or call, wrapped in `Exp::Optional` if the node is
+// marked as `optional`.
+fn optional_from_estree(node: &Value, name: &str) -> Result<Exp> {
+    let e = try!(exp_from_estree(try!(field(node, name))));
+
+    if node.get("optional").and_then(Value::as_bool).unwrap_or(false) {
+        Ok(Exp::Optional(Box::new(e)))
+    } else {
+        Ok(e)
+    }
+}
+
+// The name of an import or export, which is either an identifier or a string literal.
+fn module_name_from_estree(node: &Value) -> Result<String> {
+    match try!(node_type(node)) {
+        "Literal" => {
+            node.get("value").and_then(Value::as_str).map(String::from)
+                .ok_or_else(|| malformed(node, "value"))
+        }
+        _ => identifier_from_estree(node),
+    }
+}
+
+// The module specifier of an import or re-export.
+fn source_from_estree(node: &Value) -> Result<String> {
+    try!(field(node, "source")).get("value").and_then(Value::as_str).map(String::from)
+        .ok_or_else(|| malformed(node, "source"))
+}
+
+// An identifier, or a private name, which keeps its `#`.
+fn name_from_estree(node: &Value) -> Result<String> {
+    match try!(node_type(node)) {
+        "PrivateIdentifier" => Ok(format!("#{}", try!(str_field(node, "name")))),
+        _ => identifier_from_estree(node),
+    }
+}
+
+fn class_from_estree(node: &Value) -> Result<Class> {
+    let name = match opt_field(node, "id") {
+        Some(id) => Some(try!(identifier_from_estree(id))),
+        None => None,
+    };
+
+    let extends = match opt_field(node, "superClass") {
+        Some(base) => Some(Box::new(try!(exp_from_estree(base)))),
+        None => None,
+    };
+
+    let mut members = Vec::new();
+
+    for member in try!(array_field(try!(field(node, "body")), "body")) {
+        let typ = try!(node_type(member));
+
+        if typ != "MethodDefinition" && typ != "PropertyDefinition" {
+            return Err(JsError::unimplemented(&format!("ESTree {}", typ)));
+        }
+
+        let flag = |name| member.get(name).and_then(Value::as_bool).unwrap_or(false);
+        let key_node = try!(field(member, "key"));
+
+        let key = if flag("computed") {
+            ClassKey::Computed(Box::new(try!(exp_from_estree(key_node))))
+        } else if try!(node_type(key_node)) == "PrivateIdentifier" {
+            ClassKey::Private(String::from(try!(str_field(key_node, "name"))))
+        } else {
+            ClassKey::Name(try!(prop_key_from_estree(member)))
+        };
+
+        let kind = if typ == "PropertyDefinition" {
+            match opt_field(member, "value") {
+                Some(value) => MemberKind::Field(Some(try!(exp_from_estree(value)))),
+                None => MemberKind::Field(None),
+            }
+        } else {
+            let value = try!(field(member, "value"));
+            let (fn_kind, mut params, body) = try!(method_from_estree(value));
+
+            match (try!(str_field(member, "kind")), fn_kind, params.len()) {
+                ("constructor", FnKind::Normal, _) | ("method", _, _) => {
+                    MemberKind::Method(fn_kind, params, body)
+                }
+                ("get", FnKind::Normal, 0) => MemberKind::Get(body),
+                ("set", FnKind::Normal, 1) => MemberKind::Set(params.remove(0), body),
+                _ => return Err(malformed(member, "kind")),
+            }
+        };
+
+        members.push(ClassMember { is_static: flag("static"), key: key, kind: kind });
+    }
+
+    Ok(Class { name: name, extends: extends, members: members })
+}
+
+// The key of a non-computed property, which is either an identifier or a literal.
+fn prop_key_from_estree(prop: &Value) -> Result<String> {
+    let key = try!(field(prop, "key"));
+
+    if key.get("type") == Some(&Value::from("Identifier")) {
+        return identifier_from_estree(key);
+    }
+
+    match key.get("value") {
+        Some(&Value::String(ref s)) => Ok(s.clone()),
+        Some(&Value::Number(ref n)) => Ok(format!("{}", n.as_f64().unwrap_or(0.0))),
+        _ => Err(malformed(prop, "key")),
+    }
+}
+
+// A list of expressions, which unlike the elements of an array can't have holes.
+fn boxes_from_estree(node: &Value, name: &str) -> Result<Vec<Box<Exp>>> {
+    try!(array_field(node, name)).iter().map(|e| {
+        if e.is_null() {
+            return Err(malformed(node, name));
+        }
+
+        exp_from_estree(e).map(Box::new)
+    }).collect()
+}
+
+fn function_from_estree(node: &Value) -> Result<Exp> {
+    let flag = |name| node.get(name).and_then(Value::as_bool).unwrap_or(false);
+    let kind = FnKind::new(flag("async"), flag("generator"));
+
+    let name = match opt_field(node, "id") {
+        Some(id) => Some(try!(identifier_from_estree(id))),
+        None => None,
+    };
+
+    let params = try!(try!(array_field(node, "params")).iter()
+                                                       .map(|p| pattern_from_estree(p, true))
+                                                       .collect::<Result<Vec<_>>>());
+
+    let body = try!(field(node, "body"));
+
+    if try!(node_type(node)) == "ArrowFunctionExpression" {
+        if kind.is_generator() {
+            return Err(malformed(node, "generator"));
+        }
+
+        let body = if try!(node_type(body)) == "BlockStatement" {
+            ArrowBody::Block(try!(block_from_estree(body)))
+        } else {
+            ArrowBody::Exp(Box::new(try!(exp_from_estree(body))))
+        };
+
+        return Ok(Exp::Arrow(kind, params, body));
+    }
+
+    Ok(Exp::Defun(kind, name, params, try!(block_from_estree(body))))
+}
+
+fn template_from_estree(node: &Value) -> Result<Template> {
+    let exps = try!(boxes_from_estree(node, "expressions"));
+    let mut quasis = Vec::new();
+
+    for quasi in try!(array_field(node, "quasis")) {
+        let value = try!(field(quasi, "value"));
+
+        quasis.push(TemplatePart {
+            cooked: value.get("cooked").and_then(Value::as_str).map(String::from),
+            raw: String::from(try!(str_field(value, "raw"))),
+        });
+    }
+
+    if quasis.len() != exps.len() + 1 {
+        return Err(malformed(node, "quasis"));
+    }
+
+    Ok(Template { quasis: quasis, exps: exps })
+}
+
+// The kind, parameters and body of a method, getter or setter, which ESTree gives as a function.
+fn method_from_estree(node: &Value) -> Result<(FnKind, Vec<Pattern>, Vec<Stmt>)> {
+    match try!(function_from_estree(node)) {
+        Exp::Defun(kind, _, params, body) => Ok((kind, params, body)),
+        _ => Err(malformed(node, "type")),
+    }
+}
+
+// Plain assignments in statement position are read as `Stmt::Assign`, and other assignments as
+// expressions.
+fn is_plain_assign(node: &Value) -> bool {
+    node.get("type") == Some(&Value::from("AssignmentExpression")) &&
+    node.get("operator") == Some(&Value::from("="))
+}
+
+fn assign_from_estree(node: &Value) -> Result<Stmt> {
+    let lhs = try!(pattern_from_estree(try!(field(node, "left")), false));
+    let rhs = try!(exp_from_estree(try!(field(node, "right"))));
+    Ok(with_span(node, Stmt::Assign(lhs, rhs), Stmt::with_span))
+}
+
+fn is_declaration(node: &Value) -> bool {
+    node.get("type") == Some(&Value::from("VariableDeclaration"))
+}
+
+fn block_from_estree(node: &Value) -> Result<Vec<Stmt>> {
+    match try!(node_type(node)) {
+        "BlockStatement" => {
+            try!(array_field(node, "body")).iter().map(stmt_from_estree).collect()
+        }
+        _ => Err(malformed(node, "body")),
+    }
+}
+
+// The initialization of a for loop, or the left side of a for-in or for-of loop.
+fn for_init_from_estree(node: &Value) -> Result<Stmt> {
+    if is_declaration(node) {
+        stmt_from_estree(node)
+    } else if is_plain_assign(node) {
+        assign_from_estree(node)
+    } else {
+        // The span of an expression is taken for the statement holding it
+        match try!(exp_from_estree(node)) {
+            Exp::Spanned(span, e) => Ok(Stmt::BareExp(*e).with_span(span)),
+            e => Ok(Stmt::BareExp(e)),
+        }
+    }
+}
+
+// The body of a loop or a branch of an `if`, which needn't be a block.
+fn body_from_estree(node: &Value) -> Result<Vec<Stmt>> {
+    match try!(node_type(node)) {
+        "BlockStatement" => block_from_estree(node),
+        _ => Ok(vec![try!(stmt_from_estree(node))]),
+    }
+}
@@ -2,6 +2,7 @@ use std::fmt;
 use std::result;
 use types::js_var::{JsVar, JsPtrEnum};
 use gc_error::GcError;
+use span::Span;
 
 
 #[derive(Debug)]
@@ -31,6 +32,18 @@ impl JsError {
         JsError::ReferenceError(format!("{} is not defined", binding))
     }
 
+    /// Appends the source location `span` to the error's message, e.g.
+    /// `ReferenceError: x is not defined (at 3:14)`. Errors without a message are left untouched.
+    pub fn at(self, span: &Span) -> JsError {
+        match self {
+            JsError::ParseError(s) => JsError::ParseError(format!("{} (at {})", s, span)),
+            JsError::TypeError(s) => JsError::TypeError(format!("{} (at {})", s, span)),
+            JsError::ReferenceError(s) => JsError::ReferenceError(format!("{} (at {})", s, span)),
+            JsError::SyntaxError(s) => JsError::SyntaxError(format!("{} (at {})", s, span)),
+            e => e,
+        }
+    }
+
     /// Meta errors are problems with the interpreter -- parsing, gc, or unimplemented methods.
     pub fn is_meta_error(&self) -> bool {
         match self {
@@ -0,0 +1,810 @@
+// Splits JavaScript source into tokens for the parser. Whether a `/` starts a regex or is a
+// division, and whether a `}` closes a block or continues a template literal, depends on the
+// grammar, so the lexer always reads them as punctuators and the parser asks for them to be read
+// again with `rescan_regex` and `rescan_template` where it expects a regex or the rest of a
+// template.
+
+use std::char;
+
+use unicode_id_start;
+
+use ast::{Comment, TemplatePart};
+use js_error::{JsError, Result};
+use span::Span;
+use types::js_regexp::JsRegExpStruct;
+
+// Longest first, so that e.g. `>>>=` isn't read as `>>` followed by `>=`.
+const PUNCTUATORS: &'static [&'static str] = &[
+    ">>>=", "...", "===", "!==", "**=", "<<=", ">>=", ">>>", "&&=", "||=", "??=", "=>", "==", "!=",
+    "<=", ">=", "&&", "||", "??", "?.", "++", "--", "+=", "-=", "*=", "/=", "%=", "&=", "|=", "^=",
+    "<<", ">>", "**", "{", "}", "(", ")", "[", "]", ";", ",", "<", ">", "+", "-", "*", "/", "%",
+    "&", "|", "^", "!", "~", "?", ":", ".", "=",
+];
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Token {
+    // An identifier or a keyword, with any escapes in it decoded
+    Ident(String),
+    // #name, without the `#`
+    PrivateName(String),
+    Num(f64),
+    Str(String),
+    // A piece of a template literal, and whether it's the last piece, i.e. whether it ends with a
+    // backtick rather than `${`
+    Template(TemplatePart, bool),
+    // /pattern/flags
+    RegExp(String, String),
+    Punct(&'static str),
+    Eof,
+}
+
+#[derive(Clone, Debug)]
+pub struct Lexeme {
+    pub token: Token,
+    pub span: Span,
+    // Whether there's a line break between this token and the one before it, which automatic
+    // semicolon insertion depends on.
+    pub newline_before: bool,
+    // The comments between this token and the one before it, if the lexer keeps them
+    pub comments: Vec<Comment>,
+    // How many line breaks there are between this token and the last comment or token before it
+    pub lines_before: usize,
+}
+
+#[derive(Clone)]
+pub struct Lexer<'a> {
+    source: &'a str,
+    file: usize,
+    offset: usize,
+    line: usize,
+    column: usize,
+    keep_comments: bool,
+}
+
+impl<'a> Lexer<'a> {
+    pub fn new(source: &'a str, file: usize) -> Lexer<'a> {
+        Lexer { source: source, file: file, offset: 0, line: 1, column: 1, keep_comments: false }
+    }
+
+    /// Keeps the comments skipped before each token in its `comments`.
+    pub fn with_comments(mut self) -> Lexer<'a> {
+        self.keep_comments = true;
+        self
+    }
+
+    /// Reads the next token, skipping any whitespace and comments before it.
+    pub fn next_token(&mut self) -> Result<Lexeme> {
+        let (newline_before, comments, lines_before) = try!(self.skip_trivia());
+        let start = self.here();
+
+        let token = match self.peek() {
+            None => Token::Eof,
+            Some(c) if c == '"' || c == '\'' => try!(self.string(c)),
+            Some('`') => {
+                self.bump();
+                try!(self.template(start))
+            }
+            Some('#') => {
+                self.bump();
+
+                match self.peek() {
+                    Some(c) if is_id_start(c) || c == '\\' => {
+                        Token::PrivateName(try!(self.ident()))
+                    }
+                    _ => return Err(self.error("Invalid or unexpected token", &start)),
+                }
+            }
+            Some(c) if c.is_digit(10) => try!(self.number()),
+            Some('.') if self.peek_at(1).map_or(false, |c| c.is_digit(10)) => try!(self.number()),
+            Some(c) if is_id_start(c) || c == '\\' => Token::Ident(try!(self.ident())),
+            Some(_) => {
+                let rest = &self.source[self.offset..];
+                let punct = PUNCTUATORS.iter().find(|p| {
+                    // `a?.5:b` is a conditional, not an optional chain
+                    rest.starts_with(*p) &&
+                        !(**p == "?." && rest[2..].chars().next().map_or(false, |c| c.is_digit(10)))
+                });
+
+                match punct {
+                    Some(p) => {
+                        for _ in 0..p.len() {
+                            self.bump();
+                        }
+
+                        Token::Punct(p)
+                    }
+                    None => return Err(self.error("Invalid or unexpected token", &start)),
+                }
+            }
+        };
+
+        let lexeme = self.lexeme(token, start, newline_before);
+        Ok(Lexeme { comments: comments, lines_before: lines_before, ..lexeme })
+    }
+
+    /// Reads the `/` or `/=` token `slash` again as the start of a regex literal.
+    pub fn rescan_regex(&mut self, slash: &Lexeme) -> Result<Lexeme> {
+        self.reset(&slash.span);
+        let start = self.here();
+        self.bump();
+
+        let mut in_class = false;
+
+        loop {
+            match self.bump() {
+                Some('\\') => {
+                    match self.bump() {
+                        Some(c) if !is_line_terminator(c) => (),
+                        _ => return Err(self.error("Unterminated regular expression", &start)),
+                    }
+                }
+                Some('[') => in_class = true,
+                Some(']') => in_class = false,
+                Some('/') if !in_class => break,
+                Some(c) if !is_line_terminator(c) => (),
+                _ => return Err(self.error("Unterminated regular expression", &start)),
+            }
+        }
+
+        let pattern = String::from(&self.source[start.offset + 1..self.offset - 1]);
+        let flags_start = self.offset;
+
+        while self.peek().map_or(false, is_id_part) {
+            self.bump();
+        }
+
+        let flags = String::from(&self.source[flags_start..self.offset]);
+
+        // The pattern is compiled now so that a bad one is an early error, as it is in engines
+        if let Err(JsError::SyntaxError(msg)) = JsRegExpStruct::new(&pattern, &flags) {
+            return Err(JsError::SyntaxError(msg).at(&start));
+        }
+
+        let lexeme = self.lexeme(Token::RegExp(pattern, flags), start, slash.newline_before);
+        Ok(Lexeme { comments: slash.comments.clone(), lines_before: slash.lines_before, ..lexeme })
+    }
+
+    /// Reads the `}` token `brace` again as the end of a substitution in a template literal,
+    /// followed by the next piece of the template.
+    pub fn rescan_template(&mut self, brace: &Lexeme) -> Result<Lexeme> {
+        self.reset(&brace.span);
+        let start = self.here();
+        self.bump();
+
+        let token = try!(self.template(start));
+        let lexeme = self.lexeme(token, start, brace.newline_before);
+        Ok(Lexeme { comments: brace.comments.clone(), lines_before: brace.lines_before, ..lexeme })
+    }
+
+    /// Skips a character, which after an error is where reading can carry on past it.
+    pub fn skip_char(&mut self) {
+        self.bump();
+    }
+
+    fn lexeme(&self, token: Token, start: Span, newline_before: bool) -> Lexeme {
+        Lexeme {
+            token: token,
+            span: Span { len: self.offset - start.offset, ..start },
+            newline_before: newline_before,
+            comments: Vec::new(),
+            lines_before: 0,
+        }
+    }
+
+    fn here(&self) -> Span {
+        Span::new(self.file, self.offset, self.line, self.column, 0)
+    }
+
+    fn reset(&mut self, span: &Span) {
+        self.offset = span.offset;
+        self.line = span.line;
+        self.column = span.column;
+    }
+
+    fn error(&self, msg: &str, span: &Span) -> JsError {
+        JsError::SyntaxError(String::from(msg)).at(span)
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.source[self.offset..].chars().next()
+    }
+
+    fn peek_at(&self, n: usize) -> Option<char> {
+        self.source[self.offset..].chars().nth(n)
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = match self.peek() {
+            Some(c) => c,
+            None => return None,
+        };
+
+        self.offset += c.len_utf8();
+
+        // `\r\n` is a single line break, which is counted at the `\n`
+        if is_line_terminator(c) && !(c == '\r' && self.peek() == Some('\n')) {
+            self.line += 1;
+            self.column = 1;
+        } else {
+            self.column += c.len_utf16();
+        }
+
+        Some(c)
+    }
+
+    // Skips whitespace and comments, returning whether there was a line break among them, the
+    // comments if they're kept, and how many line breaks there are after the last comment.
+    fn skip_trivia(&mut self) -> Result<(bool, Vec<Comment>, usize)> {
+        let mut newline = false;
+        let mut comments = Vec::new();
+        // The line that the last comment or token ends on
+        let mut last_line = self.line;
+
+        // A hashbang line at the very start is treated as a comment
+        if self.offset == 0 && self.source.starts_with("#!") {
+            self.skip_line();
+        }
+
+        loop {
+            let start = self.here();
+
+            let block = match self.peek() {
+                Some(c) if is_line_terminator(c) => {
+                    newline = true;
+                    self.bump();
+                    continue;
+                }
+                Some(c) if is_whitespace(c) => {
+                    self.bump();
+                    continue;
+                }
+                Some('/') if self.peek_at(1) == Some('/') => {
+                    self.skip_line();
+                    false
+                }
+                Some('/') if self.peek_at(1) == Some('*') => {
+                    self.bump();
+                    self.bump();
+
+                    loop {
+                        match self.bump() {
+                            Some('*') if self.peek() == Some('/') => {
+                                self.bump();
+                                break;
+                            }
+                            Some(c) if is_line_terminator(c) => newline = true,
+                            Some(_) => (),
+                            None => return Err(self.error("Unterminated comment", &start)),
+                        }
+                    }
+
+                    true
+                }
+                _ => return Ok((newline, comments, self.line - last_line)),
+            };
+
+            if self.keep_comments {
+                let end = if block { self.offset - 2 } else { self.offset };
+
+                comments.push(Comment {
+                    text: String::from(&self.source[start.offset + 2..end]),
+                    block: block,
+                    lines_before: start.line - last_line,
+                });
+            }
+
+            last_line = self.line;
+        }
+    }
+
+    fn skip_line(&mut self) {
+        while self.peek().map_or(false, |c| !is_line_terminator(c)) {
+            self.bump();
+        }
+    }
+
+    fn ident(&mut self) -> Result<String> {
+        let mut name = String::new();
+
+        loop {
+            let start = self.here();
+
+            let c = match self.peek() {
+                Some('\\') => {
+                    self.bump();
+
+                    if self.bump() != Some('u') {
+                        return Err(self.error("Invalid Unicode escape sequence", &start));
+                    }
+
+                    match self.unicode_escape() {
+                        Some(c) => c,
+                        None => return Err(self.error("Invalid Unicode escape sequence", &start)),
+                    }
+                }
+                Some(c) if is_id_part(c) => {
+                    self.bump();
+                    c
+                }
+                _ => return Ok(name),
+            };
+
+            let valid = if name.is_empty() { is_id_start(c) } else { is_id_part(c) };
+
+            if !valid {
+                return Err(self.error("Invalid Unicode escape sequence", &start));
+            }
+
+            name.push(c);
+        }
+    }
+
+    fn number(&mut self) -> Result<Token> {
+        let start = self.here();
+
+        let radix = match (self.peek(), self.peek_at(1)) {
+            (Some('0'), Some('x')) | (Some('0'), Some('X')) => 16,
+            (Some('0'), Some('o')) | (Some('0'), Some('O')) => 8,
+            (Some('0'), Some('b')) | (Some('0'), Some('B')) => 2,
+            _ => 10,
+        };
+
+        let n = if radix != 10 {
+            self.bump();
+            self.bump();
+
+            let digits = self.digits(radix);
+
+            if digits.is_empty() {
+                return Err(self.error("Invalid or unexpected token", &start));
+            }
+
+            digits.chars().fold(0.0, |n, d| n * radix as f64 + d.to_digit(radix).unwrap() as f64)
+        } else {
+            let mut text = self.digits(10);
+
+            // Legacy octal literals, e.g. `017`, which are decimal if they have an 8 or a 9
+            let octal = text.len() > 1 && text.starts_with('0') &&
+                        text.chars().all(|c| c.is_digit(8));
+
+            if octal {
+                text.chars().fold(0.0, |n, d| n * 8.0 + d.to_digit(8).unwrap() as f64)
+            } else {
+                if self.peek() == Some('.') {
+                    self.bump();
+                    text.push('.');
+                    text.push_str(&self.digits(10));
+                }
+
+                if self.peek() == Some('e') || self.peek() == Some('E') {
+                    self.bump();
+                    text.push('e');
+
+                    if let Some(sign) = self.peek().filter(|&c| c == '+' || c == '-') {
+                        self.bump();
+                        text.push(sign);
+                    }
+
+                    let exponent = self.digits(10);
+
+                    if exponent.is_empty() {
+                        return Err(self.error("Invalid or unexpected token", &start));
+                    }
+
+                    text.push_str(&exponent);
+                }
+
+                text.parse().unwrap_or(::std::f64::NAN)
+            }
+        };
+
+        match self.peek() {
+            Some('n') => Err(JsError::unimplemented("BigInt literals")),
+            Some(c) if is_id_start(c) || c.is_digit(10) || c == '\\' => {
+                Err(self.error("Invalid or unexpected token", &start))
+            }
+            _ => Ok(Token::Num(n)),
+        }
+    }
+
+    // Reads digits in the given radix, leaving out any `_` separators between them.
+    fn digits(&mut self, radix: u32) -> String {
+        let mut digits = String::new();
+
+        loop {
+            match self.peek() {
+                Some(c) if c.is_digit(radix) => digits.push(c),
+                Some('_') if !digits.is_empty() &&
+                             self.peek_at(1).map_or(false, |c| c.is_digit(radix)) => (),
+                _ => return digits,
+            }
+
+            self.bump();
+        }
+    }
+
+    fn string(&mut self, quote: char) -> Result<Token> {
+        let start = self.here();
+        let mut s = String::new();
+        self.bump();
+
+        loop {
+            match self.peek() {
+                Some(c) if c == quote => {
+                    self.bump();
+                    return Ok(Token::Str(s));
+                }
+                Some('\\') => {
+                    let escape = self.here();
+
+                    match self.escape(false) {
+                        Some(text) => s.push_str(&text),
+                        None => return Err(self.error("Invalid escape sequence", &escape)),
+                    }
+                }
+                // Unlike other line terminators, U+2028 and U+2029 are allowed in strings
+                Some(c) if c != '\n' && c != '\r' => {
+                    self.bump();
+                    s.push(c);
+                }
+                _ => return Err(self.error("Unterminated string constant", &start)),
+            }
+        }
+    }
+
+    // Reads the rest of a piece of a template literal, after the opening backtick or the `}` of a
+    // substitution.
+    fn template(&mut self, start: Span) -> Result<Token> {
+        let mut cooked = Some(String::new());
+        let mut raw = String::new();
+
+        loop {
+            let piece_start = self.offset;
+
+            let tail = match self.peek() {
+                Some('`') => true,
+                Some('$') if self.peek_at(1) == Some('{') => false,
+                Some('\\') => {
+                    let text = self.escape(true);
+                    raw.push_str(&self.source[piece_start..self.offset]);
+
+                    cooked = match (cooked, text) {
+                        (Some(mut s), Some(text)) => {
+                            s.push_str(&text);
+                            Some(s)
+                        }
+                        _ => None,
+                    };
+
+                    continue;
+                }
+                Some(c) => {
+                    self.bump();
+
+                    // Line breaks are normalized to `\n` in both the raw and the cooked text
+                    let c = if c == '\r' {
+                        if self.peek() == Some('\n') {
+                            self.bump();
+                        }
+
+                        '\n'
+                    } else {
+                        c
+                    };
+
+                    raw.push(c);
+
+                    if let Some(ref mut s) = cooked {
+                        s.push(c);
+                    }
+
+                    continue;
+                }
+                None => return Err(self.error("Unterminated template literal", &start)),
+            };
+
+            self.bump();
+
+            if !tail {
+                self.bump();
+            }
+
+            return Ok(Token::Template(TemplatePart { cooked: cooked, raw: raw }, tail));
+        }
+    }
+
+    // Reads an escape sequence in a string or template, returning the text it stands for, or
+    // `None` if it isn't valid. A template doesn't allow the legacy octal escapes that a string
+    // does.
+    fn escape(&mut self, template: bool) -> Option<String> {
+        self.bump();
+
+        let c = match self.bump() {
+            Some(c) => c,
+            None => return None,
+        };
+
+        let escaped = match c {
+            'b' => '\u{8}',
+            'f' => '\u{c}',
+            'n' => '\n',
+            'r' => '\r',
+            't' => '\t',
+            'v' => '\u{b}',
+            'x' => {
+                let hex = self.hex_digits(2);
+                match hex.and_then(char::from_u32) {
+                    Some(c) => c,
+                    None => return None,
+                }
+            }
+            'u' => {
+                match self.unicode_escape() {
+                    Some(c) => c,
+                    None => return None,
+                }
+            }
+            // A line continuation, which stands for nothing
+            '\r' => {
+                if self.peek() == Some('\n') {
+                    self.bump();
+                }
+
+                return Some(String::new());
+            }
+            c if is_line_terminator(c) => return Some(String::new()),
+            '0' if !self.peek().map_or(false, |c| c.is_digit(10)) => '\0',
+            '0'..='7' if !template => {
+                // Up to three octal digits, as long as the value fits in a byte
+                let mut n = c.to_digit(8).unwrap();
+                let max_len = if c <= '3' { 3 } else { 2 };
+
+                for _ in 1..max_len {
+                    match self.peek().and_then(|c| c.to_digit(8)) {
+                        Some(d) => {
+                            self.bump();
+                            n = n * 8 + d;
+                        }
+                        None => break,
+                    }
+                }
+
+                char::from_u32(n).unwrap()
+            }
+            // `\0` followed by a digit would be octal, which a template doesn't allow either
+            '0'..='9' if template => return None,
+            c => c,
+        };
+
+        Some(escaped.to_string())
+    }
+
+    // Reads the rest of a `\u` escape, either `XXXX` or `{X...}`. A surrogate pair written as two
+    // escapes is combined into one character, and a lone surrogate, which a Rust string can't
+    // hold, becomes U+FFFD.
+    fn unicode_escape(&mut self) -> Option<char> {
+        let n = if self.peek() == Some('{') {
+            self.bump();
+            let mut n = 0;
+            let mut len = 0;
+
+            while let Some(d) = self.peek().and_then(|c| c.to_digit(16)) {
+                self.bump();
+                n = n * 16 + d;
+                len += 1;
+
+                if n > 0x10ffff {
+                    return None;
+                }
+            }
+
+            if len == 0 || self.bump() != Some('}') {
+                return None;
+            }
+
+            n
+        } else {
+            match self.hex_digits(4) {
+                Some(n) => n,
+                None => return None,
+            }
+        };
+
+        if n >= 0xd800 && n < 0xdc00 && self.source[self.offset..].starts_with("\\u") {
+            let mut after = self.clone();
+            after.bump();
+            after.bump();
+
+            if let Some(low) = after.hex_digits(4).filter(|&low| low >= 0xdc00 && low < 0xe000) {
+                *self = after;
+                return char::from_u32(0x10000 + ((n - 0xd800) << 10) + (low - 0xdc00));
+            }
+        }
+
+        Some(char::from_u32(n).unwrap_or('\u{fffd}'))
+    }
+
+    fn hex_digits(&mut self, len: usize) -> Option<u32> {
+        let mut n = 0;
+
+        for _ in 0..len {
+            match self.peek().and_then(|c| c.to_digit(16)) {
+                Some(d) => n = n * 16 + d,
+                None => return None,
+            }
+
+            self.bump();
+        }
+
+        Some(n)
+    }
+}
+
+pub fn is_line_terminator(c: char) -> bool {
+    c == '\n' || c == '\r' || c == '\u{2028}' || c == '\u{2029}'
+}
+
+// ECMAScript's WhiteSpace: tab, vertical tab, form feed, the byte order mark and the Zs (space
+// separator) characters. Unlike `char::is_whitespace`, this leaves out NEL (U+0085) and the
+// control characters U+001C to U+001F.
+fn is_whitespace(c: char) -> bool {
+    match c {
+        '\t' | '\u{b}' | '\u{c}' | '\u{feff}' => true,
+        ' ' | '\u{a0}' | '\u{1680}' | '\u{2000}'..='\u{200a}' | '\u{202f}' | '\u{205f}' |
+        '\u{3000}' => true,
+        _ => false,
+    }
+}
+
+fn is_id_start(c: char) -> bool {
+    c == '$' || c == '_' || unicode_id_start::is_id_start(c)
+}
+
+fn is_id_part(c: char) -> bool {
+    c == '$' || c == '_' || c == '\u{200c}' || c == '\u{200d}' ||
+        unicode_id_start::is_id_continue(c)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tokens(source: &str) -> Vec<Token> {
+        let mut lexer = Lexer::new(source, 0);
+        let mut tokens = Vec::new();
+
+        loop {
+            match lexer.next_token().unwrap().token {
+                Token::Eof => return tokens,
+                token => tokens.push(token),
+            }
+        }
+    }
+
+    #[test]
+    fn test_tokens() {
+        assert_eq!(vec![Token::Ident(String::from("a")), Token::Punct(">>>="), Token::Num(255.0),
+                        Token::Punct("?"), Token::Num(0.5), Token::Punct(":"), Token::Num(8.0),
+                        Token::Punct("?."), Token::PrivateName(String::from("x"))],
+                   tokens("a >>>= 0xff ?.5 : 0o10 ?. #x"));
+        assert_eq!(vec![Token::Num(1e3), Token::Num(1000000.0), Token::Num(15.0), Token::Num(19.0),
+                        Token::Ident(String::from("ab"))],
+                   tokens("1e3 1_000_000 017 019 /* a\n comment */ \\u0061\\u{62} // end"));
+        assert_eq!(vec![Token::Str(String::from("a\"\n\u{1f600}\u{fffd}\u{1}")),
+                        Token::Str(String::from("line continuation"))],
+                   tokens("'a\\\"\\n\\uD83D\\uDE00\\uD800\\1' \"line \\\ncontinuation\""));
+    }
+
+    #[test]
+    fn test_unicode_idents() {
+        assert_eq!(vec![Token::Ident(String::from("ünï")),
+                        Token::Ident(String::from("a\u{b7}b"))],
+                   tokens("ünï a\u{b7}b"));
+
+        // `²` is numeric, but it isn't in ID_Continue
+        let mut lexer = Lexer::new("a\u{b2}", 0);
+        assert_eq!(Token::Ident(String::from("a")), lexer.next_token().unwrap().token);
+        assert!(lexer.next_token().is_err());
+    }
+
+    #[test]
+    fn test_comments() {
+        let mut lexer = Lexer::new("a // one\n\n/** two\n */ /*three*/ b //", 0).with_comments();
+        assert!(lexer.next_token().unwrap().comments.is_empty());
+
+        let b = lexer.next_token().unwrap();
+        assert_eq!(vec![Comment { text: String::from(" one"), block: false, lines_before: 0 },
+                        Comment { text: String::from("* two\n "), block: true, lines_before: 2 },
+                        Comment { text: String::from("three"), block: true, lines_before: 0 }],
+                   b.comments);
+        assert_eq!(0, b.lines_before);
+
+        let eof = lexer.next_token().unwrap();
+        assert_eq!(Token::Eof, eof.token);
+        assert_eq!(vec![Comment { text: String::new(), block: false, lines_before: 0 }],
+                   eof.comments);
+
+        // Comments are skipped unless they're kept
+        assert!(Lexer::new("/* a */ b", 0).next_token().unwrap().comments.is_empty());
+    }
+
+    #[test]
+    fn test_newlines() {
+        let mut lexer = Lexer::new("a\r\n// b\n  c", 0);
+        assert!(!lexer.next_token().unwrap().newline_before);
+
+        let c = lexer.next_token().unwrap();
+        assert!(c.newline_before);
+        assert_eq!(Span::new(0, 10, 3, 3, 1), c.span);
+    }
+
+    #[test]
+    fn test_whitespace() {
+        let a = || Token::Ident(String::from("a"));
+        assert_eq!(vec![a(), a(), a(), a(), a(), a(), a(), a(), a()],
+                   tokens("a\ta\u{b}a\u{c}a\u{a0}a\u{feff}a\u{1680}a\u{2009}a\u{3000}a"));
+
+        let mut lexer = Lexer::new("a\u{2028}a", 0);
+        lexer.next_token().unwrap();
+        assert!(lexer.next_token().unwrap().newline_before);
+
+        // Rust counts NEL and the information separators as whitespace, but JavaScript doesn't
+        for source in &["a\u{85}a", "a\u{1f}a"] {
+            let mut lexer = Lexer::new(source, 0);
+            lexer.next_token().unwrap();
+            assert!(lexer.next_token().is_err(), "{:?}", source);
+        }
+    }
+
+    #[test]
+    fn test_rescan() {
+        let mut lexer = Lexer::new("/[/]+/g.x `a${b}\\u{`", 0);
+        let slash = lexer.next_token().unwrap();
+        assert_eq!(Token::Punct("/"), slash.token);
+        assert_eq!(Token::RegExp(String::from("[/]+"), String::from("g")),
+                   lexer.rescan_regex(&slash).unwrap().token);
+        assert_eq!(Token::Punct("."), lexer.next_token().unwrap().token);
+        lexer.next_token().unwrap();
+
+        let head = TemplatePart { cooked: Some(String::from("a")), raw: String::from("a") };
+        assert_eq!(Token::Template(head, false), lexer.next_token().unwrap().token);
+        lexer.next_token().unwrap();
+
+        let brace = lexer.next_token().unwrap();
+        let tail = TemplatePart { cooked: None, raw: String::from("\\u{") };
+        assert_eq!(Token::Template(tail, true), lexer.rescan_template(&brace).unwrap().token);
+    }
+
+    #[test]
+    fn test_template_octal() {
+        let part = |cooked: Option<&str>, raw: &str| {
+            let cooked = cooked.map(String::from);
+            Token::Template(TemplatePart { cooked: cooked, raw: String::from(raw) }, true)
+        };
+
+        assert_eq!(vec![part(Some("\0"), "\\0"), part(None, "\\01"), part(None, "\\1")],
+                   tokens("`\\0` `\\01` `\\1`"));
+    }
+
+    #[test]
+    fn test_errors() {
+        let error = |source| {
+            let mut lexer = Lexer::new(source, 0);
+
+            loop {
+                match lexer.next_token() {
+                    Ok(Lexeme { token: Token::Eof, .. }) => panic!("no error in {}", source),
+                    Ok(_) => (),
+                    Err(JsError::SyntaxError(msg)) => return msg,
+                    Err(e) => panic!("{:?}", e),
+                }
+            }
+        };
+
+        assert_eq!("Unterminated string constant (at 1:3)", error("a 'b\n'"));
+        assert_eq!("Unterminated comment (at 2:1)", error("\n/* a"));
+        assert_eq!("Invalid or unexpected token (at 1:1)", error("3in x"));
+        assert_eq!("Invalid escape sequence (at 1:2)", error("'\\x4'"));
+        assert_eq!("Invalid or unexpected token (at 1:3)", error("a @b"));
+    }
+}
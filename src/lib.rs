@@ -2,13 +2,24 @@
 
 #[macro_use]
 extern crate matches;
+extern crate regress;
+#[macro_use]
+extern crate serde_json;
+extern crate unicode_id_start;
 extern crate uuid;
 
 pub mod alloc_box;
 pub mod ast;
 pub mod backend;
+pub mod codegen;
+pub mod estree;
 pub mod gc_error;
 pub mod js_error;
+pub mod lexer;
 pub mod macros;
+pub mod parser;
+pub mod source_map;
+pub mod span;
 pub mod test_utils;
 pub mod types;
+pub mod visit;
@@ -54,12 +54,26 @@ macro_rules! method {
 
 #[macro_export]
 macro_rules! assign {
-    ($v:expr, $e:expr) => { Assign($v, $e) }
+    ($v:expr, $e:expr) => { Assign($crate::ast::Pattern::from($v), $e) }
 }
 
+// `decl!("x", e)` declares a single `var`; the other forms take the kind of declaration and any
+// number of names or patterns, each with an optional initial value, e.g.
+// `decl!(let "a" = Float(1.0), "b")`.
 #[macro_export]
 macro_rules! decl {
-    ($v:expr, $e:expr) => { Decl(String::from($v), $e) }
+    (@init) => { None };
+    (@init $e:expr) => { Some($e) };
+    (var $($v:tt $(= $e:expr)*),+) => { decl!(@kind Var $($v $(= $e)*),+) };
+    (let $($v:tt $(= $e:expr)*),+) => { decl!(@kind Let $($v $(= $e)*),+) };
+    (const $($v:tt $(= $e:expr)*),+) => { decl!(@kind Const $($v $(= $e)*),+) };
+    (@kind $kind:ident $($v:tt $(= $e:expr)*),+) => {
+        Decl($crate::ast::DeclKind::$kind,
+             vec![$(($crate::ast::Pattern::from($v), decl!(@init $($e)*))),+])
+    };
+    ($v:expr, $e:expr) => {
+        Decl($crate::ast::DeclKind::Var, vec![($crate::ast::Pattern::from($v), Some($e))])
+    }
 }
 
 #[macro_export]
@@ -77,9 +91,10 @@ macro_rules! var {
 macro_rules! defun {
     (($($param:expr),*) $stmt:expr) => {
         Defun(
+            $crate::ast::FnKind::Normal,
             None,
             vec![
-                $(String::from($param)),*
+                $($crate::ast::Pattern::from($param)),*
             ],
             vec![$stmt]
         )
@@ -87,9 +102,10 @@ macro_rules! defun {
 
     ($name:expr, ($($param:expr),*) $stmt:expr) => {
         Defun(
+            $crate::ast::FnKind::Normal,
             Some(String::from($name)),
             vec![
-                $(String::from($param)),*
+                $($crate::ast::Pattern::from($param)),*
             ],
             vec![$stmt]
         )
@@ -112,17 +128,17 @@ macro_rules! new_obj {
 macro_rules! obj {
     ($($name:expr => $prop:expr),*) => {
         Object(vec![
-            $((String::from($name), Box::new($prop))),*
+            $($crate::ast::Prop::Value(String::from($name), Box::new($prop))),*
         ])
     }
 }
 
 #[macro_export]
 macro_rules! parse_exp {
-    ($s:expr) => { parse_Exp($s).unwrap() }
+    ($s:expr) => { $crate::parser::parse_Exp($s).unwrap() }
 }
 
 #[macro_export]
 macro_rules! parse_stmt {
-    ($s:expr) => { parse_Stmt($s).unwrap() }
+    ($s:expr) => { $crate::parser::parse_Stmt($s).unwrap() }
 }
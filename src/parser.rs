@@ -0,0 +1,2392 @@
+// A hand-written recursive-descent parser from JavaScript source to the AST. Binary operators are
+// parsed by precedence climbing on `BinOp::precedence`. The parameters of an arrow function and
+// the target of a destructuring assignment are first read as expressions, since they look like
+// parenthesized expressions and array or object literals until the `=>` or `=` after them, and
+// then converted to patterns.
+
+use std::mem;
+
+use ast::{ArrowBody, AssignOp, BinOp, Class, ClassKey, ClassMember, Comments, DeclKind, Exp,
+          FnKind, ImportSpec, MemberKind, Pattern, Precedence, Program, Prop, PropKey, Stmt,
+          Template};
+use js_error::{JsError, Result};
+use lexer::{Lexeme, Lexer, Token};
+use span::Span;
+
+// Words that can never be the name of a variable. `yield` and `await` are only reserved in
+// generators and async functions respectively; see `Parser::check_name`.
+const RESERVED: &'static [&'static str] = &[
+    "break", "case", "catch", "class", "const", "continue", "debugger", "default", "delete", "do",
+    "else", "enum", "export", "extends", "false", "finally", "for", "function", "if", "import",
+    "in", "instanceof", "new", "null", "return", "super", "switch", "this", "throw", "true", "try",
+    "typeof", "var", "void", "while", "with",
+];
+
+// Words that start a statement, or a clause of a `switch`. A recovering parse takes one at the
+// start of a line as the end of whatever had an error before it.
+const STATEMENT_KEYWORDS: &'static [&'static str] = &[
+    "break", "case", "class", "const", "continue", "default", "do", "export", "for", "function",
+    "if", "import", "let", "return", "switch", "throw", "try", "var", "while",
+];
+
+#[derive(Clone, Debug, Default)]
+pub struct ParseOptions {
+    // Parse the source as a module, where `import` and `export` are allowed and `await` can be
+    // used at the top level
+    pub module: bool,
+    // Wrap each expression and statement in `Spanned` with the location it was parsed from
+    pub spans: bool,
+    // The file to give the spans, for a source map with more than one source
+    pub file: usize,
+    // Keep the comments and blank lines, wrapping the statements and expressions next to them in
+    // `Commented`. Comments where neither goes, e.g. before a class member, go with the next
+    // statement or expression instead.
+    pub comments: bool,
+}
+
+// What's allowed where the parser is, which starts over inside each function.
+#[derive(Clone, Default)]
+struct Context {
+    // Whether `return` is allowed
+    in_function: bool,
+    // Whether `await` and `yield` are operators rather than names
+    is_async: bool,
+    is_generator: bool,
+    // Whether an unlabeled `break` or `continue` is allowed
+    in_loop: bool,
+    in_switch: bool,
+    // The labels of the enclosing statements, and whether each one labels a loop, which a
+    // labeled `continue` needs
+    labels: Vec<(String, bool)>,
+}
+
+impl Context {
+    fn function(kind: FnKind) -> Context {
+        Context {
+            in_function: true,
+            is_async: kind.is_async(),
+            is_generator: kind.is_generator(),
+            ..Context::default()
+        }
+    }
+}
+
+pub struct Parser<'a> {
+    lexer: Lexer<'a>,
+    options: ParseOptions,
+    token: Lexeme,
+    // Where the previous token ends, which is where a node that it finishes ends
+    prev_end: usize,
+    context: Context,
+    // Whether `in` is left for a for-in loop rather than read as an operator
+    no_in: bool,
+    // Where the last arrow function read ends, to tell an arrow function from a parenthesized one
+    arrow_end: usize,
+    // The first shorthand property with a default, e.g. `{a = 1}`, that hasn't been converted to
+    // a pattern yet. It's an error unless the object turns out to be a destructuring target.
+    cover_init: Option<Span>,
+    // Whether the next `assign_exp` is an element of an array, object or parenthesized list that
+    // could still become a pattern, in which case a `cover_init` in it isn't an error yet
+    defer_cover: bool,
+    // Set by `parse_program_recovering`, which collects errors in `errors` rather than stopping
+    recovering: bool,
+    errors: Vec<JsError>,
+    // Where the token that the last error was reported at starts, since an error is often found
+    // again where a recovering parse carries on
+    last_error: Option<usize>,
+    // How many parentheses, brackets and braces have been opened and not closed yet, which tells
+    // a recovering parse where a statement with an error ends
+    depth: usize,
+}
+
+impl<'a> Parser<'a> {
+    pub fn new(source: &'a str, options: ParseOptions) -> Parser<'a> {
+        let lexer = Lexer::new(source, options.file);
+
+        Parser {
+            lexer: if options.comments { lexer.with_comments() } else { lexer },
+            token: Lexeme {
+                token: Token::Eof,
+                span: Span::default(),
+                newline_before: false,
+                comments: Vec::new(),
+                lines_before: 0,
+            },
+            prev_end: 0,
+            // A module can use `await` at the top level
+            context: Context { is_async: options.module, ..Context::default() },
+            options: options,
+            no_in: false,
+            arrow_end: 0,
+            cover_init: None,
+            defer_cover: false,
+            recovering: false,
+            errors: Vec::new(),
+            last_error: None,
+            depth: 0,
+        }
+    }
+
+    /// Parses the whole source as a script or, if `options.module` is set, a module.
+    pub fn parse_program(mut self) -> Result<Program> {
+        self.program()
+    }
+
+    /// Parses the whole source like `parse_program`, but carries on past syntax errors instead of
+    /// stopping at the first one, for tools that show every problem in a file at once. What
+    /// couldn't be parsed is left out of the AST, with an `Exp::Error` or a `Stmt::Error` in its
+    /// place, and the errors are returned in the order they were found.
+    pub fn parse_program_recovering(mut self) -> (Program, Vec<JsError>) {
+        self.recovering = true;
+
+        let program = match self.program() {
+            Ok(program) => program,
+            Err(e) => {
+                // Errors are caught at the statement they're in, so this shouldn't happen
+                self.errors.push(e);
+                Program::Script(Vec::new())
+            }
+        };
+
+        (program, self.errors)
+    }
+
+    fn program(&mut self) -> Result<Program> {
+        try!(self.next());
+        let mut stmts = Vec::new();
+
+        while self.token.token != Token::Eof {
+            let module = self.options.module;
+            push_stmt(&mut stmts, try!(self.list_item(module)));
+        }
+
+        self.dangling_comments(&mut stmts);
+
+        if self.options.module {
+            Ok(Program::Module(stmts))
+        } else {
+            Ok(Program::Script(stmts))
+        }
+    }
+
+    /// Parses the whole source as a single expression.
+    pub fn parse_exp(mut self) -> Result<Exp> {
+        try!(self.next());
+        let e = try!(self.expression());
+
+        if self.token.token != Token::Eof {
+            return Err(self.unexpected());
+        }
+
+        Ok(e)
+    }
+
+    fn next(&mut self) -> Result<Lexeme> {
+        let mut token = loop {
+            match self.lexer.next_token() {
+                // A recovering parse leaves out the character that couldn't be read
+                Err(e) if self.recovering => {
+                    self.errors.push(e);
+                    self.lexer.skip_char();
+                }
+                result => break try!(result),
+            }
+        };
+
+        match self.token.token {
+            Token::Punct("(") | Token::Punct("[") | Token::Punct("{") => self.depth += 1,
+            Token::Punct(")") | Token::Punct("]") | Token::Punct("}") => {
+                self.depth = self.depth.saturating_sub(1)
+            }
+            _ => (),
+        }
+
+        // Comments that nothing took from the token go along to the next one, so none are lost
+        if !self.token.comments.is_empty() {
+            let mut comments = mem::replace(&mut self.token.comments, Vec::new());
+            comments.append(&mut token.comments);
+            token.comments = comments;
+        }
+
+        self.prev_end = self.token.span.end();
+        Ok(mem::replace(&mut self.token, token))
+    }
+
+    fn peek(&self) -> Result<Lexeme> {
+        self.read_ahead(&mut self.lexer.clone())
+    }
+
+    // Reads the next token from `lexer`, a copy of the parser's lexer for looking ahead with.
+    fn read_ahead(&self, lexer: &mut Lexer<'a>) -> Result<Lexeme> {
+        loop {
+            match lexer.next_token() {
+                // The error is reported when `next` gets to it
+                Err(_) if self.recovering => lexer.skip_char(),
+                result => return result,
+            }
+        }
+    }
+
+    fn is(&self, punct: &str) -> bool {
+        match self.token.token {
+            Token::Punct(p) => p == punct,
+            _ => false,
+        }
+    }
+
+    fn is_word(&self, word: &str) -> bool {
+        match self.token.token {
+            Token::Ident(ref name) => name == word,
+            _ => false,
+        }
+    }
+
+    fn eat(&mut self, punct: &str) -> Result<bool> {
+        if self.is(punct) {
+            try!(self.next());
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+
+    fn eat_word(&mut self, word: &str) -> Result<bool> {
+        if self.is_word(word) {
+            try!(self.next());
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+
+    fn expect(&mut self, punct: &str) -> Result<()> {
+        if try!(self.eat(punct)) { Ok(()) } else { Err(self.unexpected()) }
+    }
+
+    fn expect_word(&mut self, word: &str) -> Result<()> {
+        if try!(self.eat_word(word)) { Ok(()) } else { Err(self.unexpected()) }
+    }
+
+    // Whether the current token is `async` with `function` after it on the same line.
+    fn at_async_function(&self) -> Result<bool> {
+        if !self.is_word("async") {
+            return Ok(false);
+        }
+
+        let next = try!(self.peek());
+        Ok(!next.newline_before && next.token == Token::Ident(String::from("function")))
+    }
+
+    // Whether the current token is the `import` of `import(...)` or `import.meta` rather than an
+    // import declaration.
+    fn at_import_call(&self) -> Result<bool> {
+        Ok(match try!(self.peek()).token {
+            Token::Punct(p) => p == "(" || p == ".",
+            _ => false,
+        })
+    }
+
+    fn unexpected(&self) -> JsError {
+        let msg = match self.token.token {
+            Token::Eof => String::from("Unexpected end of input"),
+            Token::Ident(ref name) if is_reserved(name) => format!("Unexpected token '{}'", name),
+            Token::Ident(ref name) => format!("Unexpected identifier '{}'", name),
+            Token::PrivateName(ref name) => format!("Unexpected identifier '#{}'", name),
+            Token::Num(_) => String::from("Unexpected number"),
+            Token::Str(_) => String::from("Unexpected string"),
+            Token::Template(..) => String::from("Unexpected template string"),
+            Token::RegExp(..) => String::from("Unexpected regular expression"),
+            Token::Punct(p) => format!("Unexpected token '{}'", p),
+        };
+
+        JsError::SyntaxError(msg).at(&self.token.span)
+    }
+
+    // Whether the current token starts a statement on a new line, where a recovering parse takes
+    // anything unfinished before it to have ended.
+    fn at_statement_keyword(&self) -> bool {
+        match self.token.token {
+            Token::Ident(ref word) => {
+                self.token.newline_before && STATEMENT_KEYWORDS.contains(&&word[..])
+            }
+            _ => false,
+        }
+    }
+
+    // Adds an error to those a recovering parse returns, unless it's at the same token as the last
+    // one.
+    fn report(&mut self, e: JsError) {
+        let at = Some(self.token.span.offset);
+
+        if self.last_error != at {
+            self.errors.push(e);
+            self.last_error = at;
+        }
+    }
+
+    // Fails with an error that doesn't change how the rest of the source is read, so that a
+    // recovering parse can report it and carry on where it is.
+    fn early_error(&mut self, e: JsError) -> Result<()> {
+        if self.recovering {
+            self.report(e);
+            Ok(())
+        } else {
+            Err(e)
+        }
+    }
+
+    // Ends a statement. The semicolon can be left out before a `}`, at the end of the input, or
+    // where there's a line break before the next token.
+    fn semicolon(&mut self) -> Result<()> {
+        if try!(self.eat(";")) || self.is("}") || self.token.token == Token::Eof ||
+           self.token.newline_before {
+            Ok(())
+        } else {
+            // A recovering parse goes on as if the semicolon were there
+            let e = self.unexpected();
+            self.early_error(e)
+        }
+    }
+
+    fn span_from(&self, start: &Span) -> Span {
+        // A placeholder for something missing ends before it starts
+        Span { len: self.prev_end.saturating_sub(start.offset), ..*start }
+    }
+
+    // Gives `e` the span from `start` to the end of the previous token, if spans were asked for.
+    fn spanned_exp(&self, start: &Span, e: Exp) -> Exp {
+        if self.options.spans { e.with_span(self.span_from(start)) } else { e }
+    }
+
+    fn spanned_stmt(&self, start: &Span, s: Stmt) -> Stmt {
+        if self.options.spans { s.with_span(self.span_from(start)) } else { s }
+    }
+
+    // Checks that `name` can be used as the name of a variable here.
+    fn check_name(&self, name: &str, span: &Span) -> Result<()> {
+        if is_reserved(name) {
+            Err(JsError::SyntaxError(format!("Unexpected token '{}'", name)).at(span))
+        } else if (name == "yield" && self.context.is_generator) ||
+                  (name == "await" && (self.context.is_async || self.options.module)) {
+            Err(JsError::SyntaxError(format!("Unexpected reserved word '{}'", name)).at(span))
+        } else {
+            Ok(())
+        }
+    }
+
+    // Reads the name of a variable.
+    fn name(&mut self) -> Result<String> {
+        if let Token::Ident(ref name) = self.token.token {
+            try!(self.check_name(name, &self.token.span));
+        }
+
+        self.ident_name()
+    }
+
+    // Reads an identifier, which can be a reserved word where it's a property name.
+    fn ident_name(&mut self) -> Result<String> {
+        if let Token::Ident(_) = self.token.token {
+            if let Token::Ident(name) = try!(self.next()).token {
+                return Ok(name);
+            }
+        }
+
+        Err(self.unexpected())
+    }
+
+    fn string_literal(&mut self) -> Result<String> {
+        if let Token::Str(_) = self.token.token {
+            if let Token::Str(s) = try!(self.next()).token {
+                return Ok(s);
+            }
+        }
+
+        Err(self.unexpected())
+    }
+
+    // e1, e2, ...
+    fn expression(&mut self) -> Result<Exp> {
+        let start = self.token.span;
+        let mut e = try!(self.assign_exp());
+
+        while try!(self.eat(",")) {
+            let e2 = try!(self.assign_exp());
+            e = self.spanned_exp(&start, Exp::Comma(Box::new(e), Box::new(e2)));
+        }
+
+        Ok(e)
+    }
+
+    fn assign_exp(&mut self) -> Result<Exp> {
+        let defer_cover = mem::replace(&mut self.defer_cover, false);
+        let outer_cover = self.cover_init.take();
+        let e = try!(self.assignment());
+
+        match self.cover_init {
+            Some(span) if !defer_cover => {
+                Err(JsError::SyntaxError(String::from("Invalid shorthand property initializer"))
+                        .at(&span))
+            }
+            Some(_) => Ok(e),
+            None => {
+                self.cover_init = outer_cover;
+                Ok(e)
+            }
+        }
+    }
+
+    fn assignment(&mut self) -> Result<Exp> {
+        let start = self.token.span;
+
+        if self.context.is_generator && self.is_word("yield") {
+            return self.yield_exp();
+        }
+
+        // x => ..., and async x => ...
+        if let Token::Ident(_) = self.token.token {
+            let mut lexer = self.lexer.clone();
+            let next = try!(self.read_ahead(&mut lexer));
+
+            let is_async = if next.token == Token::Punct("=>") {
+                Some(false)
+            } else if self.is_word("async") && !next.newline_before &&
+                      matches!(next.token, Token::Ident(_)) &&
+                      try!(self.read_ahead(&mut lexer)).token == Token::Punct("=>") {
+                Some(true)
+            } else {
+                None
+            };
+
+            if let Some(is_async) = is_async {
+                if is_async {
+                    try!(self.next());
+                }
+
+                let param = try!(self.name());
+                let kind = FnKind::new(is_async, false);
+                return self.arrow_body(&start, kind, vec![Pattern::Var(param)]);
+            }
+        }
+
+        let lhs = try!(self.conditional());
+
+        let op = match self.token.token {
+            Token::Punct(p) => AssignOp::from_op(p),
+            _ => None,
+        };
+
+        let op = match op {
+            Some(op) => op,
+            None => return Ok(lhs),
+        };
+
+        let target = if op == AssignOp::Assign {
+            let target = try!(self.to_pattern(lhs, false, &start));
+            self.cover_init = None;
+            target
+        } else {
+            try!(self.check_target(&lhs, &start));
+            Pattern::from(lhs)
+        };
+
+        try!(self.next());
+        let rhs = try!(self.assign_exp());
+        Ok(self.spanned_exp(&start, Exp::AssignExp(op, Box::new(target), Box::new(rhs))))
+    }
+
+    fn yield_exp(&mut self) -> Result<Exp> {
+        let start = self.token.span;
+        try!(self.next());
+
+        // A bare `yield` is followed by something that can't start an expression
+        let bare = self.token.newline_before || match self.token.token {
+            Token::Eof => true,
+            Token::Punct(p) => [")", "]", "}", ",", ";", ":"].contains(&p),
+            Token::Ident(ref word) => word == "in" || word == "of",
+            _ => false,
+        };
+
+        let e = if bare {
+            Exp::Yield(None)
+        } else if try!(self.eat("*")) {
+            Exp::YieldAll(Box::new(try!(self.assign_exp())))
+        } else {
+            Exp::Yield(Some(Box::new(try!(self.assign_exp()))))
+        };
+
+        Ok(self.spanned_exp(&start, e))
+    }
+
+    // Reads the `=>` and body of an arrow function whose parameters have been read.
+    fn arrow_body(&mut self, start: &Span, kind: FnKind, params: Vec<Pattern>) -> Result<Exp> {
+        if self.token.newline_before {
+            return Err(self.unexpected());
+        }
+
+        try!(self.expect("=>"));
+        let outer = mem::replace(&mut self.context, Context::function(kind));
+
+        let body = if self.is("{") {
+            self.block().map(ArrowBody::Block)
+        } else {
+            self.assign_exp().map(|e| ArrowBody::Exp(Box::new(e)))
+        };
+
+        self.context = outer;
+        self.arrow_end = self.prev_end;
+        Ok(self.spanned_exp(start, Exp::Arrow(kind, params, try!(body))))
+    }
+
+    fn conditional(&mut self) -> Result<Exp> {
+        let start = self.token.span;
+        let test = try!(self.binary(0));
+
+        if !try!(self.eat("?")) {
+            return Ok(test);
+        }
+
+        let no_in = mem::replace(&mut self.no_in, false);
+        let e1 = self.assign_exp();
+        self.no_in = no_in;
+
+        let e1 = try!(e1);
+        try!(self.expect(":"));
+        let e2 = try!(self.assign_exp());
+
+        Ok(self.spanned_exp(&start, Exp::Conditional(Box::new(test), Box::new(e1), Box::new(e2))))
+    }
+
+    // Reads a binary expression whose operators have a precedence of at least `min`.
+    fn binary(&mut self, min: i32) -> Result<Exp> {
+        let start = self.token.span;
+        let grouped = self.is("(");
+        let mut left = try!(self.unary());
+        // The operator at the top of `left`, if it's a binary expression read here
+        let mut left_op = None;
+
+        loop {
+            let op = match self.token.token {
+                Token::Punct(p) => BinOp::from_op(p),
+                Token::Ident(ref word) if word == "instanceof" || (word == "in" && !self.no_in) => {
+                    BinOp::from_op(word)
+                }
+                _ => None,
+            };
+
+            let op = match op {
+                Some(op) => op,
+                None => break,
+            };
+
+            let precedence = op.precedence() as i32;
+
+            if precedence < min {
+                break;
+            }
+
+            // `-a ** b` is ambiguous, so the left side of `**` can't be a unary expression
+            if op == BinOp::Exponent && left_op.is_none() && !grouped &&
+               left.precedence() == Precedence::Sign {
+                let msg = "Unary operator used immediately before exponentiation expression";
+                return Err(JsError::SyntaxError(String::from(msg)).at(&self.token.span));
+            }
+
+            // `??` can't be mixed with `&&` or `||` without grouping. The right side of `??` is
+            // read with a minimum just above its precedence, which is how that side is checked.
+            let mixes_left = match (&left_op, &op) {
+                (&Some(BinOp::And), &BinOp::Coalesce) |
+                (&Some(BinOp::Or), &BinOp::Coalesce) => true,
+                _ => false,
+            };
+
+            let mixes_right = min == Precedence::Coalesce as i32 + 1 &&
+                              (op == BinOp::And || op == BinOp::Or);
+
+            if mixes_left || mixes_right {
+                return Err(self.unexpected());
+            }
+
+            try!(self.next());
+
+            // `**` is right-associative, and every other operator is left-associative
+            let right_min = if op == BinOp::Exponent { precedence } else { precedence + 1 };
+            let right = try!(self.binary(right_min));
+
+            let e = Exp::BinExp(Box::new(left), op.clone(), Box::new(right));
+            left = self.spanned_exp(&start, e);
+            left_op = Some(op);
+        }
+
+        Ok(left)
+    }
+
+    fn unary(&mut self) -> Result<Exp> {
+        let start = self.token.span;
+
+        let unary: Option<fn(Box<Exp>) -> Exp> = match self.token.token {
+            Token::Punct("!") => Some(Exp::LogNot),
+            Token::Punct("~") => Some(Exp::BitNot),
+            Token::Punct("+") => Some(Exp::Pos),
+            Token::Punct("-") => Some(Exp::Neg),
+            Token::Punct("++") => Some(Exp::PreInc),
+            Token::Punct("--") => Some(Exp::PreDec),
+            Token::Ident(ref word) => {
+                match &word[..] {
+                    "delete" => Some(Exp::Delete),
+                    "typeof" => Some(Exp::TypeOf),
+                    "void" => Some(Exp::Void),
+                    "await" if self.context.is_async => Some(Exp::Await),
+                    _ => None,
+                }
+            }
+            _ => None,
+        };
+
+        if let Some(unary) = unary {
+            let is_inc = self.is("++") || self.is("--");
+            try!(self.next());
+            let e = try!(self.unary());
+
+            if is_inc {
+                try!(self.check_target(&e, &start));
+            }
+
+            return Ok(self.spanned_exp(&start, unary(Box::new(e))));
+        }
+
+        let e = try!(self.call_exp());
+
+        // A line break before `++` or `--` ends the statement instead, e.g. `a\n++b`
+        if (self.is("++") || self.is("--")) && !self.token.newline_before {
+            try!(self.check_target(&e, &start));
+            let postfix = if self.is("++") { Exp::PostInc } else { Exp::PostDec };
+            try!(self.next());
+            return Ok(self.spanned_exp(&start, postfix(Box::new(e))));
+        }
+
+        Ok(e)
+    }
+
+    // Reads a primary expression with any member accesses, calls and tagged templates after it.
+    fn call_exp(&mut self) -> Result<Exp> {
+        let start = self.token.span;
+
+        let mut e = if self.is_word("new") {
+            try!(self.new_exp())
+        } else if self.is_word("super") {
+            try!(self.next());
+
+            if !(self.is("(") || self.is(".") || self.is("[")) {
+                let msg = String::from("'super' keyword unexpected here");
+                return Err(JsError::SyntaxError(msg).at(&start));
+            }
+
+            self.spanned_exp(&start, Exp::Super)
+        } else {
+            try!(self.primary())
+        };
+
+        // An arrow function can't be called or have its properties read without grouping
+        if let Exp::Arrow(..) = *e.node() {
+            if self.prev_end == self.arrow_end {
+                return Ok(e);
+            }
+        }
+
+        let mut chain = false;
+
+        loop {
+            e = if try!(self.eat(".")) {
+                try!(self.member(e))
+            } else if try!(self.eat("?.")) {
+                chain = true;
+                let e = Exp::Optional(Box::new(e));
+
+                if self.is("(") {
+                    Exp::Call(Box::new(e), try!(self.arguments()))
+                } else if try!(self.eat("[")) {
+                    try!(self.computed_member(e))
+                } else {
+                    try!(self.member(e))
+                }
+            } else if try!(self.eat("[")) {
+                try!(self.computed_member(e))
+            } else if self.is("(") {
+                Exp::Call(Box::new(e), try!(self.arguments()))
+            } else if let Token::Template(..) = self.token.token {
+                if chain {
+                    let msg = String::from("Invalid tagged template on optional chain");
+                    return Err(JsError::SyntaxError(msg).at(&self.token.span));
+                }
+
+                Exp::TaggedTemplate(Box::new(e), try!(self.template(true)))
+            } else {
+                break;
+            };
+
+            e = self.spanned_exp(&start, e);
+        }
+
+        if chain {
+            e = self.spanned_exp(&start, Exp::Chain(Box::new(e)));
+        }
+
+        Ok(e)
+    }
+
+    // new callee(args), where the arguments can be left out
+    fn new_exp(&mut self) -> Result<Exp> {
+        let start = self.token.span;
+        try!(self.next());
+
+        if self.is(".") {
+            return Err(JsError::unimplemented("new.target"));
+        }
+
+        let mut callee = if self.is_word("new") {
+            try!(self.new_exp())
+        } else {
+            try!(self.primary())
+        };
+
+        loop {
+            callee = if try!(self.eat(".")) {
+                try!(self.member(callee))
+            } else if try!(self.eat("[")) {
+                try!(self.computed_member(callee))
+            } else if let Token::Template(..) = self.token.token {
+                Exp::TaggedTemplate(Box::new(callee), try!(self.template(true)))
+            } else if self.is("?.") {
+                let msg = String::from("Invalid optional chain from new expression");
+                return Err(JsError::SyntaxError(msg).at(&self.token.span));
+            } else {
+                break;
+            };
+
+            callee = self.spanned_exp(&start, callee);
+        }
+
+        let args = if self.is("(") { try!(self.arguments()) } else { Vec::new() };
+        Ok(self.spanned_exp(&start, Exp::NewObject(Box::new(callee), args)))
+    }
+
+    // Reads the name after a `.` or `?.`.
+    fn member(&mut self, e: Exp) -> Result<Exp> {
+        if let Token::PrivateName(_) = self.token.token {
+            if let Token::PrivateName(name) = try!(self.next()).token {
+                return Ok(Exp::InstanceVar(Box::new(e), format!("#{}", name)));
+            }
+        }
+
+        Ok(Exp::InstanceVar(Box::new(e), try!(self.ident_name())))
+    }
+
+    // Reads the key and `]` after a `[`.
+    fn computed_member(&mut self, e: Exp) -> Result<Exp> {
+        let no_in = mem::replace(&mut self.no_in, false);
+        let key = self.expression();
+        self.no_in = no_in;
+
+        let key = try!(key);
+        try!(self.expect("]"));
+        Ok(Exp::KeyAccessor(Box::new(e), Box::new(key)))
+    }
+
+    fn arguments(&mut self) -> Result<Vec<Box<Exp>>> {
+        try!(self.expect("("));
+        let no_in = mem::replace(&mut self.no_in, false);
+        let mut args = Vec::new();
+
+        while !try!(self.eat(")")) {
+            let arg = if try!(self.eat("...")) {
+                Exp::Spread(Box::new(try!(self.assign_exp())))
+            } else {
+                try!(self.assign_exp())
+            };
+
+            args.push(Box::new(arg));
+
+            if !self.is(")") {
+                try!(self.expect(","));
+            }
+        }
+
+        self.no_in = no_in;
+        Ok(args)
+    }
+
+    fn primary(&mut self) -> Result<Exp> {
+        // Comments before an operand go with it
+        if !self.token.comments.is_empty() {
+            let comments = Comments {
+                leading: mem::replace(&mut self.token.comments, Vec::new()),
+                trailing: Vec::new(),
+                lines_before: self.token.lines_before,
+            };
+
+            return Ok(try!(self.primary()).with_comments(comments));
+        }
+
+        let start = self.token.span;
+
+        // A recovering parse fills in an expression that's missing before a closing bracket, a
+        // separator or the next statement, e.g. `a = ;` or `f(a, )`
+        let missing = match self.token.token {
+            Token::Punct(p) => [")", "]", "}", ";", ",", ":"].contains(&p),
+            Token::Eof => true,
+            _ => self.at_statement_keyword(),
+        };
+
+        if self.recovering && missing {
+            let e = self.unexpected();
+            self.report(e);
+            return Ok(self.spanned_exp(&start, Exp::Error));
+        }
+
+        let e = match self.token.token.clone() {
+            Token::Ident(word) => {
+                match &word[..] {
+                    "function" => return self.function(&start, false),
+                    "async" if try!(self.at_async_function()) => {
+                        return self.function(&start, false)
+                    }
+                    "async" => {
+                        let next = try!(self.peek());
+
+                        if !next.newline_before && next.token == Token::Punct("(") {
+                            return self.paren(&start, true);
+                        }
+
+                        try!(self.next());
+                        Exp::Var(word)
+                    }
+                    "class" => Exp::Class(Box::new(try!(self.class(false)))),
+                    "import" => return Err(JsError::unimplemented("import() and import.meta")),
+                    // `undefined`, `NaN` and `Infinity` are ordinary names, which code can shadow
+                    "this" | "null" | "true" | "false" => {
+                        try!(self.next());
+
+                        match &word[..] {
+                            "null" => Exp::Null,
+                            "true" => Exp::Bool(true),
+                            "false" => Exp::Bool(false),
+                            _ => Exp::This,
+                        }
+                    }
+                    _ => Exp::Var(try!(self.name())),
+                }
+            }
+            Token::Num(n) => {
+                try!(self.next());
+                Exp::Float(n)
+            }
+            Token::Str(s) => {
+                try!(self.next());
+                Exp::Str(s)
+            }
+            Token::Template(..) => Exp::Template(try!(self.template(false))),
+            Token::Punct("/") | Token::Punct("/=") => {
+                self.token = try!(self.lexer.rescan_regex(&self.token));
+
+                match try!(self.next()).token {
+                    Token::RegExp(pattern, flags) => Exp::RegExp(pattern, flags),
+                    _ => unreachable!(),
+                }
+            }
+            Token::Punct("(") => return self.paren(&start, false),
+            Token::Punct("[") => try!(self.array()),
+            Token::Punct("{") => try!(self.object()),
+            // #x in obj, which checks whether `obj` has the private field
+            Token::PrivateName(name) => {
+                if try!(self.peek()).token != Token::Ident(String::from("in")) {
+                    return Err(self.unexpected());
+                }
+
+                try!(self.next());
+                Exp::Var(format!("#{}", name))
+            }
+            _ => return Err(self.unexpected()),
+        };
+
+        Ok(self.spanned_exp(&start, e))
+    }
+
+    // Reads a parenthesized expression, or the parameters of an arrow function if there's a `=>`
+    // after the `)`. With `is_async`, this is `async (...)`, which is a call to a function named
+    // `async` unless it's followed by a `=>`.
+    fn paren(&mut self, start: &Span, is_async: bool) -> Result<Exp> {
+        if is_async {
+            try!(self.next());
+        }
+
+        try!(self.expect("("));
+        let no_in = mem::replace(&mut self.no_in, false);
+        let mut elems = Vec::new();
+        let mut trailing_comma = false;
+
+        while !self.is(")") {
+            self.defer_cover = true;
+
+            let e = if try!(self.eat("...")) {
+                self.defer_cover = true;
+                Exp::Spread(Box::new(try!(self.assign_exp())))
+            } else {
+                try!(self.assign_exp())
+            };
+
+            elems.push(e);
+            trailing_comma = false;
+
+            if !self.is(")") {
+                try!(self.expect(","));
+                trailing_comma = true;
+            }
+        }
+
+        let close = self.token.span;
+        try!(self.next());
+        self.no_in = no_in;
+
+        if self.is("=>") && !self.token.newline_before {
+            let len = elems.len();
+            let mut params = Vec::new();
+
+            for (i, e) in elems.into_iter().enumerate() {
+                params.push(match e {
+                    Exp::Spread(e) => {
+                        if i + 1 < len || trailing_comma {
+                            let msg = String::from("Rest parameter must be last formal parameter");
+                            return Err(JsError::SyntaxError(msg).at(start));
+                        }
+
+                        Pattern::Rest(Box::new(try!(self.to_pattern(*e, true, start))))
+                    }
+                    e => try!(self.to_pattern(e, true, start)),
+                });
+            }
+
+            self.cover_init = None;
+            return self.arrow_body(start, FnKind::new(is_async, false), params);
+        }
+
+        if is_async {
+            let callee = Exp::Var(String::from("async"));
+            let args = elems.into_iter().map(Box::new).collect();
+            return Ok(self.spanned_exp(start, Exp::Call(Box::new(callee), args)));
+        }
+
+        let spread = elems.iter().any(|e| matches!(*e, Exp::Spread(_)));
+
+        if elems.is_empty() || trailing_comma || spread {
+            return Err(JsError::SyntaxError(String::from("Unexpected token ')'")).at(&close));
+        }
+
+        let mut elems = elems.into_iter();
+        let first = elems.next().unwrap();
+        let e = elems.fold(first, |e1, e2| Exp::Comma(Box::new(e1), Box::new(e2)));
+
+        Ok(self.spanned_exp(start, e))
+    }
+
+    fn array(&mut self) -> Result<Exp> {
+        try!(self.expect("["));
+        let no_in = mem::replace(&mut self.no_in, false);
+        let mut elems = Vec::new();
+
+        while !try!(self.eat("]")) {
+            if try!(self.eat(",")) {
+                elems.push(None);
+                continue;
+            }
+
+            self.defer_cover = true;
+
+            let e = if try!(self.eat("...")) {
+                self.defer_cover = true;
+                Exp::Spread(Box::new(try!(self.assign_exp())))
+            } else {
+                try!(self.assign_exp())
+            };
+
+            elems.push(Some(Box::new(e)));
+
+            if !self.is("]") {
+                try!(self.expect(","));
+            }
+        }
+
+        self.no_in = no_in;
+        Ok(Exp::Array(elems))
+    }
+
+    fn object(&mut self) -> Result<Exp> {
+        try!(self.expect("{"));
+        let no_in = mem::replace(&mut self.no_in, false);
+        let mut props = Vec::new();
+
+        while !try!(self.eat("}")) {
+            props.push(try!(self.prop()));
+
+            if !self.is("}") {
+                try!(self.expect(","));
+            }
+        }
+
+        self.no_in = no_in;
+        Ok(Exp::Object(props))
+    }
+
+    fn prop(&mut self) -> Result<Prop> {
+        if try!(self.eat("...")) {
+            self.defer_cover = true;
+            return Ok(Prop::Spread(Box::new(try!(self.assign_exp()))));
+        }
+
+        let (kind, accessor) = try!(self.method_modifiers());
+        let key_span = self.token.span;
+
+        let shorthand = match self.token.token {
+            Token::Ident(ref name) => Some(name.clone()),
+            _ => None,
+        };
+
+        let key = match try!(self.prop_key()) {
+            ClassKey::Name(key) => PropKey::Name(key),
+            ClassKey::Computed(key) => PropKey::Computed(key),
+            ClassKey::Private(name) => {
+                let msg = format!("Unexpected identifier '#{}'", name);
+                return Err(JsError::SyntaxError(msg).at(&key_span));
+            }
+        };
+
+        if self.is("(") {
+            return Ok(match accessor {
+                Some("get") => Prop::Get(key, try!(self.getter())),
+                Some(_) => {
+                    let (param, body) = try!(self.setter());
+                    Prop::Set(key, param, body)
+                }
+                None => {
+                    let (params, body) = try!(self.function_rest(kind));
+                    Prop::Method(kind, key, params, body)
+                }
+            });
+        }
+
+        if kind != FnKind::Normal || accessor.is_some() {
+            return Err(self.unexpected());
+        }
+
+        if try!(self.eat(":")) {
+            self.defer_cover = true;
+            let value = Box::new(try!(self.assign_exp()));
+
+            return Ok(match key {
+                PropKey::Name(key) => Prop::Value(key, value),
+                PropKey::Computed(key) => Prop::Computed(key, value),
+            });
+        }
+
+        let name = match shorthand {
+            Some(name) => name,
+            None => return Err(self.unexpected()),
+        };
+
+        try!(self.check_name(&name, &key_span));
+
+        if self.is("=") {
+            // Only valid in a pattern; see `cover_init`
+            if self.cover_init.is_none() {
+                self.cover_init = Some(self.token.span);
+            }
+
+            try!(self.next());
+            let default = try!(self.assign_exp());
+
+            let mut target = Exp::Var(name.clone());
+
+            if self.options.spans {
+                target = target.with_span(key_span);
+            }
+
+            let target = Box::new(Pattern::from(target));
+            let e = Exp::AssignExp(AssignOp::Assign, target, Box::new(default));
+            return Ok(Prop::Value(name, Box::new(e)));
+        }
+
+        Ok(Prop::Shorthand(name))
+    }
+
+    // Reads the `async`, `*`, `get` or `set` before the key of a method. Each of those words can
+    // also be the key itself, e.g. `{ get: 1 }` or `{ async() {} }`.
+    fn method_modifiers(&mut self) -> Result<(FnKind, Option<&'static str>)> {
+        let mut is_async = false;
+
+        if self.is_word("async") {
+            let next = try!(self.peek());
+
+            let starts_method = starts_key(&next.token) || next.token == Token::Punct("*");
+
+            if !next.newline_before && starts_method {
+                try!(self.next());
+                is_async = true;
+            }
+        }
+
+        let generator = try!(self.eat("*"));
+        let mut accessor = None;
+
+        if !is_async && !generator && (self.is_word("get") || self.is_word("set")) &&
+           starts_key(&try!(self.peek()).token) {
+            accessor = Some(if self.is_word("get") { "get" } else { "set" });
+            try!(self.next());
+        }
+
+        Ok((FnKind::new(is_async, generator), accessor))
+    }
+
+    // Reads the key of a property or class member. A numeric key is kept as the string of its
+    // value, e.g. `0x10` is "16".
+    fn prop_key(&mut self) -> Result<ClassKey> {
+        if !starts_key(&self.token.token) {
+            return Err(self.unexpected());
+        }
+
+        Ok(match try!(self.next()).token {
+            Token::Ident(key) | Token::Str(key) => ClassKey::Name(key),
+            Token::Num(n) => ClassKey::Name(format!("{}", n)),
+            Token::PrivateName(name) => ClassKey::Private(name),
+            _ => {
+                let no_in = mem::replace(&mut self.no_in, false);
+                let key = self.assign_exp();
+                self.no_in = no_in;
+
+                let key = try!(key);
+                try!(self.expect("]"));
+                ClassKey::Computed(Box::new(key))
+            }
+        })
+    }
+
+    // Reads a function from the `async` or `function` keyword. The name can only be left out of
+    // an expression.
+    fn function(&mut self, start: &Span, is_declaration: bool) -> Result<Exp> {
+        let is_async = try!(self.eat_word("async"));
+        try!(self.expect_word("function"));
+        let kind = FnKind::new(is_async, try!(self.eat("*")));
+
+        let name = match self.token.token {
+            Token::Ident(_) => Some(try!(self.name())),
+            _ if is_declaration => return Err(self.unexpected()),
+            _ => None,
+        };
+
+        let (params, body) = try!(self.function_rest(kind));
+        Ok(self.spanned_exp(start, Exp::Defun(kind, name, params, body)))
+    }
+
+    // Reads the parameters and body of a function of the given kind.
+    fn function_rest(&mut self, kind: FnKind) -> Result<(Vec<Pattern>, Vec<Stmt>)> {
+        let outer = mem::replace(&mut self.context, Context::function(kind));
+        let no_in = mem::replace(&mut self.no_in, false);
+
+        let params = self.params();
+        let body = if params.is_ok() { self.block() } else { Ok(Vec::new()) };
+
+        self.context = outer;
+        self.no_in = no_in;
+        Ok((try!(params), try!(body)))
+    }
+
+    fn params(&mut self) -> Result<Vec<Pattern>> {
+        try!(self.expect("("));
+        let mut params = Vec::new();
+
+        while !try!(self.eat(")")) {
+            if try!(self.eat("...")) {
+                params.push(Pattern::Rest(Box::new(try!(self.binding_target()))));
+                try!(self.expect(")"));
+                break;
+            }
+
+            params.push(try!(self.binding_element()));
+
+            if !self.is(")") {
+                try!(self.expect(","));
+            }
+        }
+
+        Ok(params)
+    }
+
+    fn getter(&mut self) -> Result<Vec<Stmt>> {
+        let start = self.token.span;
+        let (params, body) = try!(self.function_rest(FnKind::Normal));
+
+        if !params.is_empty() {
+            let msg = String::from("Getter must not have any formal parameters");
+            return Err(JsError::SyntaxError(msg).at(&start));
+        }
+
+        Ok(body)
+    }
+
+    fn setter(&mut self) -> Result<(Pattern, Vec<Stmt>)> {
+        let start = self.token.span;
+        let (mut params, body) = try!(self.function_rest(FnKind::Normal));
+
+        match params.pop() {
+            Some(Pattern::Rest(_)) | None => (),
+            Some(param) => {
+                if params.is_empty() {
+                    return Ok((param, body));
+                }
+            }
+        }
+
+        let msg = String::from("Setter must have exactly one formal parameter");
+        Err(JsError::SyntaxError(msg).at(&start))
+    }
+
+    // Reads a class from the `class` keyword. The name can only be left out of an expression.
+    fn class(&mut self, is_declaration: bool) -> Result<Class> {
+        try!(self.expect_word("class"));
+
+        let name = match self.token.token {
+            Token::Ident(_) if !self.is_word("extends") => Some(try!(self.name())),
+            _ if is_declaration => return Err(self.unexpected()),
+            _ => None,
+        };
+
+        let extends = if try!(self.eat_word("extends")) {
+            Some(Box::new(try!(self.call_exp())))
+        } else {
+            None
+        };
+
+        try!(self.expect("{"));
+        let mut members = Vec::new();
+
+        while !try!(self.eat("}")) {
+            if !try!(self.eat(";")) {
+                members.push(try!(self.class_member()));
+            }
+        }
+
+        Ok(Class { name: name, extends: extends, members: members })
+    }
+
+    fn class_member(&mut self) -> Result<ClassMember> {
+        let mut is_static = false;
+
+        if self.is_word("static") {
+            let next = try!(self.peek());
+
+            if starts_key(&next.token) || next.token == Token::Punct("*") {
+                try!(self.next());
+                is_static = true;
+            } else if next.token == Token::Punct("{") {
+                return Err(JsError::unimplemented("class static blocks"));
+            }
+        }
+
+        let (kind, accessor) = try!(self.method_modifiers());
+        let key = try!(self.prop_key());
+
+        let kind = if self.is("(") {
+            match accessor {
+                Some("get") => MemberKind::Get(try!(self.getter())),
+                Some(_) => {
+                    let (param, body) = try!(self.setter());
+                    MemberKind::Set(param, body)
+                }
+                None => {
+                    let (params, body) = try!(self.function_rest(kind));
+                    MemberKind::Method(kind, params, body)
+                }
+            }
+        } else {
+            if kind != FnKind::Normal || accessor.is_some() {
+                return Err(self.unexpected());
+            }
+
+            // A field's initial value is evaluated like the body of a method
+            let value = if try!(self.eat("=")) {
+                let outer = mem::replace(&mut self.context, Context::function(FnKind::Normal));
+                let value = self.assign_exp();
+                self.context = outer;
+                Some(try!(value))
+            } else {
+                None
+            };
+
+            try!(self.semicolon());
+            MemberKind::Field(value)
+        };
+
+        Ok(ClassMember { is_static: is_static, key: key, kind: kind })
+    }
+
+    // Reads a template literal from its first piece. An escape that isn't valid in a string is
+    // only allowed in a tagged template.
+    fn template(&mut self, tagged: bool) -> Result<Template> {
+        let mut quasis = Vec::new();
+        let mut exps = Vec::new();
+
+        loop {
+            let piece = try!(self.next());
+
+            let (part, tail) = match piece.token {
+                Token::Template(part, tail) => (part, tail),
+                _ => unreachable!(),
+            };
+
+            if part.cooked.is_none() && !tagged {
+                let msg = String::from("Invalid escape sequence in template");
+                return Err(JsError::SyntaxError(msg).at(&piece.span));
+            }
+
+            quasis.push(part);
+
+            if tail {
+                return Ok(Template { quasis: quasis, exps: exps });
+            }
+
+            let no_in = mem::replace(&mut self.no_in, false);
+            let e = self.expression();
+            self.no_in = no_in;
+            exps.push(Box::new(try!(e)));
+
+            if !self.is("}") {
+                return Err(self.unexpected());
+            }
+
+            self.token = try!(self.lexer.rescan_template(&self.token));
+        }
+    }
+
+    // Converts an expression read before a `=` or `=>` to the pattern it stands for. A `binding`
+    // pattern declares names, as the parameters of an arrow function do, while any other pattern
+    // assigns to existing variables and properties. Errors are reported at `start`, the start of
+    // the whole target.
+    fn to_pattern(&self, e: Exp, binding: bool, start: &Span) -> Result<Pattern> {
+        let simple = match *e.node() {
+            Exp::Var(_) => !binding,
+            Exp::InstanceVar(..) | Exp::KeyAccessor(..) => !binding,
+            _ => false,
+        };
+
+        if simple {
+            return Ok(Pattern::Exp(Box::new(e)));
+        }
+
+        let invalid = || {
+            let msg = if binding {
+                "Invalid destructuring target"
+            } else {
+                "Invalid left-hand side in assignment"
+            };
+
+            JsError::SyntaxError(String::from(msg)).at(start)
+        };
+
+        match unspan(e) {
+            Exp::Var(name) => {
+                if !binding {
+                    return Err(invalid());
+                }
+
+                try!(self.check_name(&name, start));
+                Ok(Pattern::Var(name))
+            }
+            Exp::Array(elems) => {
+                let len = elems.len();
+                let mut patterns = Vec::new();
+
+                for (i, elem) in elems.into_iter().enumerate() {
+                    let pattern = match elem.map(|e| unspan(*e)) {
+                        Some(Exp::Spread(ref e)) if i + 1 < len => {
+                            let msg = String::from("Rest element must be last element");
+                            return Err(JsError::SyntaxError(msg).at(start));
+                        }
+                        Some(Exp::Spread(e)) => {
+                            Some(Pattern::Rest(Box::new(try!(self.to_pattern(*e, binding, start)))))
+                        }
+                        Some(e) => Some(try!(self.to_pattern(e, binding, start))),
+                        None => None,
+                    };
+
+                    patterns.push(pattern);
+                }
+
+                Ok(Pattern::Array(patterns))
+            }
+            Exp::Object(props) => {
+                let len = props.len();
+                let mut patterns = Vec::new();
+                let mut rest = None;
+
+                for (i, prop) in props.into_iter().enumerate() {
+                    match prop {
+                        Prop::Shorthand(name) => {
+                            let e = Exp::Var(name.clone());
+                            patterns.push((name, try!(self.to_pattern(e, binding, start))));
+                        }
+                        Prop::Value(key, e) => {
+                            patterns.push((key, try!(self.to_pattern(*e, binding, start))));
+                        }
+                        Prop::Spread(e) if i + 1 == len => {
+                            rest = Some(Box::new(try!(self.to_pattern(*e, binding, start))));
+                        }
+                        _ => return Err(invalid()),
+                    }
+                }
+
+                Ok(Pattern::Object(patterns, rest))
+            }
+            Exp::AssignExp(AssignOp::Assign, target, default) => {
+                let target = if binding { try!(self.to_binding(*target, start)) } else { *target };
+                Ok(Pattern::Default(Box::new(target), *default))
+            }
+            _ => Err(invalid()),
+        }
+    }
+
+    // Converts a pattern that assigns to variables to one that declares them, for the defaults in
+    // the parameters of an arrow function.
+    fn to_binding(&self, p: Pattern, start: &Span) -> Result<Pattern> {
+        Ok(match p {
+            Pattern::Exp(e) => try!(self.to_pattern(*e, true, start)),
+            Pattern::Default(p, default) => {
+                Pattern::Default(Box::new(try!(self.to_binding(*p, start))), default)
+            }
+            Pattern::Rest(p) => Pattern::Rest(Box::new(try!(self.to_binding(*p, start)))),
+            Pattern::Array(elems) => {
+                let mut patterns = Vec::new();
+
+                for elem in elems {
+                    patterns.push(match elem {
+                        Some(p) => Some(try!(self.to_binding(p, start))),
+                        None => None,
+                    });
+                }
+
+                Pattern::Array(patterns)
+            }
+            Pattern::Object(props, rest) => {
+                let mut patterns = Vec::new();
+
+                for (key, p) in props {
+                    patterns.push((key, try!(self.to_binding(p, start))));
+                }
+
+                let rest = match rest {
+                    Some(p) => Some(Box::new(try!(self.to_binding(*p, start)))),
+                    None => None,
+                };
+
+                Pattern::Object(patterns, rest)
+            }
+            p @ Pattern::Var(_) => p,
+        })
+    }
+
+    // Checks that `e` can be assigned to or incremented as a whole.
+    fn check_target(&self, e: &Exp, start: &Span) -> Result<()> {
+        match *e.node() {
+            Exp::Var(_) | Exp::InstanceVar(..) | Exp::KeyAccessor(..) => Ok(()),
+            _ => {
+                let msg = String::from("Invalid left-hand side in assignment");
+                Err(JsError::SyntaxError(msg).at(start))
+            }
+        }
+    }
+
+    // Reads the target of a declaration or parameter: a name, or an array or object pattern.
+    fn binding_target(&mut self) -> Result<Pattern> {
+        if try!(self.eat("[")) {
+            let mut elems = Vec::new();
+
+            while !try!(self.eat("]")) {
+                if try!(self.eat(",")) {
+                    elems.push(None);
+                } else if try!(self.eat("...")) {
+                    elems.push(Some(Pattern::Rest(Box::new(try!(self.binding_target())))));
+                    try!(self.expect("]"));
+                    break;
+                } else {
+                    elems.push(Some(try!(self.binding_element())));
+
+                    if !self.is("]") {
+                        try!(self.expect(","));
+                    }
+                }
+            }
+
+            return Ok(Pattern::Array(elems));
+        }
+
+        if try!(self.eat("{")) {
+            let mut props = Vec::new();
+            let mut rest = None;
+
+            while !try!(self.eat("}")) {
+                if try!(self.eat("...")) {
+                    rest = Some(Box::new(Pattern::Var(try!(self.name()))));
+                    try!(self.expect("}"));
+                    break;
+                }
+
+                let key_span = self.token.span;
+
+                let shorthand = match self.token.token {
+                    Token::Ident(ref name) => Some(name.clone()),
+                    _ => None,
+                };
+
+                let key = match try!(self.prop_key()) {
+                    ClassKey::Name(key) => key,
+                    ClassKey::Computed(_) => {
+                        return Err(JsError::unimplemented("computed keys in patterns"));
+                    }
+                    ClassKey::Private(name) => {
+                        let msg = format!("Unexpected identifier '#{}'", name);
+                        return Err(JsError::SyntaxError(msg).at(&key_span));
+                    }
+                };
+
+                let pattern = if try!(self.eat(":")) {
+                    try!(self.binding_element())
+                } else {
+                    let name = match shorthand {
+                        Some(name) => name,
+                        None => return Err(self.unexpected()),
+                    };
+
+                    try!(self.check_name(&name, &key_span));
+
+                    if try!(self.eat("=")) {
+                        Pattern::Default(Box::new(Pattern::Var(name)), try!(self.assign_exp()))
+                    } else {
+                        Pattern::Var(name)
+                    }
+                };
+
+                props.push((key, pattern));
+
+                if !self.is("}") {
+                    try!(self.expect(","));
+                }
+            }
+
+            return Ok(Pattern::Object(props, rest));
+        }
+
+        Ok(Pattern::Var(try!(self.name())))
+    }
+
+    // A binding target with an optional default, e.g. `[a, b] = []`.
+    fn binding_element(&mut self) -> Result<Pattern> {
+        let target = try!(self.binding_target());
+
+        if try!(self.eat("=")) {
+            Ok(Pattern::Default(Box::new(target), try!(self.assign_exp())))
+        } else {
+            Ok(target)
+        }
+    }
+
+    // Reads the declarators after `var`, `let` or `const`. Only a declaration on the left of a
+    // for-in or for-of loop, which assigns to it, can leave out the initial value of a constant
+    // or a pattern.
+    fn declarations(&mut self, kind: DeclKind, in_for: bool)
+                    -> Result<Vec<(Pattern, Option<Exp>)>> {
+        let mut decls = Vec::new();
+
+        loop {
+            let start = self.token.span;
+            let target = try!(self.binding_target());
+            let init = if try!(self.eat("=")) { Some(try!(self.assign_exp())) } else { None };
+
+            let for_each = in_for && (self.is_word("in") || self.is_word("of"));
+
+            if init.is_none() && !for_each {
+                let what = match target {
+                    Pattern::Var(_) if kind != DeclKind::Const => None,
+                    Pattern::Var(_) => Some("const"),
+                    _ => Some("destructuring"),
+                };
+
+                if let Some(what) = what {
+                    let msg = format!("Missing initializer in {} declaration", what);
+                    try!(self.early_error(JsError::SyntaxError(msg).at(&start)));
+                }
+            }
+
+            decls.push((target, init));
+
+            if !try!(self.eat(",")) {
+                return Ok(decls);
+            }
+        }
+    }
+
+    // Whether the current token starts a `let` declaration rather than being the name `let`.
+    fn at_let_declaration(&self) -> Result<bool> {
+        if !self.is_word("let") {
+            return Ok(false);
+        }
+
+        Ok(match try!(self.peek()).token {
+            Token::Ident(ref word) => word != "in" && word != "instanceof" && word != "of",
+            Token::Punct("[") | Token::Punct("{") => true,
+            _ => false,
+        })
+    }
+
+    // { stmts }
+    fn block(&mut self) -> Result<Vec<Stmt>> {
+        try!(self.expect("{"));
+        let mut stmts = Vec::new();
+
+        while !self.is("}") {
+            // A recovering parse keeps what it has of a block that's never closed
+            if self.token.token == Token::Eof {
+                let e = self.unexpected();
+                try!(self.early_error(e));
+                break;
+            }
+
+            push_stmt(&mut stmts, try!(self.list_item(false)));
+        }
+
+        self.dangling_comments(&mut stmts);
+        try!(self.eat("}"));
+        Ok(stmts)
+    }
+
+    // Reads the body of a loop or a branch of an `if`, which is the statements in it if it's a
+    // block.
+    fn body(&mut self) -> Result<Vec<Stmt>> {
+        if self.is("{") {
+            self.block()
+        } else {
+            let mut stmts = Vec::new();
+            push_stmt(&mut stmts, try!(self.list_item(false)));
+            Ok(stmts)
+        }
+    }
+
+    // Reads a statement in a block or at the top level, or a module item if `module` is set,
+    // along with the comments before it and after it on the same line if they're kept.
+    fn list_item(&mut self, module: bool) -> Result<Stmt> {
+        if !self.options.comments {
+            return self.recovering_item(module);
+        }
+
+        let leading = mem::replace(&mut self.token.comments, Vec::new());
+        let lines_before = self.token.lines_before;
+        let s = try!(self.recovering_item(module));
+
+        let same_line = self.token.comments.iter().take_while(|c| c.lines_before == 0).count();
+        let trailing: Vec<_> = self.token.comments.drain(..same_line).collect();
+
+        if leading.is_empty() && trailing.is_empty() && lines_before < 2 {
+            return Ok(s);
+        }
+
+        Ok(s.with_comments(Comments {
+            leading: leading,
+            trailing: trailing,
+            lines_before: lines_before,
+        }))
+    }
+
+    // Adds the comments left at the end of a block or of the source to the last statement in it,
+    // or to an `Empty` statement if there are no others.
+    fn dangling_comments(&mut self, stmts: &mut Vec<Stmt>) {
+        if self.token.comments.is_empty() {
+            return;
+        }
+
+        let mut dangling = mem::replace(&mut self.token.comments, Vec::new());
+
+        let s = match stmts.pop() {
+            Some(s) => {
+                let mut comments = s.comments().cloned().unwrap_or_default();
+                comments.trailing.append(&mut dangling);
+                s.with_comments(comments)
+            }
+            None => {
+                Stmt::Empty.with_comments(Comments { leading: dangling, ..Comments::default() })
+            }
+        };
+
+        stmts.push(s);
+    }
+
+    // Reads a list item without its comments. A recovering parse replaces a statement with an
+    // error with `Stmt::Error`, skipping the rest of it.
+    fn recovering_item(&mut self, module: bool) -> Result<Stmt> {
+        let start = self.token.span;
+        let depth = self.depth;
+        let result = if module { self.module_item() } else { self.statement() };
+
+        if !self.recovering {
+            return result;
+        }
+
+        match result {
+            Ok(s) if self.token.span.offset != start.offset => return Ok(s),
+            // Nothing was read, so the statement was just a placeholder that's been reported
+            Ok(_) => (),
+            Err(e) => {
+                self.report(e);
+                self.skip_statement(depth);
+            }
+        }
+
+        // What the statement left set isn't carried over to the next one
+        self.no_in = false;
+        self.cover_init = None;
+        self.defer_cover = false;
+
+        // The parse has to move on even from a token that can't start a statement, but a `}`
+        // closes an enclosing block
+        let closes_block = self.is("}") && self.depth > 0;
+
+        if self.token.span.offset == start.offset && self.token.token != Token::Eof &&
+           !closes_block {
+            try!(self.next());
+        }
+
+        Ok(self.spanned_stmt(&start, Stmt::Error))
+    }
+
+    // Skips to the end of a statement with an error, which is a `;` or the `}` of the enclosing
+    // block outside any brackets opened since `depth`, or a statement keyword at the start of a
+    // line.
+    fn skip_statement(&mut self, depth: usize) {
+        loop {
+            let outside = self.depth <= depth;
+
+            match self.token.token {
+                Token::Eof => break,
+                Token::Punct("}") if outside && self.depth > 0 => break,
+                Token::Punct(";") if outside => {
+                    let _ = self.next();
+                    break;
+                }
+                _ if self.at_statement_keyword() => break,
+                _ => (),
+            }
+
+            let _ = self.next();
+        }
+
+        // Brackets left open by the statement are taken as closed
+        self.depth = depth;
+    }
+
+    fn loop_body(&mut self) -> Result<Vec<Stmt>> {
+        let in_loop = mem::replace(&mut self.context.in_loop, true);
+        let body = self.body();
+        self.context.in_loop = in_loop;
+        body
+    }
+
+    // An expression as a statement, where a plain `=` assignment is a `Stmt::Assign`.
+    fn exp_stmt(&self, e: Exp) -> Stmt {
+        if let Exp::AssignExp(AssignOp::Assign, ..) = *e.node() {
+            if let Exp::AssignExp(_, target, value) = unspan(e) {
+                return Stmt::Assign(*target, *value);
+            }
+
+            unreachable!()
+        }
+
+        Stmt::BareExp(e)
+    }
+
+    fn statement(&mut self) -> Result<Stmt> {
+        let start = self.token.span;
+        let s = try!(self.statement_kind());
+        Ok(self.spanned_stmt(&start, s))
+    }
+
+    fn statement_kind(&mut self) -> Result<Stmt> {
+        let start = self.token.span;
+
+        if self.is("{") {
+            return Ok(Stmt::Block(try!(self.block())));
+        }
+
+        if try!(self.eat(";")) {
+            return Ok(Stmt::Empty);
+        }
+
+        let word = match self.token.token {
+            Token::Ident(ref word) => word.clone(),
+            _ => String::new(),
+        };
+
+        let s = match &word[..] {
+            "var" | "let" | "const" if word != "let" || try!(self.at_let_declaration()) => {
+                try!(self.next());
+
+                let kind = match &word[..] {
+                    "var" => DeclKind::Var,
+                    "let" => DeclKind::Let,
+                    _ => DeclKind::Const,
+                };
+
+                let decls = try!(self.declarations(kind, false));
+                try!(self.semicolon());
+                Stmt::Decl(kind, decls)
+            }
+            "function" | "async" if word == "function" || try!(self.at_async_function()) => {
+                match unspan(try!(self.function(&start, true))) {
+                    Exp::Defun(kind, Some(name), params, body) => {
+                        Stmt::FnDecl(kind, name, params, body)
+                    }
+                    _ => unreachable!(),
+                }
+            }
+            "class" => Stmt::ClassDecl(try!(self.class(true))),
+            "if" => {
+                try!(self.next());
+                try!(self.expect("("));
+                let cond = try!(self.expression());
+                try!(self.expect(")"));
+
+                let then_block = try!(self.body());
+                let else_block = if try!(self.eat_word("else")) {
+                    try!(self.body())
+                } else {
+                    Vec::new()
+                };
+
+                Stmt::If(cond, then_block, else_block)
+            }
+            "for" => try!(self.for_stmt()),
+            "while" => {
+                try!(self.next());
+                try!(self.expect("("));
+                let cond = try!(self.expression());
+                try!(self.expect(")"));
+                Stmt::While(cond, try!(self.loop_body()))
+            }
+            "do" => {
+                try!(self.next());
+                let body = try!(self.loop_body());
+                try!(self.expect_word("while"));
+                try!(self.expect("("));
+                let cond = try!(self.expression());
+                try!(self.expect(")"));
+
+                // The semicolon after a do-while loop can always be left out
+                try!(self.eat(";"));
+                Stmt::DoWhile(body, cond)
+            }
+            "break" | "continue" => {
+                try!(self.next());
+                let is_break = word == "break";
+
+                let label = match self.token.token {
+                    Token::Ident(_) if !self.token.newline_before => Some(try!(self.name())),
+                    _ => None,
+                };
+
+                let error = match label {
+                    Some(ref label) => {
+                        match self.context.labels.iter().find(|l| l.0 == *label) {
+                            None => Some(format!("Undefined label '{}'", label)),
+                            Some(&(_, false)) if !is_break => {
+                                Some(format!("Illegal continue statement: '{}' does not denote \
+                                              an iteration statement",
+                                             label))
+                            }
+                            _ => None,
+                        }
+                    }
+                    None if is_break && !(self.context.in_loop || self.context.in_switch) => {
+                        Some(String::from("Illegal break statement"))
+                    }
+                    None if !is_break && !self.context.in_loop => {
+                        Some(String::from("Illegal continue statement: no surrounding iteration \
+                                           statement"))
+                    }
+                    None => None,
+                };
+
+                if let Some(msg) = error {
+                    try!(self.early_error(JsError::SyntaxError(msg).at(&start)));
+                }
+
+                try!(self.semicolon());
+                if is_break { Stmt::Break(label) } else { Stmt::Continue(label) }
+            }
+            "return" => {
+                if !self.context.in_function {
+                    let msg = String::from("Illegal return statement");
+                    try!(self.early_error(JsError::SyntaxError(msg).at(&start)));
+                }
+
+                try!(self.next());
+
+                let e = if self.is(";") || self.is("}") || self.token.token == Token::Eof ||
+                           self.token.newline_before {
+                    Exp::Undefined
+                } else {
+                    try!(self.expression())
+                };
+
+                try!(self.semicolon());
+                Stmt::Ret(e)
+            }
+            "throw" => {
+                try!(self.next());
+
+                if self.token.newline_before {
+                    let msg = String::from("Illegal newline after throw");
+                    return Err(JsError::SyntaxError(msg).at(&self.token.span));
+                }
+
+                let e = try!(self.expression());
+                try!(self.semicolon());
+                Stmt::Throw(Box::new(e))
+            }
+            "try" => try!(self.try_stmt()),
+            "switch" => try!(self.switch()),
+            "import" if !try!(self.at_import_call()) => {
+                let msg = String::from("Cannot use import statement outside a module");
+                return Err(JsError::SyntaxError(msg).at(&start));
+            }
+            "debugger" => return Err(JsError::unimplemented("debugger statements")),
+            "with" => return Err(JsError::unimplemented("with statements")),
+            _ if !word.is_empty() && try!(self.peek()).token == Token::Punct(":") => {
+                let label = try!(self.name());
+                try!(self.next());
+
+                if self.context.labels.iter().any(|l| l.0 == label) {
+                    let msg = format!("Label '{}' has already been declared", label);
+                    try!(self.early_error(JsError::SyntaxError(msg).at(&start)));
+                }
+
+                let is_loop = self.is_word("for") || self.is_word("while") || self.is_word("do");
+                self.context.labels.push((label.clone(), is_loop));
+                let s = self.statement();
+                self.context.labels.pop();
+
+                Stmt::Labeled(label, Box::new(try!(s)))
+            }
+            _ => {
+                let e = try!(self.expression());
+                try!(self.semicolon());
+                self.exp_stmt(e)
+            }
+        };
+
+        Ok(s)
+    }
+
+    fn for_stmt(&mut self) -> Result<Stmt> {
+        try!(self.next());
+
+        if self.is_word("await") {
+            return Err(JsError::unimplemented("for await"));
+        }
+
+        try!(self.expect("("));
+        let start = self.token.span;
+
+        let init = if self.is(";") {
+            Stmt::Empty
+        } else if self.is_word("var") || self.is_word("const") || try!(self.at_let_declaration()) {
+            let kind = match try!(self.next()).token {
+                Token::Ident(ref word) if word == "var" => DeclKind::Var,
+                Token::Ident(ref word) if word == "let" => DeclKind::Let,
+                _ => DeclKind::Const,
+            };
+
+            let no_in = mem::replace(&mut self.no_in, true);
+            let decls = self.declarations(kind, true);
+            self.no_in = no_in;
+
+            let decls = try!(decls);
+
+            if self.is_word("in") || self.is_word("of") {
+                if decls.len() != 1 || decls[0].1.is_some() {
+                    let msg = String::from("Invalid left-hand side in for-in or for-of loop: \
+                                            must have a single binding");
+                    return Err(JsError::SyntaxError(msg).at(&start));
+                }
+
+                let left = self.spanned_stmt(&start, Stmt::Decl(kind, decls));
+                return self.for_each(left);
+            }
+
+            self.spanned_stmt(&start, Stmt::Decl(kind, decls))
+        } else {
+            let no_in = mem::replace(&mut self.no_in, true);
+            // The left side of a for-in or for-of loop can be a pattern
+            self.defer_cover = true;
+            let e = self.expression();
+            self.no_in = no_in;
+
+            let e = try!(e);
+
+            if self.is_word("in") || self.is_word("of") {
+                // A pattern is kept as the array or object literal it was written as
+                match *e.node() {
+                    Exp::Array(_) | Exp::Object(_) => {
+                        try!(self.to_pattern(e.clone(), false, &start));
+                        self.cover_init = None;
+                    }
+                    _ => try!(self.check_target(&e, &start)),
+                }
+
+                let left = self.spanned_stmt(&start, Stmt::BareExp(e));
+                return self.for_each(left);
+            }
+
+            if let Some(span) = self.cover_init.take() {
+                let msg = String::from("Invalid shorthand property initializer");
+                return Err(JsError::SyntaxError(msg).at(&span));
+            }
+
+            let init = self.exp_stmt(e);
+            self.spanned_stmt(&start, init)
+        };
+
+        try!(self.expect(";"));
+        let cond = if self.is(";") { Exp::Bool(true) } else { try!(self.expression()) };
+        try!(self.expect(";"));
+        let step = if self.is(")") { Exp::Undefined } else { try!(self.expression()) };
+        try!(self.expect(")"));
+
+        Ok(Stmt::For(Box::new(init), cond, step, try!(self.loop_body())))
+    }
+
+    // Reads the rest of a for-in or for-of loop from the `in` or `of`.
+    fn for_each(&mut self, left: Stmt) -> Result<Stmt> {
+        let of = self.is_word("of");
+        try!(self.next());
+
+        let right = if of { try!(self.assign_exp()) } else { try!(self.expression()) };
+        try!(self.expect(")"));
+        let body = try!(self.loop_body());
+
+        if of {
+            Ok(Stmt::ForOf(Box::new(left), right, body))
+        } else {
+            Ok(Stmt::ForIn(Box::new(left), right, body))
+        }
+    }
+
+    fn try_stmt(&mut self) -> Result<Stmt> {
+        let start = self.token.span;
+        try!(self.next());
+        let try_block = try!(self.block());
+
+        let has_catch = self.is_word("catch");
+        let (catch_var, catch_block) = if try!(self.eat_word("catch")) {
+            // The catch binding can be left out, e.g. `catch { ... }`
+            let catch_var = if try!(self.eat("(")) {
+                let catch_var = try!(self.binding_target());
+                try!(self.expect(")"));
+                Some(catch_var)
+            } else {
+                None
+            };
+
+            (catch_var, try!(self.block()))
+        } else {
+            (None, Vec::new())
+        };
+
+        let finally_block = if try!(self.eat_word("finally")) {
+            try!(self.block())
+        } else if has_catch {
+            Vec::new()
+        } else {
+            let msg = String::from("Missing catch or finally after try");
+            return Err(JsError::SyntaxError(msg).at(&start));
+        };
+
+        Ok(Stmt::Try(try_block, catch_var, catch_block, finally_block))
+    }
+
+    fn switch(&mut self) -> Result<Stmt> {
+        try!(self.next());
+        try!(self.expect("("));
+        let e = try!(self.expression());
+        try!(self.expect(")"));
+        try!(self.expect("{"));
+
+        let in_switch = mem::replace(&mut self.context.in_switch, true);
+        let cases = self.cases();
+        self.context.in_switch = in_switch;
+
+        Ok(Stmt::Switch(e, try!(cases)))
+    }
+
+    fn cases(&mut self) -> Result<Vec<(Option<Exp>, Vec<Stmt>)>> {
+        let mut cases = Vec::new();
+        let mut has_default = false;
+
+        loop {
+            // Comments before a clause go at the end of the clause before it
+            if let Some(&mut (_, ref mut body)) = cases.last_mut() {
+                self.dangling_comments(body);
+            }
+
+            if try!(self.eat("}")) {
+                break;
+            }
+
+            let start = self.token.span;
+            // and those before the first clause go with its first statement
+            let mut comments = mem::replace(&mut self.token.comments, Vec::new());
+
+            let test = if try!(self.eat_word("case")) {
+                Some(try!(self.expression()))
+            } else if try!(self.eat_word("default")) {
+                if has_default {
+                    let msg = String::from("More than one default clause in switch statement");
+                    try!(self.early_error(JsError::SyntaxError(msg).at(&start)));
+                }
+
+                has_default = true;
+                None
+            } else {
+                return Err(self.unexpected());
+            };
+
+            try!(self.expect(":"));
+            comments.append(&mut self.token.comments);
+            self.token.comments = comments;
+
+            let mut body = Vec::new();
+
+            while !(self.is_word("case") || self.is_word("default") || self.is("}") ||
+                    self.token.token == Token::Eof) {
+                push_stmt(&mut body, try!(self.list_item(false)));
+            }
+
+            cases.push((test, body));
+        }
+
+        Ok(cases)
+    }
+
+    // Reads a statement at the top level of a module, where `import` and `export` are allowed.
+    fn module_item(&mut self) -> Result<Stmt> {
+        let start = self.token.span;
+
+        let s = if self.is_word("import") && !try!(self.at_import_call()) {
+            try!(self.import())
+        } else if self.is_word("export") {
+            try!(self.export())
+        } else {
+            return self.statement();
+        };
+
+        Ok(self.spanned_stmt(&start, s))
+    }
+
+    fn import(&mut self) -> Result<Stmt> {
+        try!(self.next());
+        let mut specs = Vec::new();
+
+        if let Token::Str(_) = self.token.token {
+            let source = try!(self.string_literal());
+            try!(self.semicolon());
+            return Ok(Stmt::Import(specs, source));
+        }
+
+        let mut more = true;
+
+        if let Token::Ident(_) = self.token.token {
+            specs.push(ImportSpec::Default(try!(self.name())));
+            more = try!(self.eat(","));
+        }
+
+        if more && try!(self.eat("*")) {
+            try!(self.expect_word("as"));
+            specs.push(ImportSpec::Namespace(try!(self.name())));
+        } else if more {
+            try!(self.expect("{"));
+
+            while !try!(self.eat("}")) {
+                let span = self.token.span;
+                let (imported, is_str) = try!(self.module_name());
+
+                let local = if try!(self.eat_word("as")) {
+                    try!(self.name())
+                } else if is_str {
+                    return Err(self.unexpected());
+                } else {
+                    try!(self.check_name(&imported, &span));
+                    imported.clone()
+                };
+
+                specs.push(ImportSpec::Named(imported, local));
+
+                if !self.is("}") {
+                    try!(self.expect(","));
+                }
+            }
+        }
+
+        try!(self.expect_word("from"));
+        let source = try!(self.string_literal());
+
+        if self.is_word("with") && !self.token.newline_before {
+            return Err(JsError::unimplemented("import attributes"));
+        }
+
+        try!(self.semicolon());
+        Ok(Stmt::Import(specs, source))
+    }
+
+    fn export(&mut self) -> Result<Stmt> {
+        try!(self.next());
+
+        if try!(self.eat("*")) {
+            let name = if try!(self.eat_word("as")) {
+                Some(try!(self.module_name()).0)
+            } else {
+                None
+            };
+
+            try!(self.expect_word("from"));
+            let source = try!(self.string_literal());
+            try!(self.semicolon());
+            return Ok(Stmt::ExportAll(name, source));
+        }
+
+        if try!(self.eat_word("default")) {
+            let start = self.token.span;
+
+            // A function or class is a declaration here, so it isn't followed by a semicolon
+            let e = if self.is_word("function") || try!(self.at_async_function()) {
+                try!(self.function(&start, false))
+            } else if self.is_word("class") {
+                let class = Exp::Class(Box::new(try!(self.class(false))));
+                self.spanned_exp(&start, class)
+            } else {
+                let e = try!(self.assign_exp());
+                try!(self.semicolon());
+                e
+            };
+
+            return Ok(Stmt::ExportDefault(e));
+        }
+
+        if try!(self.eat("{")) {
+            let mut names = Vec::new();
+            // The first local name that's a string or a reserved word, which is only valid in a
+            // re-export
+            let mut invalid = None;
+
+            while !try!(self.eat("}")) {
+                let span = self.token.span;
+                let (local, is_str) = try!(self.module_name());
+
+                if invalid.is_none() && (is_str || self.check_name(&local, &span).is_err()) {
+                    invalid = Some(span);
+                }
+
+                let exported = if try!(self.eat_word("as")) {
+                    try!(self.module_name()).0
+                } else {
+                    local.clone()
+                };
+
+                names.push((local, exported));
+
+                if !self.is("}") {
+                    try!(self.expect(","));
+                }
+            }
+
+            let source = if try!(self.eat_word("from")) {
+                Some(try!(self.string_literal()))
+            } else if let Some(span) = invalid {
+                return Err(JsError::SyntaxError(String::from("Unexpected export name")).at(&span));
+            } else {
+                None
+            };
+
+            try!(self.semicolon());
+            return Ok(Stmt::ExportNames(names, source));
+        }
+
+        let is_declaration = self.is_word("var") || self.is_word("const") ||
+                             self.is_word("class") || self.is_word("function") ||
+                             try!(self.at_let_declaration()) || try!(self.at_async_function());
+
+        if !is_declaration {
+            return Err(self.unexpected());
+        }
+
+        Ok(Stmt::Export(Box::new(try!(self.statement()))))
+    }
+
+    // Reads the name of an import or export, which can be any identifier or a string, and
+    // whether it was a string.
+    fn module_name(&mut self) -> Result<(String, bool)> {
+        if let Token::Str(_) = self.token.token {
+            Ok((try!(self.string_literal()), true))
+        } else {
+            Ok((try!(self.ident_name()), false))
+        }
+    }
+}
+
+/// Parses a single expression, e.g. `a + b * c`.
+#[allow(non_snake_case)]
+pub fn parse_Exp(source: &str) -> Result<Exp> {
+    Parser::new(source, ParseOptions::default()).parse_exp()
+}
+
+/// Parses a script. A script of more than one statement is returned as a `Seq` of them, and an
+/// empty one as `Stmt::Empty`.
+#[allow(non_snake_case)]
+pub fn parse_Stmt(source: &str) -> Result<Stmt> {
+    let mut stmts = match try!(Parser::new(source, ParseOptions::default()).parse_program()) {
+        Program::Script(stmts) | Program::Module(stmts) => stmts,
+    };
+
+    let last = stmts.pop().unwrap_or(Stmt::Empty);
+    Ok(stmts.into_iter().rev().fold(last, |rest, s| Stmt::Seq(Box::new(s), Box::new(rest))))
+}
+
+fn is_reserved(name: &str) -> bool {
+    RESERVED.contains(&name)
+}
+
+// Whether `token` can start the key of a property or class member.
+fn starts_key(token: &Token) -> bool {
+    match *token {
+        Token::Ident(_) | Token::Str(_) | Token::Num(_) | Token::PrivateName(_) => true,
+        Token::Punct(p) => p == "[",
+        _ => false,
+    }
+}
+
+// Adds `s` to a list of statements. An empty statement does nothing there, so it's left out, as it
+// is when printing, unless it holds comments.
+fn push_stmt(stmts: &mut Vec<Stmt>, s: Stmt) {
+    if !matches!(*s.node(), Stmt::Empty) || s.comments().is_some() {
+        stmts.push(s);
+    }
+}
+
+fn unspan(e: Exp) -> Exp {
+    match e {
+        Exp::Commented(_, e) | Exp::Spanned(_, e) => unspan(*e),
+        e => e,
+    }
+}
@@ -0,0 +1,157 @@
+// Source Map v3 (https://sourcemaps.info/spec.html) output for `CodeGen`. Lines and columns here
+// are 0-based, as in the format itself, and generated columns count UTF-16 code units.
+
+use serde_json::{self, Value};
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Mapping {
+    pub generated_line: usize,
+    pub generated_column: usize,
+    // (file, line, column) in the original source, or `None` for generated code that has no
+    // original location.
+    pub original: Option<(usize, usize, usize)>,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct SourceMap {
+    // Name of the generated file.
+    pub file: Option<String>,
+    // Names of the original files, indexed by the `file` of the spans in the AST.
+    pub sources: Vec<String>,
+    // Optionally, the text of each original file, so tools don't need to fetch it.
+    pub sources_content: Vec<Option<String>>,
+    mappings: Vec<Mapping>,
+}
+
+impl SourceMap {
+    pub fn new(mappings: Vec<Mapping>) -> SourceMap {
+        SourceMap { mappings: mappings, ..SourceMap::default() }
+    }
+
+    pub fn mappings(&self) -> &[Mapping] {
+        &self.mappings
+    }
+
+    /// The `mappings` field of the source map: Base64 VLQ segments, with each generated line
+    /// separated by `;`.
+    pub fn encode_mappings(&self) -> String {
+        let mut encoded = String::new();
+        let mut line = 0;
+        let (mut prev_column, mut prev_file, mut prev_line, mut prev_orig_column) = (0, 0, 0, 0);
+
+        for (i, mapping) in self.mappings.iter().enumerate() {
+            if mapping.generated_line != line || i == 0 {
+                while line < mapping.generated_line {
+                    encoded.push(';');
+                    line += 1;
+                }
+
+                prev_column = 0;
+            } else {
+                encoded.push(',');
+            }
+
+            encode_vlq(&mut encoded, mapping.generated_column as i64 - prev_column);
+            prev_column = mapping.generated_column as i64;
+
+            if let Some((file, orig_line, orig_column)) = mapping.original {
+                encode_vlq(&mut encoded, file as i64 - prev_file);
+                encode_vlq(&mut encoded, orig_line as i64 - prev_line);
+                encode_vlq(&mut encoded, orig_column as i64 - prev_orig_column);
+
+                prev_file = file as i64;
+                prev_line = orig_line as i64;
+                prev_orig_column = orig_column as i64;
+            }
+        }
+
+        encoded
+    }
+
+    pub fn to_json(&self) -> String {
+        // Every file referred to by a mapping needs an entry, even if it wasn't given a name
+        let num_sources = self.mappings.iter()
+            .filter_map(|m| m.original.map(|(file, _, _)| file + 1))
+            .chain(Some(self.sources.len()))
+            .max()
+            .unwrap_or(0);
+
+        let sources: Vec<_> = (0..num_sources)
+            .map(|i| self.sources.get(i).cloned().unwrap_or_default())
+            .collect();
+
+        let mut map = serde_json::Map::new();
+        map.insert(String::from("version"), Value::from(3));
+
+        if let Some(ref file) = self.file {
+            map.insert(String::from("file"), Value::from(file.clone()));
+        }
+
+        map.insert(String::from("sources"), Value::from(sources));
+
+        if !self.sources_content.is_empty() {
+            let content: Vec<_> = self.sources_content.iter()
+                .map(|c| c.clone().map(Value::from).unwrap_or(Value::Null))
+                .collect();
+
+            map.insert(String::from("sourcesContent"), Value::from(content));
+        }
+
+        map.insert(String::from("names"), Value::Array(Vec::new()));
+        map.insert(String::from("mappings"), Value::from(self.encode_mappings()));
+
+        Value::Object(map).to_string()
+    }
+}
+
+const BASE64: &'static [u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+// Appends `n` as a Base64 VLQ: the sign goes in the lowest bit, then the value is written in
+// groups of five bits, least significant first, with a continuation bit on all but the last.
+fn encode_vlq(out: &mut String, n: i64) {
+    let mut vlq = if n < 0 { ((-n) << 1) | 1 } else { n << 1 };
+
+    loop {
+        let mut digit = vlq & 0b11111;
+        vlq >>= 5;
+
+        if vlq > 0 {
+            digit |= 0b100000;
+        }
+
+        out.push(BASE64[digit as usize] as char);
+
+        if vlq == 0 {
+            break;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_vlq() {
+        let mut out = String::new();
+
+        for &n in &[0, 1, -1, 15, 16, -16, 1000] {
+            encode_vlq(&mut out, n);
+            out.push(' ');
+        }
+
+        assert_eq!("A C D e gB hB w+B ", out);
+    }
+
+    #[test]
+    fn test_encode_mappings() {
+        let map = SourceMap::new(vec![
+            Mapping { generated_line: 0, generated_column: 0, original: Some((0, 0, 0)) },
+            Mapping { generated_line: 0, generated_column: 4, original: Some((0, 0, 6)) },
+            Mapping { generated_line: 0, generated_column: 7, original: None },
+            Mapping { generated_line: 2, generated_column: 2, original: Some((1, 3, 2)) },
+        ]);
+
+        assert_eq!("AAAA,IAAM,G;;ECGJ", map.encode_mappings());
+    }
+}
@@ -0,0 +1,59 @@
+use std::fmt::{Display, Error, Formatter};
+
+// A region of source text. `offset` and `len` are measured in bytes, while `line` and `column`
+// are 1-based and meant for humans.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct Span {
+    pub file: usize,
+    pub offset: usize,
+    pub line: usize,
+    // Counted in UTF-16 code units, like JavaScript engines and source maps count them, so a
+    // character outside the BMP takes two columns. Spans built by hand have to count the same way,
+    // since source maps made without the source text take their columns straight from here.
+    pub column: usize,
+    pub len: usize,
+}
+
+impl Span {
+    pub fn new(file: usize, offset: usize, line: usize, column: usize, len: usize) -> Span {
+        Span { file: file, offset: offset, line: line, column: column, len: len }
+    }
+
+    /// Byte offset one past the end of the span.
+    pub fn end(&self) -> usize {
+        self.offset + self.len
+    }
+
+    /// The smallest span covering both `self` and `other`; the line and column are taken from
+    /// whichever span starts first.
+    pub fn to(&self, other: &Span) -> Span {
+        let (first, last) = if self.offset <= other.offset { (self, other) } else { (other, self) };
+        let end = if first.end() > last.end() { first.end() } else { last.end() };
+
+        Span { len: end - first.offset, ..*first }
+    }
+}
+
+impl Display for Span {
+    fn fmt(&self, fmt: &mut Formatter) -> Result<(), Error> {
+        write!(fmt, "{}:{}", self.line, self.column)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to() {
+        let a = Span::new(0, 4, 1, 5, 3);
+        let b = Span::new(0, 10, 2, 1, 6);
+        assert_eq!(Span::new(0, 4, 1, 5, 12), a.to(&b));
+        assert_eq!(Span::new(0, 4, 1, 5, 12), b.to(&a));
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!("3:14", format!("{}", Span::new(0, 40, 3, 14, 1)));
+    }
+}
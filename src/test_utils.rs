@@ -2,7 +2,7 @@ use std::cell::RefCell;
 use std::rc::Rc;
 
 use alloc_box::AllocBox;
-use ast::{Exp, Stmt};
+use ast::{Exp, Pattern, Stmt};
 use types::js_fn::JsFnStruct;
 use types::js_obj::JsObjStruct;
 use types::js_str::JsStrStruct;
@@ -24,7 +24,8 @@ pub fn make_obj(kvs: Vec<(JsKey, JsVar, Option<JsPtrEnum>)>, heap: Rc<RefCell<Al
 
 pub fn make_fn(name: &Option<String>, params: &Vec<String>) -> (JsVar, JsPtrEnum) {
     let var = JsVar::new(JsType::JsPtr(JsPtrTag::JsFn { name: None }));
-    (var, JsPtrEnum::JsFn(JsFnStruct::new(name, params, &vec![Stmt::BareExp(Exp::Undefined)])))
+    let params = params.iter().cloned().map(Pattern::Var).collect();
+    (var, JsPtrEnum::JsFn(JsFnStruct::new(name, &params, &vec![Stmt::BareExp(Exp::Undefined)])))
 }
 
 pub fn make_alloc_box() -> Rc<RefCell<AllocBox>> {
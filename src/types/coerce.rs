@@ -40,6 +40,7 @@ impl AsBool for JsPtrEnum {
             JsPtrEnum::JsSym(_) |
             JsPtrEnum::JsFn(_) |
             JsPtrEnum::JsObj(_) |
+            JsPtrEnum::JsRegExp(_) |
             JsPtrEnum::NativeFn(_) => true,
             JsPtrEnum::JsStr(ref s) => s.text.len() != 0,
             JsPtrEnum::NativeVar(ref nv) => nv.ptr.as_ref().map(|ptr| ptr.as_bool()).unwrap_or(nv.var.as_bool()),
@@ -103,6 +104,7 @@ impl AsString for JsType {
             JsPtr(JsPtrTag::JsSym) => "Symbol(...)",
             JsPtr(JsPtrTag::JsStr) => "\"...\"",
             JsPtr(JsPtrTag::JsObj) => "{ ... }",
+            JsPtr(JsPtrTag::JsRegExp) => "/.../",
             JsPtr(JsPtrTag::JsFn{..}) => "function() { ... }",
             JsPtr(JsPtrTag::NativeFn{..}) => "function() { [native code] }",
             JsPtr(JsPtrTag::NativeVar{..}) => "[native code]",
@@ -120,6 +122,7 @@ impl AsString for JsPtrEnum {
 
             // TODO: Check object's `toString` method
             JsPtrEnum::JsObj(_) => String::from("[object Object]"),
+            JsPtrEnum::JsRegExp(ref r) => format!("{}", r),
 
             // TODO: A function's string representation is apparently the string of source code that
             // created it; the AST doesn't currently support this, so we'll need to do some
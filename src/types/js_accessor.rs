@@ -0,0 +1,202 @@
+use std::cell::RefCell;
+use std::fmt::{Display, Formatter, Error};
+use std::rc::Rc;
+
+use ast::{Prop, PropKey};
+use backend::Backend;
+use js_error;
+use super::js_fn::JsFnStruct;
+use super::js_var::{JsPtrEnum, JsType, JsVar};
+
+// For storing properties defined with `get` and `set` in an object literal. These play the part
+// that a `NativeVar` does for native code, except that the getter and setter are JavaScript
+// functions, which `get` and `set` run through the backend with the object as `this`. An accessor
+// isn't a value, so it's kept in the object's `accessors` rather than in a `JsPtrEnum`.
+#[derive(Clone, Debug)]
+pub struct JsAccessor {
+    pub name: String,
+    pub getter: Option<JsFnStruct>,
+    pub setter: Option<JsFnStruct>,
+}
+
+impl JsAccessor {
+    pub fn new(name: &str, getter: Option<JsFnStruct>, setter: Option<JsFnStruct>) -> JsAccessor {
+        JsAccessor { name: String::from(name), getter: getter, setter: setter }
+    }
+
+    /// Builds the accessor for a `get` or `set` property of an object literal, or returns `None`
+    /// for any other kind of property. A computed key isn't known until it's evaluated, so the
+    /// accessor for one has to be built with `new` instead.
+    pub fn from_prop(prop: &Prop) -> Option<JsAccessor> {
+        match *prop {
+            Prop::Get(PropKey::Name(ref name), ref body) => {
+                let getter = JsFnStruct::new(&Some(format!("get {}", name)), &vec![], body);
+                Some(JsAccessor::new(name, Some(getter), None))
+            }
+            Prop::Set(PropKey::Name(ref name), ref param, ref body) => {
+                let setter = JsFnStruct::new(&Some(format!("set {}", name)), &vec![param.clone()],
+                                             body);
+                Some(JsAccessor::new(name, None, Some(setter)))
+            }
+            _ => None,
+        }
+    }
+
+    /// Reads the property by calling the getter, or gives `undefined` if there isn't one.
+    pub fn get(&self, backend: Rc<RefCell<Backend>>, this: Option<(JsVar, JsPtrEnum)>)
+               -> js_error::Result<(JsVar, Option<JsPtrEnum>)> {
+        match self.getter {
+            Some(ref getter) => backend.borrow_mut().call(getter, this, vec![]),
+            None => Ok((JsVar::new(JsType::JsUndef), None)),
+        }
+    }
+
+    /// Writes the property by calling the setter with the new value. Without a setter, the
+    /// assignment is ignored, as it is outside of strict mode.
+    pub fn set(&self, backend: Rc<RefCell<Backend>>, this: Option<(JsVar, JsPtrEnum)>, var: JsVar,
+               ptr: Option<JsPtrEnum>) -> js_error::Result<()> {
+        if let Some(ref setter) = self.setter {
+            try!(backend.borrow_mut().call(setter, this, vec![(var, ptr)]));
+        }
+
+        Ok(())
+    }
+
+    /// Adds the getter and setter of `other` to this accessor, replacing any that are already
+    /// defined, so that `{get a() {}, set a(v) {}}` ends up as a single property.
+    pub fn merge(&mut self, other: JsAccessor) {
+        if other.getter.is_some() {
+            self.getter = other.getter;
+        }
+
+        if other.setter.is_some() {
+            self.setter = other.setter;
+        }
+    }
+}
+
+impl Display for JsAccessor {
+    fn fmt(&self, fmt: &mut Formatter) -> Result<(), Error> {
+        match (&self.getter, &self.setter) {
+            (&Some(_), &Some(_)) => write!(fmt, "[Getter/Setter]"),
+            (&Some(_), &None) => write!(fmt, "[Getter]"),
+            (&None, _) => write!(fmt, "[Setter]"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    use alloc_box::AllocBox;
+    use ast::{Exp, Pattern, Prop, PropKey, Stmt};
+    use backend::Backend;
+    use gc_error::GcError;
+    use js_error;
+    use test_utils;
+    use types::binding::Binding;
+    use types::js_fn::JsFnStruct;
+    use types::js_str::JsStrStruct;
+    use types::js_var::{JsKey, JsPtrEnum, JsType, JsVar};
+
+    // Records the functions it's asked to call, and gives back the number of arguments.
+    struct CallLog(Vec<(Option<String>, usize)>);
+
+    impl Backend for CallLog {
+        fn alloc(&mut self, _: JsVar, _: Option<JsPtrEnum>) -> Result<Binding, GcError> {
+            unimplemented!()
+        }
+
+        fn load(&mut self, _: &Binding) -> Result<(JsVar, Option<JsPtrEnum>), GcError> {
+            unimplemented!()
+        }
+
+        fn store(&mut self, _: JsVar, _: Option<JsPtrEnum>) -> Result<(), GcError> {
+            unimplemented!()
+        }
+
+        fn get_alloc_box(&self) -> Rc<RefCell<AllocBox>> {
+            Rc::new(RefCell::new(AllocBox::new()))
+        }
+
+        fn call(&mut self, func: &JsFnStruct, _: Option<(JsVar, JsPtrEnum)>,
+                args: Vec<(JsVar, Option<JsPtrEnum>)>)
+                -> js_error::Result<(JsVar, Option<JsPtrEnum>)> {
+            self.0.push((func.name.clone(), args.len()));
+            Ok((JsVar::new(JsType::JsNum(args.len() as f64)), None))
+        }
+    }
+
+    #[test]
+    fn test_from_prop() {
+        let get = Prop::Get(PropKey::Name(String::from("a")), vec![Stmt::Ret(Exp::Float(1.0))]);
+        let set = Prop::Set(PropKey::Name(String::from("a")), Pattern::from("v"), vec![]);
+
+        let mut accessor = JsAccessor::from_prop(&get).unwrap();
+        assert_eq!(Some(String::from("get a")), accessor.getter.as_ref().unwrap().name);
+        assert_eq!("[Getter]", &format!("{}", accessor));
+
+        accessor.merge(JsAccessor::from_prop(&set).unwrap());
+        assert_eq!(1, accessor.setter.as_ref().unwrap().length());
+        assert_eq!("[Getter/Setter]", &format!("{}", accessor));
+
+        assert!(JsAccessor::from_prop(&Prop::Shorthand(String::from("a"))).is_none());
+    }
+
+    #[test]
+    fn test_get_set() {
+        let log = Rc::new(RefCell::new(CallLog(Vec::new())));
+        let get = Prop::Get(PropKey::Name(String::from("a")), vec![]);
+        let set = Prop::Set(PropKey::Name(String::from("a")), Pattern::from("v"), vec![]);
+
+        let getter = JsAccessor::from_prop(&get).unwrap();
+        let (var, _) = getter.get(log.clone(), None).unwrap();
+        assert_eq!(JsType::JsNum(0.0), var.t);
+
+        // Without a setter, assigning to the property does nothing.
+        getter.set(log.clone(), None, JsVar::new(JsType::JsNull), None).unwrap();
+
+        let setter = JsAccessor::from_prop(&set).unwrap();
+        setter.set(log.clone(), None, JsVar::new(JsType::JsNull), None).unwrap();
+
+        // Without a getter, reading the property gives `undefined`.
+        let (var, _) = setter.get(log.clone(), None).unwrap();
+        assert_eq!(JsType::JsUndef, var.t);
+
+        assert_eq!(vec![(Some(String::from("get a")), 0), (Some(String::from("set a")), 1)],
+                   log.borrow().0);
+    }
+
+    #[test]
+    fn test_object_accessors() {
+        let heap = test_utils::make_alloc_box();
+        let key = JsKey::JsStr(JsStrStruct::new("a"));
+        let (var, ptr) = test_utils::make_obj(vec![(key.clone(), test_utils::make_num(1.0), None)],
+                                              heap.clone());
+        heap.borrow_mut().alloc(var.unique.clone(), ptr.clone()).unwrap();
+
+        let mut obj = match ptr {
+            JsPtrEnum::JsObj(obj) => obj,
+            _ => unreachable!(),
+        };
+
+        let get = Prop::Get(PropKey::Name(String::from("a")), vec![]);
+        let set = Prop::Set(PropKey::Name(String::from("a")), Pattern::from("v"), vec![]);
+
+        // An accessor replaces the value, and the getter and setter end up on the same property
+        obj.add_accessor(&var.unique, key.clone(), JsAccessor::from_prop(&get).unwrap(),
+                         &mut *heap.borrow_mut());
+        obj.add_accessor(&var.unique, key.clone(), JsAccessor::from_prop(&set).unwrap(),
+                         &mut *heap.borrow_mut());
+        assert!(obj.dict.get(&key).is_none());
+        assert_eq!("[Getter/Setter]", &format!("{}", obj.accessors[&key]));
+
+        obj.add_key(&var.unique, key.clone(), test_utils::make_num(2.0), None,
+                    &mut *heap.borrow_mut());
+        assert!(obj.accessors.is_empty());
+        assert_eq!(JsType::JsNum(2.0), obj.dict[&key].t);
+    }
+}
@@ -1,6 +1,6 @@
 use std::fmt::{Display, Formatter, Error};
 
-use ast::Stmt;
+use ast::{FnKind, Pattern, Stmt};
 
 // For storing functions.
 // In the future, this will have to store some sense of local variable scope,
@@ -8,23 +8,62 @@ use ast::Stmt;
 #[derive(Clone, Debug)]
 pub struct JsFnStruct {
     pub name: Option<String>,
-    pub params: Vec<String>,
+    pub params: Vec<Pattern>,
     pub stmt: Vec<Stmt>,
+    // Arrow functions take `this` from the scope they were defined in, and can't be used as
+    // constructors.
+    pub arrow: bool,
+    // Async functions and generators don't run their body when they're called, but return a
+    // promise or an iterator that does.
+    pub kind: FnKind,
 }
 
 impl JsFnStruct {
-    pub fn new(name: &Option<String>, params: &Vec<String>, block: &Vec<Stmt>) -> JsFnStruct {
+    pub fn new(name: &Option<String>, params: &Vec<Pattern>, block: &Vec<Stmt>) -> JsFnStruct {
         JsFnStruct {
             name: name.clone(),
             params: params.clone(),
             stmt: block.clone(),
+            arrow: false,
+            kind: FnKind::Normal,
         }
     }
+
+    pub fn new_arrow(name: &Option<String>, params: &Vec<Pattern>, block: &Vec<Stmt>)
+                     -> JsFnStruct {
+        JsFnStruct { arrow: true, ..JsFnStruct::new(name, params, block) }
+    }
+
+    /// The number of arguments the function expects, as in its `length` property: the
+    /// parameters before the first one with a default value or a rest parameter.
+    pub fn length(&self) -> usize {
+        self.params.iter()
+            .take_while(|p| !matches!(**p, Pattern::Default(..) | Pattern::Rest(_)))
+            .count()
+    }
+
+    pub fn with_kind(self, kind: FnKind) -> JsFnStruct {
+        JsFnStruct { kind: kind, ..self }
+    }
+
+    /// Whether the function can be called with `new`, which async functions and generators can't.
+    pub fn is_constructor(&self) -> bool {
+        !self.arrow && self.kind == FnKind::Normal
+    }
 }
 
 impl Display for JsFnStruct {
     fn fmt(&self, mut fmt: &mut Formatter) -> Result<(), Error> {
-        try!(write!(fmt, "function {}(", self.name.clone().unwrap_or(String::new())));
+        if self.kind.is_async() {
+            try!(write!(fmt, "async "));
+        }
+
+        if self.arrow {
+            try!(write!(fmt, "("));
+        } else {
+            try!(write!(fmt, "function{} {}(", if self.kind.is_generator() { "*" } else { "" },
+                        self.name.clone().unwrap_or(String::new())));
+        }
 
         for (i, param) in self.params.iter().enumerate() {
             if i != 0 {
@@ -34,15 +73,59 @@ impl Display for JsFnStruct {
             try!(write!(fmt, "{}", param));
         }
 
-        try!(write!(fmt, ") {{\n"));
+        try!(write!(fmt, "){} {{\n", if self.arrow { " =>" } else { "" }));
         for s in &self.stmt {
-            try!(s.fmt_helper(&mut fmt, 2));
+            if !matches!(*s.node(), Stmt::Empty) {
+                try!(s.fmt_helper(&mut fmt, 2));
+            }
         }
-        write!(fmt, "\n}}")
+        write!(fmt, "}}")
     }
 }
 
 #[cfg(test)]
 mod tests {
-    // TODO tests for fn objs
+    use super::*;
+    use ast::Exp;
+
+    #[test]
+    fn test_arrow_fn() {
+        let body = vec![Stmt::Ret(Exp::Var(String::from("x")))];
+        let arrow = JsFnStruct::new_arrow(&None, &vec![Pattern::from("x")], &body);
+
+        assert!(!arrow.is_constructor());
+        assert!(JsFnStruct::new(&None, &vec![], &body).is_constructor());
+        assert_eq!("(x) => {\n  return x;\n}", &format!("{}", arrow));
+        assert_eq!("async (x) => {\n  return x;\n}",
+                   &format!("{}", arrow.with_kind(FnKind::Async)));
+    }
+
+    #[test]
+    fn test_kind() {
+        let name = Some(String::from("g"));
+        let body = vec![Stmt::BareExp(Exp::Yield(Some(Box::new(Exp::Float(1.0)))))];
+        let gen = JsFnStruct::new(&name, &vec![], &body).with_kind(FnKind::Generator);
+
+        assert!(!gen.is_constructor());
+        assert!(gen.kind.is_generator() && !gen.kind.is_async());
+        assert_eq!("function* g() {\n  yield 1;\n}", &format!("{}", gen));
+        assert_eq!("async function* g() {\n  yield 1;\n}",
+                   &format!("{}", gen.with_kind(FnKind::AsyncGenerator)));
+    }
+
+    #[test]
+    fn test_length() {
+        let params = vec![
+            Pattern::from("a"),
+            Pattern::Array(vec![Some(Pattern::from("b"))]),
+            Pattern::Default(Box::new(Pattern::from("c")), Exp::Float(1.0)),
+            Pattern::from("d"),
+            Pattern::Rest(Box::new(Pattern::from("rest"))),
+        ];
+
+        assert_eq!(2, JsFnStruct::new(&None, &params, &vec![]).length());
+        assert_eq!(0, JsFnStruct::new(&None, &params[4..].to_vec(), &vec![]).length());
+        assert_eq!("function f(a, [b], c = 1, d, ...rest) {\n}",
+                   &format!("{}", JsFnStruct::new(&Some(String::from("f")), &params, &vec![])));
+    }
 }
@@ -6,6 +6,7 @@ use std::vec::Vec;
 
 use alloc_box::AllocBox;
 use super::binding::UniqueBinding;
+use super::js_accessor::JsAccessor;
 use super::js_var::{JsVar, JsKey, JsType, JsPtrEnum};
 
 macro_rules! ptr_type_mismatch {
@@ -21,6 +22,9 @@ pub struct JsObjStruct {
     pub proto: JsProto,
     pub name: String,
     pub dict: HashMap<JsKey, JsVar>,
+    // Properties defined with `get` and `set`. These aren't values, since reading or writing one
+    // calls a function, so they're kept apart from `dict`.
+    pub accessors: HashMap<JsKey, JsAccessor>,
 }
 
 impl JsObjStruct {
@@ -35,9 +39,12 @@ impl JsObjStruct {
             None => Vec::new(),
         };
 
+        let accessors = proto.as_ref().map(|obj| obj.accessors.clone()).unwrap_or_default();
+
         JsObjStruct {
             proto: proto,
             name: String::from(name),
+            accessors: accessors,
             dict: proto_vec.into_iter().chain(kv_tuples.into_iter()).map(|(k, v, ptr)| {
                 match v.t {
                     JsType::JsPtr(ref tag) => match ptr {
@@ -75,15 +82,52 @@ impl JsObjStruct {
 
         match allocator.find_id(obj_binding) {
             Some(ref ptr) => match &mut *(ptr.borrow_mut()) {
-                &mut JsPtrEnum::JsObj(ref mut obj) => obj.dict.insert(k.clone(), v.clone()),
+                &mut JsPtrEnum::JsObj(ref mut obj) => {
+                    obj.accessors.remove(&k);
+                    obj.dict.insert(k.clone(), v.clone())
+                }
                 _ => panic!("Binding does not belong to an object!"),
             },
             None => panic!("No pointer with matching binding found!"),
         };
 
+        self.accessors.remove(&k);
         self.dict.insert(k, v);
     }
 
+    /// Makes `k` an accessor property, replacing its value if it has one. If it's already an
+    /// accessor, `accessor` is merged into it, so that a getter and a setter defined separately
+    /// end up on the same property.
+    pub fn add_accessor(&mut self, obj_binding: &UniqueBinding, k: JsKey, accessor: JsAccessor,
+                        allocator: &mut AllocBox) {
+        if let Some(var) = self.dict.remove(&k) {
+            if let JsType::JsPtr(_) = var.t {
+                allocator.condemn(var.unique).expect("Unable to whiten!");
+            }
+        }
+
+        let accessor = match self.accessors.remove(&k) {
+            Some(mut existing) => {
+                existing.merge(accessor);
+                existing
+            }
+            None => accessor,
+        };
+
+        match allocator.find_id(obj_binding) {
+            Some(ref ptr) => match &mut *(ptr.borrow_mut()) {
+                &mut JsPtrEnum::JsObj(ref mut obj) => {
+                    obj.dict.remove(&k);
+                    obj.accessors.insert(k.clone(), accessor.clone());
+                }
+                _ => panic!("Binding does not belong to an object!"),
+            },
+            None => panic!("No pointer with matching binding found!"),
+        };
+
+        self.accessors.insert(k, accessor);
+    }
+
     pub fn remove_key(&mut self, obj_binding: &UniqueBinding, k: &JsKey, allocator: &mut AllocBox)
         -> Option<(JsVar, Option<JsPtrEnum>)>{
         let var_opt = match &mut *(allocator.find_id(obj_binding).expect("No pointer with matching binding found!").borrow_mut()) {
@@ -129,6 +173,14 @@ impl Display for JsObjStruct {
             try!(write!(fmt, "{}: {}", key, val));
         }
 
+        for (i, (ref key, ref accessor)) in self.accessors.iter().enumerate() {
+            if i != 0 || !self.dict.is_empty() {
+                try!(write!(fmt, ", "));
+            }
+
+            try!(write!(fmt, "{}: {}", key, accessor));
+        }
+
         write!(fmt, " }}")
     }
 }
@@ -0,0 +1,197 @@
+use std::fmt::{self, Display, Formatter};
+
+use regress::{Match, Regex};
+
+use js_error::{JsError, Result};
+
+// The flags a regex can have, in the order that `RegExp.prototype.flags` lists them.
+const FLAGS: &'static str = "dgimsuvy";
+
+// `RegExp`
+#[derive(Clone, Debug)]
+pub struct JsRegExpStruct {
+    pub source: String,
+    pub flags: String,
+    // Where the next search starts from in a global or sticky regex, in UTF-16 code units.
+    pub last_index: usize,
+    regex: Regex,
+}
+
+// The result of a successful `exec`. As in JavaScript, `index` counts UTF-16 code units.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RegExpMatch {
+    pub index: usize,
+    // The matched text, followed by the text of each capture group, or `None` for a group that
+    // didn't take part in the match.
+    pub captures: Vec<Option<String>>,
+    // The named capture groups, in the order they appear in the pattern.
+    pub groups: Vec<(String, Option<String>)>,
+}
+
+impl JsRegExpStruct {
+    /// Compiles the regex `/source/flags`. A pattern that doesn't parse, or an unknown or repeated
+    /// flag, is a SyntaxError, just as it is for the `RegExp` constructor.
+    pub fn new(source: &str, flags: &str) -> Result<JsRegExpStruct> {
+        for (i, c) in flags.char_indices() {
+            if !FLAGS.contains(c) || flags[i + c.len_utf8()..].contains(c) {
+                return Err(JsError::SyntaxError(format!("Invalid regular expression flags '{}'",
+                                                        flags)));
+            }
+        }
+
+        // `u` and `v` are different modes that can't be combined
+        if flags.contains('u') && flags.contains('v') {
+            return Err(JsError::SyntaxError(format!("Invalid regular expression flags '{}'",
+                                                    flags)));
+        }
+
+        let regex = match Regex::with_flags(source, flags) {
+            Ok(regex) => regex,
+            Err(e) => {
+                return Err(JsError::SyntaxError(format!("Invalid regular expression: /{}/: {}",
+                                                        source, e)))
+            }
+        };
+
+        Ok(JsRegExpStruct {
+            source: String::from(source),
+            flags: FLAGS.chars().filter(|&c| flags.contains(c)).collect(),
+            last_index: 0,
+            regex: regex,
+        })
+    }
+
+    pub fn global(&self) -> bool {
+        self.flags.contains('g')
+    }
+
+    pub fn sticky(&self) -> bool {
+        self.flags.contains('y')
+    }
+
+    /// Searches `s` as `RegExp.prototype.exec` does. A global or sticky regex starts from
+    /// `last_index` and moves it past the match, or back to 0 if there isn't one; a sticky regex
+    /// only matches at `last_index` itself. Other regexes search from the start and leave
+    /// `last_index` alone.
+    pub fn exec(&mut self, s: &str) -> Option<RegExpMatch> {
+        let stateful = self.global() || self.sticky();
+        let start = if stateful { self.last_index } else { 0 };
+
+        let found = byte_offset(s, start).and_then(|offset| {
+            self.regex.find_from(s, offset).next().and_then(|m| {
+                if self.sticky() && m.start() != offset { None } else { Some(m) }
+            })
+        });
+
+        match found {
+            Some(m) => {
+                if stateful {
+                    self.last_index = utf16_len(&s[..m.end()]);
+                }
+
+                Some(RegExpMatch::new(s, &m))
+            }
+            None => {
+                if stateful {
+                    self.last_index = 0;
+                }
+
+                None
+            }
+        }
+    }
+
+    /// Whether the regex matches `s`, with the same effect on `last_index` as `exec`.
+    pub fn test(&mut self, s: &str) -> bool {
+        self.exec(s).is_some()
+    }
+}
+
+impl RegExpMatch {
+    fn new(s: &str, m: &Match) -> RegExpMatch {
+        let text = |range: Option<::std::ops::Range<usize>>| range.map(|r| String::from(&s[r]));
+
+        RegExpMatch {
+            index: utf16_len(&s[..m.start()]),
+            captures: m.groups().map(&text).collect(),
+            groups: m.named_groups()
+                     .map(|(name, range)| (String::from(name), text(range)))
+                     .collect(),
+        }
+    }
+}
+
+impl Display for JsRegExpStruct {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        let source = if self.source.is_empty() { "(?:)" } else { &self.source };
+        write!(fmt, "/{}/{}", source, self.flags)
+    }
+}
+
+fn utf16_len(s: &str) -> usize {
+    s.chars().map(char::len_utf16).sum()
+}
+
+// Converts an index in UTF-16 code units into a byte offset into `s`, or `None` if it's past the
+// end of the string. An index in the middle of a surrogate pair is rounded up to the next
+// character.
+fn byte_offset(s: &str, index: usize) -> Option<usize> {
+    let mut units = 0;
+
+    for (i, c) in s.char_indices() {
+        if units >= index {
+            return Some(i);
+        }
+
+        units += c.len_utf16();
+    }
+
+    if units >= index { Some(s.len()) } else { None }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exec() {
+        let mut re = JsRegExpStruct::new("(?<=\\$)(?<dollars>\\d+)(\\.\\d+)?", "g").unwrap();
+        let m = re.exec("ab $12 $3.50").unwrap();
+
+        assert_eq!(4, m.index);
+        assert_eq!(vec![Some(String::from("12")), Some(String::from("12")), None], m.captures);
+        assert_eq!(vec![(String::from("dollars"), Some(String::from("12")))], m.groups);
+        assert_eq!(6, re.last_index);
+
+        assert_eq!(8, re.exec("ab $12 $3.50").unwrap().index);
+        assert_eq!(12, re.last_index);
+        assert!(!re.test("ab $12 $3.50"));
+        assert_eq!(0, re.last_index);
+    }
+
+    #[test]
+    fn test_sticky_and_unicode() {
+        let mut re = JsRegExpStruct::new("a", "y").unwrap();
+        assert!(re.test("aab"));
+        assert!(re.test("aab"));
+        assert_eq!(2, re.last_index);
+        assert!(!re.test("aab"));
+        assert_eq!(0, re.last_index);
+
+        // Indices count UTF-16 code units, so the emoji takes up two
+        let mut re = JsRegExpStruct::new(".", "gu").unwrap();
+        assert_eq!(Some(String::from("\u{1f600}")), re.exec("\u{1f600}x").unwrap().captures[0]);
+        assert_eq!(2, re.last_index);
+        assert_eq!(2, re.exec("\u{1f600}x").unwrap().index);
+    }
+
+    #[test]
+    fn test_new() {
+        assert_eq!("/a/gimsy", &format!("{}", JsRegExpStruct::new("a", "ymsig").unwrap()));
+        assert_eq!("/(?:)/", &format!("{}", JsRegExpStruct::new("", "").unwrap()));
+        assert!(matches!(JsRegExpStruct::new("a", "gg"), Err(JsError::SyntaxError(_))));
+        assert!(matches!(JsRegExpStruct::new("a", "x"), Err(JsError::SyntaxError(_))));
+        assert!(matches!(JsRegExpStruct::new("a", "uv"), Err(JsError::SyntaxError(_))));
+        assert!(matches!(JsRegExpStruct::new("(", ""), Err(JsError::SyntaxError(_))));
+    }
+}
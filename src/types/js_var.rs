@@ -6,6 +6,7 @@ use super::binding::{Binding, UniqueBinding};
 use super::coerce::AsString;
 use super::js_fn::JsFnStruct;
 use super::js_obj::JsObjStruct;
+use super::js_regexp::JsRegExpStruct;
 use super::js_str::JsStrStruct;
 use super::native_fn::NativeFn;
 use super::native_var::NativeVar;
@@ -90,6 +91,7 @@ pub enum JsPtrEnum {
     JsSym(String),
     JsStr(JsStrStruct),
     JsObj(JsObjStruct),
+    JsRegExp(JsRegExpStruct),
     JsFn(JsFnStruct),
     NativeFn(NativeFn),
     NativeVar(NativeVar),
@@ -101,6 +103,7 @@ impl Display for JsPtrEnum {
             &JsPtrEnum::JsSym(ref s) => write!(fmt, "Symbol({})", s),
             &JsPtrEnum::JsStr(ref s) => write!(fmt, "\"{}\"", s),
             &JsPtrEnum::JsObj(ref o) => write!(fmt, "{}", o),
+            &JsPtrEnum::JsRegExp(ref r) => write!(fmt, "{}", r),
             &JsPtrEnum::JsFn(ref f) => write!(fmt, "{}", f),
             &JsPtrEnum::NativeFn(_) => write!(fmt, "[native code]"),
             &JsPtrEnum::NativeVar(ref v) => write!(fmt, "{}", v.var),
@@ -113,6 +116,7 @@ pub enum JsPtrTag {
     JsSym,
     JsStr,
     JsObj,
+    JsRegExp,
     JsFn { name: Option<String> },
     NativeFn { name: String },
     NativeVar { type_string: String },
@@ -124,6 +128,7 @@ impl JsPtrTag {
             (&JsPtrTag::JsSym, &JsPtrEnum::JsSym(_)) |
             (&JsPtrTag::JsStr, &JsPtrEnum::JsStr(_)) |
             (&JsPtrTag::JsObj, &JsPtrEnum::JsObj(_)) |
+            (&JsPtrTag::JsRegExp, &JsPtrEnum::JsRegExp(_)) |
             (&JsPtrTag::JsFn{..},  &JsPtrEnum::JsFn(_)) |
             (&JsPtrTag::NativeFn{..}, &JsPtrEnum::NativeFn(_)) |
             (&JsPtrTag::NativeVar{..}, &JsPtrEnum::NativeVar(_)) => true,
@@ -135,7 +140,7 @@ impl JsPtrTag {
         let s = match *self {
             JsPtrTag::JsSym => "symbol",
             JsPtrTag::JsStr => "string",
-            JsPtrTag::JsObj => "object",
+            JsPtrTag::JsObj | JsPtrTag::JsRegExp => "object",
             JsPtrTag::JsFn{..} | JsPtrTag::NativeFn{..} => "function",
             JsPtrTag::NativeVar{ ref type_string } => return type_string.to_owned(),
         };
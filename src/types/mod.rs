@@ -1,6 +1,8 @@
 pub mod binding;
+pub mod js_accessor;
 pub mod coerce;
 pub mod js_obj;
+pub mod js_regexp;
 pub mod js_str;
 pub mod js_var;
 pub mod js_fn;
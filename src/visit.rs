@@ -0,0 +1,889 @@
+// Traversals over the AST. Each trait's default methods walk into every child of a node, so an
+// implementation only needs to override the methods for the nodes it cares about; an overriding
+// method can call the corresponding `walk_*`/`noop_fold_*` function to keep recursing.
+
+use ast::{ArrowBody, Class, ClassKey, ClassMember, Exp, MemberKind, Pattern, Prop, PropKey, Stmt,
+          Template};
+
+/// Read-only traversal.
+pub trait Visitor {
+    fn visit_exp(&mut self, e: &Exp) {
+        walk_exp(self, e)
+    }
+
+    fn visit_stmt(&mut self, s: &Stmt) {
+        walk_stmt(self, s)
+    }
+
+    fn visit_pattern(&mut self, p: &Pattern) {
+        walk_pattern(self, p)
+    }
+}
+
+pub fn walk_exp<V: Visitor + ?Sized>(v: &mut V, e: &Exp) {
+    match *e {
+        Exp::Array(ref elems) => {
+            for elem in elems.iter().filter_map(Option::as_ref) {
+                v.visit_exp(elem);
+            }
+        }
+        Exp::AssignExp(_, ref lhs, ref rhs) => {
+            v.visit_pattern(lhs);
+            v.visit_exp(rhs);
+        }
+        Exp::BinExp(ref e1, _, ref e2) => {
+            v.visit_exp(e1);
+            v.visit_exp(e2);
+        }
+        Exp::Class(ref class) => walk_class(v, class),
+        Exp::Call(ref func, ref args) | Exp::NewObject(ref func, ref args) => {
+            v.visit_exp(func);
+
+            for arg in args {
+                v.visit_exp(arg);
+            }
+        }
+        Exp::Comma(ref e1, ref e2) => {
+            v.visit_exp(e1);
+            v.visit_exp(e2);
+        }
+        Exp::Conditional(ref test, ref e1, ref e2) => {
+            v.visit_exp(test);
+            v.visit_exp(e1);
+            v.visit_exp(e2);
+        }
+        Exp::Arrow(_, ref params, ref body) => {
+            for param in params {
+                v.visit_pattern(param);
+            }
+
+            match *body {
+                ArrowBody::Exp(ref e) => v.visit_exp(e),
+                ArrowBody::Block(ref body) => {
+                    for s in body {
+                        v.visit_stmt(s);
+                    }
+                }
+            }
+        }
+        Exp::Defun(_, _, ref params, ref body) => {
+            for param in params {
+                v.visit_pattern(param);
+            }
+
+            for s in body {
+                v.visit_stmt(s);
+            }
+        }
+        Exp::KeyAccessor(ref obj, ref key) => {
+            v.visit_exp(obj);
+            v.visit_exp(key);
+        }
+        Exp::TaggedTemplate(ref tag, ref template) => {
+            v.visit_exp(tag);
+
+            for e in &template.exps {
+                v.visit_exp(e);
+            }
+        }
+        Exp::Template(ref template) => {
+            for e in &template.exps {
+                v.visit_exp(e);
+            }
+        }
+        Exp::Object(ref properties) => {
+            for prop in properties {
+                match *prop {
+                    Prop::Value(_, ref e) | Prop::Spread(ref e) => v.visit_exp(e),
+                    Prop::Computed(ref key, ref e) => {
+                        v.visit_exp(key);
+                        v.visit_exp(e);
+                    }
+                    Prop::Shorthand(_) => (),
+                    Prop::Method(_, ref key, ref params, ref body) => {
+                        walk_prop_key(v, key);
+
+                        for param in params {
+                            v.visit_pattern(param);
+                        }
+
+                        for s in body {
+                            v.visit_stmt(s);
+                        }
+                    }
+                    Prop::Get(ref key, ref body) => {
+                        walk_prop_key(v, key);
+
+                        for s in body {
+                            v.visit_stmt(s);
+                        }
+                    }
+                    Prop::Set(ref key, ref param, ref body) => {
+                        walk_prop_key(v, key);
+                        v.visit_pattern(param);
+
+                        for s in body {
+                            v.visit_stmt(s);
+                        }
+                    }
+                }
+            }
+        }
+        Exp::Await(ref e) | Exp::BitNot(ref e) | Exp::Chain(ref e) | Exp::Commented(_, ref e) |
+        Exp::Delete(ref e) | Exp::InstanceVar(ref e, _) | Exp::LogNot(ref e) | Exp::Neg(ref e) |
+        Exp::Optional(ref e) | Exp::Pos(ref e) | Exp::PostDec(ref e) | Exp::PostInc(ref e) |
+        Exp::PreDec(ref e) | Exp::PreInc(ref e) | Exp::Spanned(_, ref e) | Exp::Spread(ref e) |
+        Exp::TypeOf(ref e) | Exp::Void(ref e) | Exp::Yield(Some(ref e)) |
+        Exp::YieldAll(ref e) => v.visit_exp(e),
+        Exp::Bool(_) | Exp::Error | Exp::Float(_) | Exp::Null | Exp::RegExp(..) |
+        Exp::Str(_) | Exp::Super | Exp::This | Exp::Undefined | Exp::Var(_) |
+        Exp::Yield(None) => (),
+    }
+}
+
+pub fn walk_stmt<V: Visitor + ?Sized>(v: &mut V, s: &Stmt) {
+    macro_rules! block {
+        ($block:expr) => {
+            for s in $block {
+                v.visit_stmt(s);
+            }
+        }
+    }
+
+    match *s {
+        Stmt::Assign(ref lhs, ref rhs) => {
+            v.visit_pattern(lhs);
+            v.visit_exp(rhs);
+        }
+        Stmt::BareExp(ref e) | Stmt::ExportDefault(ref e) | Stmt::Ret(ref e) => v.visit_exp(e),
+        Stmt::Block(ref block) => block!(block),
+        Stmt::ClassDecl(ref class) => walk_class(v, class),
+        Stmt::Decl(_, ref decls) => {
+            for &(ref p, ref init) in decls {
+                v.visit_pattern(p);
+
+                if let Some(ref e) = *init {
+                    v.visit_exp(e);
+                }
+            }
+        }
+        Stmt::FnDecl(_, _, ref params, ref body) => {
+            for param in params {
+                v.visit_pattern(param);
+            }
+
+            block!(body);
+        }
+        Stmt::If(ref cond, ref then_block, ref else_block) => {
+            v.visit_exp(cond);
+            block!(then_block);
+            block!(else_block);
+        }
+        Stmt::For(ref init, ref cond, ref step, ref body) => {
+            v.visit_stmt(init);
+            v.visit_exp(cond);
+            v.visit_exp(step);
+            block!(body);
+        }
+        Stmt::Seq(ref s1, ref s2) => {
+            v.visit_stmt(s1);
+            v.visit_stmt(s2);
+        }
+        Stmt::Spanned(_, ref s) | Stmt::Commented(_, ref s) => v.visit_stmt(s),
+        Stmt::Throw(ref e) => v.visit_exp(e),
+        Stmt::Try(ref try_block, ref catch_var, ref catch_block, ref finally_block) => {
+            block!(try_block);
+
+            if let Some(ref p) = *catch_var {
+                v.visit_pattern(p);
+            }
+
+            block!(catch_block);
+            block!(finally_block);
+        }
+        Stmt::Switch(ref e, ref cases) => {
+            v.visit_exp(e);
+
+            for &(ref test, ref body) in cases {
+                if let Some(ref test) = *test {
+                    v.visit_exp(test);
+                }
+
+                block!(body);
+            }
+        }
+        Stmt::While(ref cond, ref body) => {
+            v.visit_exp(cond);
+            block!(body);
+        }
+        Stmt::DoWhile(ref body, ref cond) => {
+            block!(body);
+            v.visit_exp(cond);
+        }
+        Stmt::ForIn(ref left, ref e, ref body) | Stmt::ForOf(ref left, ref e, ref body) => {
+            v.visit_stmt(left);
+            v.visit_exp(e);
+            block!(body);
+        }
+        Stmt::Export(ref s) | Stmt::Labeled(_, ref s) => v.visit_stmt(s),
+        Stmt::Break(_) | Stmt::Continue(_) | Stmt::Empty | Stmt::Error | Stmt::ExportAll(..) |
+        Stmt::ExportNames(..) | Stmt::Import(..) => (),
+    }
+}
+
+pub fn walk_pattern<V: Visitor + ?Sized>(v: &mut V, p: &Pattern) {
+    match *p {
+        Pattern::Var(_) => (),
+        Pattern::Exp(ref e) => v.visit_exp(e),
+        Pattern::Default(ref p, ref e) => {
+            v.visit_pattern(p);
+            v.visit_exp(e);
+        }
+        Pattern::Rest(ref p) => v.visit_pattern(p),
+        Pattern::Array(ref elems) => {
+            for elem in elems.iter().filter_map(Option::as_ref) {
+                v.visit_pattern(elem);
+            }
+        }
+        Pattern::Object(ref props, ref rest) => {
+            for &(_, ref p) in props {
+                v.visit_pattern(p);
+            }
+
+            if let Some(ref rest) = *rest {
+                v.visit_pattern(rest);
+            }
+        }
+    }
+}
+
+// Walks the expression of a computed key of a method or accessor in an object literal.
+fn walk_prop_key<V: Visitor + ?Sized>(v: &mut V, key: &PropKey) {
+    if let PropKey::Computed(ref e) = *key {
+        v.visit_exp(e);
+    }
+}
+
+// Walks the base class and each member of a class. Classes aren't visited on their own, since they
+// only appear inside an `Exp::Class` or a `Stmt::ClassDecl`.
+fn walk_class<V: Visitor + ?Sized>(v: &mut V, class: &Class) {
+    if let Some(ref base) = class.extends {
+        v.visit_exp(base);
+    }
+
+    for member in &class.members {
+        if let ClassKey::Computed(ref e) = member.key {
+            v.visit_exp(e);
+        }
+
+        match member.kind {
+            MemberKind::Method(_, ref params, ref body) => {
+                for param in params {
+                    v.visit_pattern(param);
+                }
+
+                for s in body {
+                    v.visit_stmt(s);
+                }
+            }
+            MemberKind::Get(ref body) => {
+                for s in body {
+                    v.visit_stmt(s);
+                }
+            }
+            MemberKind::Set(ref param, ref body) => {
+                v.visit_pattern(param);
+
+                for s in body {
+                    v.visit_stmt(s);
+                }
+            }
+            MemberKind::Field(ref value) => {
+                if let Some(ref e) = *value {
+                    v.visit_exp(e);
+                }
+            }
+        }
+    }
+}
+
+/// In-place traversal that may mutate nodes as it goes.
+pub trait VisitorMut {
+    fn visit_exp_mut(&mut self, e: &mut Exp) {
+        walk_exp_mut(self, e)
+    }
+
+    fn visit_stmt_mut(&mut self, s: &mut Stmt) {
+        walk_stmt_mut(self, s)
+    }
+
+    fn visit_pattern_mut(&mut self, p: &mut Pattern) {
+        walk_pattern_mut(self, p)
+    }
+}
+
+pub fn walk_exp_mut<V: VisitorMut + ?Sized>(v: &mut V, e: &mut Exp) {
+    match *e {
+        Exp::Array(ref mut elems) => {
+            for elem in elems.iter_mut().filter_map(Option::as_mut) {
+                v.visit_exp_mut(elem);
+            }
+        }
+        Exp::AssignExp(_, ref mut lhs, ref mut rhs) => {
+            v.visit_pattern_mut(lhs);
+            v.visit_exp_mut(rhs);
+        }
+        Exp::BinExp(ref mut e1, _, ref mut e2) => {
+            v.visit_exp_mut(e1);
+            v.visit_exp_mut(e2);
+        }
+        Exp::Class(ref mut class) => walk_class_mut(v, class),
+        Exp::Call(ref mut func, ref mut args) | Exp::NewObject(ref mut func, ref mut args) => {
+            v.visit_exp_mut(func);
+
+            for arg in args {
+                v.visit_exp_mut(arg);
+            }
+        }
+        Exp::Comma(ref mut e1, ref mut e2) => {
+            v.visit_exp_mut(e1);
+            v.visit_exp_mut(e2);
+        }
+        Exp::Conditional(ref mut test, ref mut e1, ref mut e2) => {
+            v.visit_exp_mut(test);
+            v.visit_exp_mut(e1);
+            v.visit_exp_mut(e2);
+        }
+        Exp::Arrow(_, ref mut params, ref mut body) => {
+            for param in params {
+                v.visit_pattern_mut(param);
+            }
+
+            match *body {
+                ArrowBody::Exp(ref mut e) => v.visit_exp_mut(e),
+                ArrowBody::Block(ref mut body) => {
+                    for s in body {
+                        v.visit_stmt_mut(s);
+                    }
+                }
+            }
+        }
+        Exp::Defun(_, _, ref mut params, ref mut body) => {
+            for param in params {
+                v.visit_pattern_mut(param);
+            }
+
+            for s in body {
+                v.visit_stmt_mut(s);
+            }
+        }
+        Exp::KeyAccessor(ref mut obj, ref mut key) => {
+            v.visit_exp_mut(obj);
+            v.visit_exp_mut(key);
+        }
+        Exp::TaggedTemplate(ref mut tag, ref mut template) => {
+            v.visit_exp_mut(tag);
+
+            for e in &mut template.exps {
+                v.visit_exp_mut(e);
+            }
+        }
+        Exp::Template(ref mut template) => {
+            for e in &mut template.exps {
+                v.visit_exp_mut(e);
+            }
+        }
+        Exp::Object(ref mut properties) => {
+            for prop in properties {
+                match *prop {
+                    Prop::Value(_, ref mut e) | Prop::Spread(ref mut e) => v.visit_exp_mut(e),
+                    Prop::Computed(ref mut key, ref mut e) => {
+                        v.visit_exp_mut(key);
+                        v.visit_exp_mut(e);
+                    }
+                    Prop::Shorthand(_) => (),
+                    Prop::Method(_, ref mut key, ref mut params, ref mut body) => {
+                        walk_prop_key_mut(v, key);
+
+                        for param in params {
+                            v.visit_pattern_mut(param);
+                        }
+
+                        for s in body {
+                            v.visit_stmt_mut(s);
+                        }
+                    }
+                    Prop::Get(ref mut key, ref mut body) => {
+                        walk_prop_key_mut(v, key);
+
+                        for s in body {
+                            v.visit_stmt_mut(s);
+                        }
+                    }
+                    Prop::Set(ref mut key, ref mut param, ref mut body) => {
+                        walk_prop_key_mut(v, key);
+                        v.visit_pattern_mut(param);
+
+                        for s in body {
+                            v.visit_stmt_mut(s);
+                        }
+                    }
+                }
+            }
+        }
+        Exp::Await(ref mut e) | Exp::BitNot(ref mut e) | Exp::Chain(ref mut e) |
+        Exp::Commented(_, ref mut e) | Exp::Delete(ref mut e) | Exp::InstanceVar(ref mut e, _) |
+        Exp::LogNot(ref mut e) | Exp::Neg(ref mut e) | Exp::Optional(ref mut e) |
+        Exp::Pos(ref mut e) | Exp::PostDec(ref mut e) | Exp::PostInc(ref mut e) |
+        Exp::PreDec(ref mut e) | Exp::PreInc(ref mut e) | Exp::Spanned(_, ref mut e) |
+        Exp::Spread(ref mut e) | Exp::TypeOf(ref mut e) | Exp::Void(ref mut e) |
+        Exp::Yield(Some(ref mut e)) | Exp::YieldAll(ref mut e) => v.visit_exp_mut(e),
+        Exp::Bool(_) | Exp::Error | Exp::Float(_) | Exp::Null | Exp::RegExp(..) |
+        Exp::Str(_) | Exp::Super | Exp::This | Exp::Undefined | Exp::Var(_) |
+        Exp::Yield(None) => (),
+    }
+}
+
+pub fn walk_stmt_mut<V: VisitorMut + ?Sized>(v: &mut V, s: &mut Stmt) {
+    macro_rules! block {
+        ($block:expr) => {
+            for s in $block {
+                v.visit_stmt_mut(s);
+            }
+        }
+    }
+
+    match *s {
+        Stmt::Assign(ref mut lhs, ref mut rhs) => {
+            v.visit_pattern_mut(lhs);
+            v.visit_exp_mut(rhs);
+        }
+        Stmt::BareExp(ref mut e) | Stmt::ExportDefault(ref mut e) | Stmt::Ret(ref mut e) => {
+            v.visit_exp_mut(e)
+        }
+        Stmt::Block(ref mut block) => block!(block),
+        Stmt::ClassDecl(ref mut class) => walk_class_mut(v, class),
+        Stmt::Decl(_, ref mut decls) => {
+            for &mut (ref mut p, ref mut init) in decls {
+                v.visit_pattern_mut(p);
+
+                if let Some(ref mut e) = *init {
+                    v.visit_exp_mut(e);
+                }
+            }
+        }
+        Stmt::FnDecl(_, _, ref mut params, ref mut body) => {
+            for param in params {
+                v.visit_pattern_mut(param);
+            }
+
+            block!(body);
+        }
+        Stmt::If(ref mut cond, ref mut then_block, ref mut else_block) => {
+            v.visit_exp_mut(cond);
+            block!(then_block);
+            block!(else_block);
+        }
+        Stmt::For(ref mut init, ref mut cond, ref mut step, ref mut body) => {
+            v.visit_stmt_mut(init);
+            v.visit_exp_mut(cond);
+            v.visit_exp_mut(step);
+            block!(body);
+        }
+        Stmt::Seq(ref mut s1, ref mut s2) => {
+            v.visit_stmt_mut(s1);
+            v.visit_stmt_mut(s2);
+        }
+        Stmt::Spanned(_, ref mut s) | Stmt::Commented(_, ref mut s) => v.visit_stmt_mut(s),
+        Stmt::Throw(ref mut e) => v.visit_exp_mut(e),
+        Stmt::Try(ref mut try_block, ref mut catch_var, ref mut catch_block,
+                  ref mut finally_block) => {
+            block!(try_block);
+
+            if let Some(ref mut p) = *catch_var {
+                v.visit_pattern_mut(p);
+            }
+
+            block!(catch_block);
+            block!(finally_block);
+        }
+        Stmt::Switch(ref mut e, ref mut cases) => {
+            v.visit_exp_mut(e);
+
+            for &mut (ref mut test, ref mut body) in cases {
+                if let Some(ref mut test) = *test {
+                    v.visit_exp_mut(test);
+                }
+
+                block!(body);
+            }
+        }
+        Stmt::While(ref mut cond, ref mut body) => {
+            v.visit_exp_mut(cond);
+            block!(body);
+        }
+        Stmt::DoWhile(ref mut body, ref mut cond) => {
+            block!(body);
+            v.visit_exp_mut(cond);
+        }
+        Stmt::ForIn(ref mut left, ref mut e, ref mut body) |
+        Stmt::ForOf(ref mut left, ref mut e, ref mut body) => {
+            v.visit_stmt_mut(left);
+            v.visit_exp_mut(e);
+            block!(body);
+        }
+        Stmt::Export(ref mut s) | Stmt::Labeled(_, ref mut s) => v.visit_stmt_mut(s),
+        Stmt::Break(_) | Stmt::Continue(_) | Stmt::Empty | Stmt::Error | Stmt::ExportAll(..) |
+        Stmt::ExportNames(..) | Stmt::Import(..) => (),
+    }
+}
+
+pub fn walk_pattern_mut<V: VisitorMut + ?Sized>(v: &mut V, p: &mut Pattern) {
+    match *p {
+        Pattern::Var(_) => (),
+        Pattern::Exp(ref mut e) => v.visit_exp_mut(e),
+        Pattern::Default(ref mut p, ref mut e) => {
+            v.visit_pattern_mut(p);
+            v.visit_exp_mut(e);
+        }
+        Pattern::Rest(ref mut p) => v.visit_pattern_mut(p),
+        Pattern::Array(ref mut elems) => {
+            for elem in elems.iter_mut().filter_map(Option::as_mut) {
+                v.visit_pattern_mut(elem);
+            }
+        }
+        Pattern::Object(ref mut props, ref mut rest) => {
+            for &mut (_, ref mut p) in props {
+                v.visit_pattern_mut(p);
+            }
+
+            if let Some(ref mut rest) = *rest {
+                v.visit_pattern_mut(rest);
+            }
+        }
+    }
+}
+
+fn walk_prop_key_mut<V: VisitorMut + ?Sized>(v: &mut V, key: &mut PropKey) {
+    if let PropKey::Computed(ref mut e) = *key {
+        v.visit_exp_mut(e);
+    }
+}
+
+fn walk_class_mut<V: VisitorMut + ?Sized>(v: &mut V, class: &mut Class) {
+    if let Some(ref mut base) = class.extends {
+        v.visit_exp_mut(base);
+    }
+
+    for member in &mut class.members {
+        if let ClassKey::Computed(ref mut e) = member.key {
+            v.visit_exp_mut(e);
+        }
+
+        match member.kind {
+            MemberKind::Method(_, ref mut params, ref mut body) => {
+                for param in params {
+                    v.visit_pattern_mut(param);
+                }
+
+                for s in body {
+                    v.visit_stmt_mut(s);
+                }
+            }
+            MemberKind::Get(ref mut body) => {
+                for s in body {
+                    v.visit_stmt_mut(s);
+                }
+            }
+            MemberKind::Set(ref mut param, ref mut body) => {
+                v.visit_pattern_mut(param);
+
+                for s in body {
+                    v.visit_stmt_mut(s);
+                }
+            }
+            MemberKind::Field(ref mut value) => {
+                if let Some(ref mut e) = *value {
+                    v.visit_exp_mut(e);
+                }
+            }
+        }
+    }
+}
+
+/// Rewriting traversal that consumes a tree and builds a new one.
+pub trait Fold {
+    fn fold_exp(&mut self, e: Exp) -> Exp {
+        noop_fold_exp(self, e)
+    }
+
+    fn fold_stmt(&mut self, s: Stmt) -> Stmt {
+        noop_fold_stmt(self, s)
+    }
+
+    fn fold_pattern(&mut self, p: Pattern) -> Pattern {
+        noop_fold_pattern(self, p)
+    }
+}
+
+pub fn noop_fold_exp<F: Fold + ?Sized>(f: &mut F, e: Exp) -> Exp {
+    macro_rules! fold_box {
+        ($e:expr) => { Box::new(f.fold_exp(*$e)) }
+    }
+
+    macro_rules! fold_boxes {
+        ($vec:expr) => { $vec.into_iter().map(|e| Box::new(f.fold_exp(*e))).collect() }
+    }
+
+    match e {
+        Exp::Array(elems) => {
+            Exp::Array(elems.into_iter().map(|elem| elem.map(|e| fold_box!(e))).collect())
+        }
+        Exp::AssignExp(o, lhs, rhs) => {
+            let lhs = Box::new(f.fold_pattern(*lhs));
+            Exp::AssignExp(o, lhs, fold_box!(rhs))
+        }
+        Exp::BinExp(e1, o, e2) => {
+            let e1 = fold_box!(e1);
+            Exp::BinExp(e1, o, fold_box!(e2))
+        }
+        Exp::Await(e) => Exp::Await(fold_box!(e)),
+        Exp::BitNot(e) => Exp::BitNot(fold_box!(e)),
+        Exp::Chain(e) => Exp::Chain(fold_box!(e)),
+        Exp::Class(class) => Exp::Class(Box::new(noop_fold_class(f, *class))),
+        Exp::Call(func, args) => {
+            let func = fold_box!(func);
+            Exp::Call(func, fold_boxes!(args))
+        }
+        Exp::Comma(e1, e2) => {
+            let e1 = fold_box!(e1);
+            Exp::Comma(e1, fold_box!(e2))
+        }
+        Exp::Commented(comments, e) => Exp::Commented(comments, fold_box!(e)),
+        Exp::Conditional(test, e1, e2) => {
+            let test = fold_box!(test);
+            let e1 = fold_box!(e1);
+            Exp::Conditional(test, e1, fold_box!(e2))
+        }
+        Exp::Arrow(kind, params, body) => {
+            let params = params.into_iter().map(|p| f.fold_pattern(p)).collect();
+            let body = match body {
+                ArrowBody::Exp(e) => ArrowBody::Exp(fold_box!(e)),
+                ArrowBody::Block(body) => {
+                    ArrowBody::Block(body.into_iter().map(|s| f.fold_stmt(s)).collect())
+                }
+            };
+
+            Exp::Arrow(kind, params, body)
+        }
+        Exp::Defun(kind, name, params, body) => {
+            let params = params.into_iter().map(|p| f.fold_pattern(p)).collect();
+            Exp::Defun(kind, name, params, body.into_iter().map(|s| f.fold_stmt(s)).collect())
+        }
+        Exp::Delete(e) => Exp::Delete(fold_box!(e)),
+        Exp::InstanceVar(obj, name) => Exp::InstanceVar(fold_box!(obj), name),
+        Exp::KeyAccessor(obj, key) => {
+            let obj = fold_box!(obj);
+            Exp::KeyAccessor(obj, fold_box!(key))
+        }
+        Exp::LogNot(e) => Exp::LogNot(fold_box!(e)),
+        Exp::Neg(e) => Exp::Neg(fold_box!(e)),
+        Exp::NewObject(func, args) => {
+            let func = fold_box!(func);
+            Exp::NewObject(func, fold_boxes!(args))
+        }
+        Exp::Object(properties) => {
+            let properties = properties.into_iter().map(|prop| {
+                match prop {
+                    Prop::Value(name, e) => Prop::Value(name, fold_box!(e)),
+                    Prop::Computed(key, e) => {
+                        let key = fold_box!(key);
+                        Prop::Computed(key, fold_box!(e))
+                    }
+                    Prop::Shorthand(name) => Prop::Shorthand(name),
+                    Prop::Method(kind, key, params, body) => {
+                        let key = fold_prop_key(f, key);
+                        let params = params.into_iter().map(|p| f.fold_pattern(p)).collect();
+                        let body = body.into_iter().map(|s| f.fold_stmt(s)).collect();
+                        Prop::Method(kind, key, params, body)
+                    }
+                    Prop::Get(key, body) => {
+                        let key = fold_prop_key(f, key);
+                        Prop::Get(key, body.into_iter().map(|s| f.fold_stmt(s)).collect())
+                    }
+                    Prop::Set(key, param, body) => {
+                        let key = fold_prop_key(f, key);
+                        let param = f.fold_pattern(param);
+                        Prop::Set(key, param, body.into_iter().map(|s| f.fold_stmt(s)).collect())
+                    }
+                    Prop::Spread(e) => Prop::Spread(fold_box!(e)),
+                }
+            });
+
+            Exp::Object(properties.collect())
+        }
+        Exp::Optional(e) => Exp::Optional(fold_box!(e)),
+        Exp::Pos(e) => Exp::Pos(fold_box!(e)),
+        Exp::PostDec(e) => Exp::PostDec(fold_box!(e)),
+        Exp::PostInc(e) => Exp::PostInc(fold_box!(e)),
+        Exp::PreDec(e) => Exp::PreDec(fold_box!(e)),
+        Exp::PreInc(e) => Exp::PreInc(fold_box!(e)),
+        Exp::Spanned(span, e) => Exp::Spanned(span, fold_box!(e)),
+        Exp::Spread(e) => Exp::Spread(fold_box!(e)),
+        Exp::TaggedTemplate(tag, Template { quasis, exps }) => {
+            let tag = fold_box!(tag);
+            Exp::TaggedTemplate(tag, Template { quasis: quasis, exps: fold_boxes!(exps) })
+        }
+        Exp::Template(Template { quasis, exps }) => {
+            Exp::Template(Template { quasis: quasis, exps: fold_boxes!(exps) })
+        }
+        Exp::TypeOf(e) => Exp::TypeOf(fold_box!(e)),
+        Exp::Void(e) => Exp::Void(fold_box!(e)),
+        Exp::Yield(arg) => Exp::Yield(arg.map(|e| fold_box!(e))),
+        Exp::YieldAll(e) => Exp::YieldAll(fold_box!(e)),
+        e @ Exp::Bool(_) | e @ Exp::Error | e @ Exp::Float(_) | e @ Exp::Null |
+        e @ Exp::RegExp(..) | e @ Exp::Str(_) | e @ Exp::Super | e @ Exp::This |
+        e @ Exp::Undefined | e @ Exp::Var(_) => e,
+    }
+}
+
+pub fn noop_fold_stmt<F: Fold + ?Sized>(f: &mut F, s: Stmt) -> Stmt {
+    macro_rules! block {
+        ($block:expr) => { $block.into_iter().map(|s| f.fold_stmt(s)).collect() }
+    }
+
+    match s {
+        Stmt::Assign(lhs, rhs) => {
+            let lhs = f.fold_pattern(lhs);
+            Stmt::Assign(lhs, f.fold_exp(rhs))
+        }
+        Stmt::BareExp(e) => Stmt::BareExp(f.fold_exp(e)),
+        Stmt::Block(block) => Stmt::Block(block!(block)),
+        Stmt::ClassDecl(class) => Stmt::ClassDecl(noop_fold_class(f, class)),
+        Stmt::Decl(kind, decls) => {
+            let decls = decls.into_iter().map(|(p, init)| {
+                let p = f.fold_pattern(p);
+                (p, init.map(|e| f.fold_exp(e)))
+            });
+
+            Stmt::Decl(kind, decls.collect())
+        }
+        Stmt::FnDecl(kind, name, params, body) => {
+            let params = params.into_iter().map(|p| f.fold_pattern(p)).collect();
+            Stmt::FnDecl(kind, name, params, block!(body))
+        }
+        Stmt::If(cond, then_block, else_block) => {
+            let cond = f.fold_exp(cond);
+            let then_block = block!(then_block);
+            Stmt::If(cond, then_block, block!(else_block))
+        }
+        Stmt::For(init, cond, step, body) => {
+            let init = Box::new(f.fold_stmt(*init));
+            let cond = f.fold_exp(cond);
+            let step = f.fold_exp(step);
+            Stmt::For(init, cond, step, block!(body))
+        }
+        Stmt::Ret(e) => Stmt::Ret(f.fold_exp(e)),
+        Stmt::Seq(s1, s2) => {
+            let s1 = Box::new(f.fold_stmt(*s1));
+            Stmt::Seq(s1, Box::new(f.fold_stmt(*s2)))
+        }
+        Stmt::Spanned(span, s) => Stmt::Spanned(span, Box::new(f.fold_stmt(*s))),
+        Stmt::Commented(comments, s) => Stmt::Commented(comments, Box::new(f.fold_stmt(*s))),
+        Stmt::Throw(e) => Stmt::Throw(Box::new(f.fold_exp(*e))),
+        Stmt::Try(try_block, catch_var, catch_block, finally_block) => {
+            let try_block = block!(try_block);
+            let catch_var = catch_var.map(|p| f.fold_pattern(p));
+            let catch_block = block!(catch_block);
+            Stmt::Try(try_block, catch_var, catch_block, block!(finally_block))
+        }
+        Stmt::Switch(e, cases) => {
+            let e = f.fold_exp(e);
+            let cases = cases.into_iter().map(|(test, body)| {
+                let test = test.map(|test| f.fold_exp(test));
+                (test, block!(body))
+            }).collect();
+
+            Stmt::Switch(e, cases)
+        }
+        Stmt::While(cond, body) => {
+            let cond = f.fold_exp(cond);
+            Stmt::While(cond, block!(body))
+        }
+        Stmt::DoWhile(body, cond) => {
+            let body = block!(body);
+            Stmt::DoWhile(body, f.fold_exp(cond))
+        }
+        Stmt::ForIn(left, e, body) => {
+            let left = Box::new(f.fold_stmt(*left));
+            let e = f.fold_exp(e);
+            Stmt::ForIn(left, e, block!(body))
+        }
+        Stmt::ForOf(left, e, body) => {
+            let left = Box::new(f.fold_stmt(*left));
+            let e = f.fold_exp(e);
+            Stmt::ForOf(left, e, block!(body))
+        }
+        Stmt::Export(s) => Stmt::Export(Box::new(f.fold_stmt(*s))),
+        Stmt::ExportDefault(e) => Stmt::ExportDefault(f.fold_exp(e)),
+        Stmt::Labeled(label, s) => Stmt::Labeled(label, Box::new(f.fold_stmt(*s))),
+        s @ Stmt::Break(_) | s @ Stmt::Continue(_) | s @ Stmt::Empty | s @ Stmt::Error |
+        s @ Stmt::ExportAll(..) | s @ Stmt::ExportNames(..) | s @ Stmt::Import(..) => s,
+    }
+}
+
+pub fn noop_fold_pattern<F: Fold + ?Sized>(f: &mut F, p: Pattern) -> Pattern {
+    match p {
+        p @ Pattern::Var(_) => p,
+        Pattern::Exp(e) => Pattern::Exp(Box::new(f.fold_exp(*e))),
+        Pattern::Default(p, e) => {
+            let p = Box::new(f.fold_pattern(*p));
+            Pattern::Default(p, f.fold_exp(e))
+        }
+        Pattern::Rest(p) => Pattern::Rest(Box::new(f.fold_pattern(*p))),
+        Pattern::Array(elems) => {
+            Pattern::Array(elems.into_iter().map(|elem| elem.map(|p| f.fold_pattern(p))).collect())
+        }
+        Pattern::Object(props, rest) => {
+            let props = props.into_iter().map(|(key, p)| (key, f.fold_pattern(p))).collect();
+            Pattern::Object(props, rest.map(|rest| Box::new(f.fold_pattern(*rest))))
+        }
+    }
+}
+
+fn fold_prop_key<F: Fold + ?Sized>(f: &mut F, key: PropKey) -> PropKey {
+    match key {
+        PropKey::Computed(e) => PropKey::Computed(Box::new(f.fold_exp(*e))),
+        key => key,
+    }
+}
+
+fn noop_fold_class<F: Fold + ?Sized>(f: &mut F, class: Class) -> Class {
+    let extends = class.extends.map(|base| Box::new(f.fold_exp(*base)));
+    let members = class.members.into_iter().map(|member| {
+        let key = match member.key {
+            ClassKey::Computed(e) => ClassKey::Computed(Box::new(f.fold_exp(*e))),
+            key => key,
+        };
+
+        let kind = match member.kind {
+            MemberKind::Method(kind, params, body) => {
+                let params = params.into_iter().map(|p| f.fold_pattern(p)).collect();
+                let body = body.into_iter().map(|s| f.fold_stmt(s)).collect();
+                MemberKind::Method(kind, params, body)
+            }
+            MemberKind::Get(body) => {
+                MemberKind::Get(body.into_iter().map(|s| f.fold_stmt(s)).collect())
+            }
+            MemberKind::Set(param, body) => {
+                let param = f.fold_pattern(param);
+                MemberKind::Set(param, body.into_iter().map(|s| f.fold_stmt(s)).collect())
+            }
+            MemberKind::Field(value) => MemberKind::Field(value.map(|e| f.fold_exp(e))),
+        };
+
+        ClassMember { is_static: member.is_static, key: key, kind: kind }
+    }).collect();
+
+    Class { name: class.name, extends: extends, members: members }
+}
@@ -0,0 +1,105 @@
+use jsrs_common::ast::BinOp::*;
+use jsrs_common::ast::Exp::*;
+use jsrs_common::ast::Stmt::*;
+use jsrs_common::ast::{Comment, Comments, Exp, Stmt};
+use jsrs_common::codegen::{CodeGen, CodeGenOptions, Quotes, Semicolons};
+
+fn gen(options: CodeGenOptions, stmts: &[Stmt]) -> String {
+    let mut gen = CodeGen::new(options);
+    gen.stmts(stmts);
+    gen.finish()
+}
+
+fn gen_exp(options: CodeGenOptions, e: &Exp) -> String {
+    let mut gen = CodeGen::new(options);
+    gen.exp(e);
+    gen.finish()
+}
+
+fn program() -> Vec<Stmt> {
+    vec![
+        decl!("f", defun! { "f", ("a", "b") If(exp!(var!("a"), Gt, var!("b")),
+            vec![Ret(exp!(var!("a"), Minus, Neg(Box::new(var!("b")))))],
+            vec![Ret(obj! { "k" => Str(String::from("it's")) })]) }),
+        BareExp(call!(defun! { () Empty }, Vec::<Exp>::new())),
+        While(Bool(true), vec![BareExp(TypeOf(Box::new(var!("x")))), Break(None)]),
+    ]
+}
+
+#[test]
+fn default_matches_display() {
+    let expected: String = program().iter().map(|s| format!("{}", s)).collect();
+    assert_eq!(expected, gen(CodeGenOptions::default(), &program()));
+}
+
+#[test]
+fn indentation() {
+    let four = CodeGenOptions { indent_width: 4, ..CodeGenOptions::default() };
+    let tabs = CodeGenOptions { use_tabs: true, ..CodeGenOptions::default() };
+    let s = [While(var!("a"), vec![If(var!("b"), vec![Continue(None)], vec![])])];
+
+    assert_eq!("while (a) {\n    if (b) {\n        continue;\n    }\n}\n", gen(four, &s));
+    assert_eq!("while (a) {\n\tif (b) {\n\t\tcontinue;\n\t}\n}\n", gen(tabs, &s));
+}
+
+#[test]
+fn minify() {
+    assert_eq!("var f=function f(a,b){if(a>b){return a- -b;}else{return{k:\"it's\"};}};\
+                (function(){}());while(true){typeof x;break;}",
+        gen(CodeGenOptions::minified(), &program()));
+    assert_eq!("a*(b+c)-d", gen_exp(CodeGenOptions::minified(),
+        &exp!(exp!(var!("a"), Star, exp!(var!("b"), Plus, var!("c"))), Minus, var!("d"))));
+    assert_eq!("a+ +b+ ++c+-d", gen_exp(CodeGenOptions::minified(),
+        &exp!(exp!(exp!(var!("a"), Plus, pos_var!("b")), Plus, pre_inc!(var!("c"))), Plus, neg_var!("d"))));
+    assert_eq!("x instanceof Y", gen_exp(CodeGenOptions::minified(), &exp!(var!("x"), InstanceOf, var!("Y"))));
+}
+
+#[test]
+fn semicolons_as_needed() {
+    let as_needed = CodeGenOptions { semicolons: Semicolons::AsNeeded, ..CodeGenOptions::default() };
+    let s = [BareExp(var!("a")), BareExp(call!(defun! { () Empty }, Vec::<Exp>::new())),
+             While(var!("b"), vec![Break(None)]), Empty];
+
+    assert_eq!("a\n;(function() {\n}())\nwhile (b) {\n  break\n}\n", gen(as_needed, &s));
+
+    let minified = CodeGenOptions { semicolons: Semicolons::AsNeeded, ..CodeGenOptions::minified() };
+    assert_eq!("a;(function(){}());while(b){break}", gen(minified, &s));
+}
+
+#[test]
+fn quotes() {
+    let single = CodeGenOptions { quotes: Quotes::Single, ..CodeGenOptions::default() };
+    let auto = CodeGenOptions { quotes: Quotes::Auto, ..CodeGenOptions::default() };
+
+    assert_eq!(r#"'it\'s "x"'"#, gen_exp(single, &Str(String::from("it's \"x\""))));
+    assert_eq!(r#""it's""#, gen_exp(auto.clone(), &Str(String::from("it's"))));
+    assert_eq!(r#"'say "hi"'"#, gen_exp(auto, &Str(String::from("say \"hi\""))));
+}
+
+#[test]
+fn max_width() {
+    let narrow = CodeGenOptions { max_width: 20, ..CodeGenOptions::default() };
+    let args = vec![var!("first"), var!("second"), var!("third")];
+
+    assert_eq!("f(first, second, third)", gen_exp(CodeGenOptions::default(), &call!(var!("f"), args.clone())));
+    assert_eq!("f(\n  first,\n  second,\n  third\n)", gen_exp(narrow.clone(), &call!(var!("f"), args.clone())));
+    assert_eq!("return [\n  first,\n  second,\n  third\n];\n",
+        gen(narrow, &[Ret(Array(args.into_iter().map(|e| Some(Box::new(e))).collect()))]));
+}
+
+#[test]
+fn comments() {
+    let line = |text: &str| {
+        let comment = Comment { text: String::from(text), block: false, lines_before: 0 };
+        Comments { leading: vec![comment], ..Comments::default() }
+    };
+    let trailing = |text: &str| Comments { trailing: line(text).leading, ..Comments::default() };
+
+    let s = [BareExp(var!("a")).with_comments(trailing(" a")),
+             Ret(var!("b").with_comments(line(" b"))),
+             BareExp(post_inc!(var!("c").with_comments(trailing(" c"))))];
+
+    // A line comment can't come between `return` and its value, or a value and `++`
+    assert_eq!("a; // a\nreturn (// b\nb);\n(c // c\n)++;\n", gen(CodeGenOptions::default(), &s));
+    assert_eq!("a;return b;c++;", gen(CodeGenOptions::minified(), &s));
+}
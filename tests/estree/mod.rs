@@ -0,0 +1,345 @@
+use serde_json::{self, Value};
+
+use jsrs_common::ast::BinOp::*;
+use jsrs_common::ast::Exp::*;
+use jsrs_common::ast::{self, ArrowBody, AssignOp, ClassKey, ClassMember, FnKind, ImportSpec, MemberKind,
+                       Pattern, Program, Prop, PropKey, Stmt, TemplatePart};
+use jsrs_common::ast::Stmt::*;
+use jsrs_common::estree::*;
+use jsrs_common::js_error::JsError;
+use jsrs_common::parser::{ParseOptions, Parser};
+use jsrs_common::span::Span;
+
+fn json(s: &str) -> Value {
+    serde_json::from_str(s).unwrap()
+}
+
+fn round_trip(stmts: Vec<Stmt>) {
+    round_trip_program(Program::Script(stmts));
+}
+
+fn round_trip_program(program: Program) {
+    let estree = program_to_estree(&program);
+    assert_eq!(program, program_from_estree(&estree).unwrap());
+
+    // Going through text shouldn't lose anything either
+    assert_eq!(program, program_from_estree(&json(&estree.to_string())).unwrap());
+}
+
+#[test]
+fn exp_nodes() {
+    assert_eq!(json(r#"{"type": "BinaryExpression", "operator": "+",
+                         "left": {"type": "Identifier", "name": "a"},
+                         "right": {"type": "Literal", "value": 1.5}}"#),
+               exp_to_estree(&exp!(var!("a"), Plus, Float(1.5))));
+    assert_eq!(json(r#"{"type": "LogicalExpression", "operator": "||",
+                         "left": {"type": "Literal", "value": null},
+                         "right": {"type": "Identifier", "name": "undefined", "constant": true}}"#),
+               exp_to_estree(&exp!(Null, Or, Undefined)));
+    assert_eq!(json(r#"{"type": "MemberExpression", "computed": false, "optional": false,
+                         "object": {"type": "Identifier", "name": "o"},
+                         "property": {"type": "Identifier", "name": "p"}}"#),
+               exp_to_estree(&instance_var!(var!("o"), "p")));
+    assert_eq!(json(r#"{"type": "ChainExpression",
+                         "expression": {"type": "CallExpression", "optional": true,
+                                        "callee": {"type": "Identifier", "name": "f"},
+                                        "arguments": []}}"#),
+               exp_to_estree(&Chain(Box::new(call!(Optional(Box::new(var!("f"))), vec![])))));
+    assert_eq!(json(r#"{"type": "MemberExpression", "computed": false, "optional": false,
+                         "object": {"type": "ThisExpression"},
+                         "property": {"type": "Identifier", "name": "p"}}"#),
+               exp_to_estree(&instance_var!(This, "p")));
+    assert_eq!(json(r#"{"type": "UnaryExpression", "operator": "-", "prefix": true,
+                         "argument": {"type": "Literal", "value": 1.5}, "constant": true}"#),
+               exp_to_estree(&Float(-1.5)));
+}
+
+// Parsed source should come back from ESTree as it was parsed, with names like `undefined` left
+// as variables, since they can be shadowed.
+#[test]
+fn round_trip_parsed() {
+    let source = "x = [-1, -0, -Infinity, NaN, -1.5e300]; \
+                  function f(undefined, NaN) { return undefined + NaN; }";
+    let program = Parser::new(source, ParseOptions::default()).parse_program().unwrap();
+    round_trip_program(program);
+
+    let exps = vec![Neg(Box::new(Float(1.0))), Neg(Box::new(Float(0.0))),
+                    Neg(Box::new(var!("Infinity"))), Neg(Box::new(Float(::std::f64::INFINITY))),
+                    var!("undefined"), var!("NaN")];
+    round_trip(exps.into_iter().map(BareExp).collect());
+
+    // `NaN` isn't equal to itself, and `-0` is equal to `0`
+    match exp_from_estree(&exp_to_estree(&Float(::std::f64::NAN))).unwrap() {
+        Float(f) => assert!(f.is_nan()),
+        e => panic!("{:?}", e),
+    }
+
+    match exp_from_estree(&exp_to_estree(&Float(-0.0))).unwrap() {
+        Float(f) => assert!(f == 0.0 && f.is_sign_negative()),
+        e => panic!("{:?}", e),
+    }
+}
+
+#[test]
+fn round_trip_exps() {
+    let exps = vec![
+        exp!(exp!(var!("a"), Exponent, Float(2.0)), InstanceOf, var!("B")),
+        Array(vec![Some(Box::new(Bool(true))), None, Some(Box::new(Str(String::from("\"q\"\n")))),
+                   Some(Box::new(Spread(Box::new(var!("xs")))))]),
+        call!(instance_var!(var!("console"), "log"), vec![Neg(Box::new(var!("x"))), Float(-3.0)]),
+        NewObject(Box::new(var!("Date")), vec![]),
+        KeyAccessor(Box::new(var!("a")), Box::new(TypeOf(Box::new(var!("b"))))),
+        LogNot(Box::new(BitNot(Box::new(Pos(Box::new(var!("x"))))))),
+        exp!(post_inc!(var!("i")), Minus, pre_dec!(var!("j"))),
+        exp!(pre_inc!(var!("i")), ShiftRightUnsigned, post_dec!(var!("j"))),
+        Float(::std::f64::INFINITY),
+        Float(::std::f64::NEG_INFINITY),
+        Object(vec![Prop::Value(String::from("a"), Box::new(Null)),
+                    Prop::Value(String::from("b-c"), Box::new(Undefined)),
+                    Prop::Spread(Box::new(var!("base")))]),
+        Object(vec![Prop::Value(String::from("0"), Box::new(Null)),
+                    Prop::Computed(Box::new(var!("k")), Box::new(var!("v"))),
+                    Prop::Shorthand(String::from("x")),
+                    Prop::Method(FnKind::Normal, PropKey::Name(String::from("m")), vec![Pattern::from("a")], vec![]),
+                    Prop::Get(PropKey::Name(String::from("a")), vec![Ret(var!("b"))]),
+                    Prop::Set(PropKey::Name(String::from("a")), Pattern::from("v"), vec![])]),
+        call!(var!("f"), vec![Spread(Box::new(var!("args"))), Float(1.0)]),
+        defun! { "f", ("a", "b") Ret(exp!(var!("a"), NeqStrict, var!("b"))) },
+        Conditional(Box::new(exp!(Str(String::from("k")), In, var!("o"))),
+                    Box::new(Void(Box::new(Float(0.0)))),
+                    Box::new(Delete(Box::new(instance_var!(var!("o"), "k"))))),
+        Comma(Box::new(Comma(Box::new(var!("a")), Box::new(var!("b")))),
+              Box::new(Comma(Box::new(var!("c")), Box::new(var!("d"))))),
+        AssignExp(AssignOp::Plus, Box::new(Pattern::from(var!("a"))),
+                  Box::new(AssignExp(AssignOp::ShiftRightUnsigned, Box::new(Pattern::from(var!("b"))),
+                                     Box::new(Float(1.0))))),
+        Arrow(FnKind::Normal, vec![Pattern::from("x")], ArrowBody::Exp(Box::new(Object(vec![])))),
+        Arrow(FnKind::Normal, vec![], ArrowBody::Block(vec![BareExp(This)])),
+        RegExp(String::from("a\\/b+"), String::from("gi")),
+        exp!(exp!(var!("a"), Coalesce, var!("b")), Coalesce, exp!(var!("c"), Or, var!("d"))),
+        Chain(Box::new(KeyAccessor(Box::new(Optional(Box::new(instance_var!(Optional(Box::new(var!("a"))), "b")))),
+                                   Box::new(var!("k"))))),
+        instance_var!(Chain(Box::new(instance_var!(Optional(Box::new(var!("a"))), "b"))), "c"),
+        Template(ast::Template { quasis: vec![TemplatePart::new("a"), TemplatePart::new("`c`")],
+                                 exps: vec![Box::new(var!("b"))] }),
+        TaggedTemplate(Box::new(var!("tag")),
+                       ast::Template { quasis: vec![TemplatePart { cooked: None, raw: String::from("\\u") }],
+                                       exps: vec![] }),
+        Defun(FnKind::AsyncGenerator, Some(String::from("g")), vec![],
+              vec![BareExp(Yield(Some(Box::new(Await(Box::new(var!("x"))))))),
+                   BareExp(YieldAll(Box::new(var!("y")))),
+                   BareExp(Yield(None))]),
+        Arrow(FnKind::Async, vec![], ArrowBody::Exp(Box::new(Await(Box::new(call!(var!("f"), vec![])))))),
+        Object(vec![Prop::Method(FnKind::Normal, PropKey::Computed(Box::new(instance_var!(var!("Symbol"), "iterator"))),
+                                 vec![], vec![]),
+                    Prop::Get(PropKey::Computed(Box::new(var!("k"))), vec![Ret(Null)]),
+                    Prop::Set(PropKey::Computed(Box::new(Str(String::from("a b")))), Pattern::from("v"), vec![])]),
+        Object(vec![Prop::Method(FnKind::Generator, PropKey::Name(String::from("g")), vec![], vec![]),
+                    Prop::Method(FnKind::Async, PropKey::Name(String::from("a")), vec![], vec![])]),
+    ];
+
+    round_trip(exps.into_iter().map(BareExp).collect());
+}
+
+#[test]
+fn round_trip_stmts() {
+    round_trip(vec![
+        decl!("x", Float(0.0)),
+        decl!(var "y"),
+        assign!(var!("y"), instance_var!(var!("x"), "z")),
+        If(var!("x"), vec![Break(None)], vec![If(var!("y"), vec![Continue(None)], vec![])]),
+        For(Box::new(decl!("i", Float(0.0))), exp!(var!("i"), Lt, Float(10.0)),
+            post_inc!(var!("i")), vec![]),
+        For(Box::new(Empty), Bool(true), Undefined, vec![Break(None)]),
+        For(Box::new(assign!(var!("i"), Float(1.0))), Bool(false), Undefined, vec![]),
+        For(Box::new(BareExp(AssignExp(AssignOp::Coalesce, Box::new(Pattern::from(var!("i"))), Box::new(Null)))),
+            Bool(false), Undefined, vec![]),
+        While(Bool(false), vec![Empty]),
+        FnDecl(FnKind::Generator, String::from("f"), vec![Pattern::from("a")], vec![Ret(var!("a"))]),
+        Try(vec![Throw(Box::new(Str(String::from("e"))))], Some(Pattern::from("e")),
+            vec![BareExp(var!("e"))], vec![]),
+        Try(vec![], None, vec![], vec![Ret(Undefined)]),
+        Try(vec![], None, vec![], vec![]),
+        Block(vec![decl!(let "a" = Null, "b"), Empty, Block(vec![])]),
+        decl!(const "c" = Float(1.0)),
+        DoWhile(vec![Continue(None)], var!("c")),
+        Labeled(String::from("l"), Box::new(ForIn(Box::new(decl!(let "k")), var!("o"),
+                                                  vec![Break(Some(String::from("l")))]))),
+        ForOf(Box::new(BareExp(instance_var!(var!("o"), "x"))), Array(vec![]), vec![]),
+        Switch(var!("c"), vec![(Some(Str(String::from("a"))), vec![]),
+                               (None, vec![Block(vec![]), Break(None)])]),
+        decl!(let (Pattern::Object(vec![(String::from("a"), Pattern::from("a")),
+                                         (String::from("b"), Pattern::Array(vec![None, Some(Pattern::from("c"))]))],
+                                    Some(Box::new(Pattern::from("d"))))) = var!("o")),
+        assign!(Pattern::Array(vec![Some(Pattern::from(instance_var!(var!("o"), "a"))),
+                                    Some(Pattern::Rest(Box::new(Pattern::from(var!("b")))))]),
+                var!("c")),
+        BareExp(defun! { "f", (Pattern::Default(Box::new(Pattern::from("x")), Null),
+                               Pattern::Rest(Box::new(Pattern::from("y")))) Empty }),
+        ClassDecl(ast::Class {
+            name: Some(String::from("A")),
+            extends: Some(Box::new(var!("B"))),
+            members: vec![
+                ClassMember { is_static: false, key: ClassKey::Name(String::from("constructor")),
+                              kind: MemberKind::Method(FnKind::Normal, vec![], vec![BareExp(call!(Super, vec![]))]) },
+                ClassMember { is_static: true, key: ClassKey::Computed(Box::new(var!("k"))),
+                              kind: MemberKind::Get(vec![]) },
+                ClassMember { is_static: false, key: ClassKey::Private(String::from("x")),
+                              kind: MemberKind::Field(None) },
+                ClassMember { is_static: true, key: ClassKey::Name(String::from("constructor")),
+                              kind: MemberKind::Method(FnKind::AsyncGenerator, vec![], vec![]) },
+                ClassMember { is_static: false, key: ClassKey::Name(String::from("m")),
+                              kind: MemberKind::Set(Pattern::from("v"), vec![
+                                  BareExp(exp!(var!("#x"), In, instance_var!(var!("v"), "#x")))]) },
+            ],
+        }),
+        BareExp(Class(Box::new(ast::Class { name: None, extends: None, members: vec![] }))),
+        Ret(Float(1.0)),
+        Stmt::Error,
+        assign!(var!("a"), call!(var!("f"), vec![ast::Exp::Error])),
+    ]);
+}
+
+#[test]
+fn round_trip_module() {
+    let f = Defun(FnKind::Normal, Some(String::from("f")), vec![], vec![]);
+    let module = Program::Module(vec![
+        Import(vec![ImportSpec::Default(String::from("a")), ImportSpec::Named(String::from("b"), String::from("b")),
+                    ImportSpec::Named(String::from("c-d"), String::from("c"))],
+               String::from("./m.js")),
+        Import(vec![ImportSpec::Namespace(String::from("ns"))], String::from("ns")),
+        Import(vec![], String::from("./side-effect.js")),
+        Export(Box::new(decl!(const "x" = Float(1.0)))),
+        Export(Box::new(ClassDecl(ast::Class { name: Some(String::from("A")), extends: None, members: vec![] }))),
+        Export(Box::new(FnDecl(FnKind::Async, String::from("g"), vec![], vec![]))),
+        ExportNames(vec![(String::from("a"), String::from("default")), (String::from("b"), String::from("b"))], None),
+        ExportNames(vec![(String::from("y"), String::from("z w"))], Some(String::from("./m.js"))),
+        ExportAll(None, String::from("./all.js")),
+        ExportAll(Some(String::from("all")), String::from("./all.js")),
+        ExportDefault(f.clone()),
+    ]);
+
+    round_trip_program(module.clone());
+    round_trip_program(Program::Module(vec![ExportDefault(exp!(var!("a"), Plus, Float(1.0)))]));
+    round_trip_program(Program::Module(vec![ExportDefault(Class(Box::new(ast::Class {
+        name: None, extends: None, members: vec![] })))]));
+
+    let estree = program_to_estree(&module);
+    assert_eq!(json(r#""module""#), estree["sourceType"]);
+    assert_eq!(json(r#""FunctionDeclaration""#), estree["body"][5]["declaration"]["type"]);
+    assert_eq!(json(r#""FunctionDeclaration""#), estree["body"][10]["declaration"]["type"]);
+    assert_eq!(json(r#"{"type": "Literal", "value": "z w"}"#), estree["body"][7]["specifiers"][0]["exported"]);
+}
+
+#[test]
+fn round_trip_spans() {
+    let a = Span::new(0, 4, 1, 5, 1);
+    let sum = Span::new(0, 4, 1, 5, 5);
+    let stmt = Span::new(2, 0, 1, 1, 10);
+
+    round_trip(vec![
+        assign!(var!("x"), exp!(var!("a").with_span(a), Plus, Float(1.0)).with_span(sum))
+            .with_span(stmt),
+        decl!("y", Null).with_span(stmt),
+        For(Box::new(decl!("i", Null).with_span(a)), Bool(true), Undefined, vec![])
+            .with_span(stmt),
+    ]);
+
+    let estree = exp_to_estree(&var!("a").with_span(stmt));
+    assert_eq!(json(r#"{"type": "Identifier", "name": "a", "range": [0, 10],
+                         "loc": {"start": {"line": 1, "column": 0}, "file": 2}}"#),
+               estree);
+}
+
+#[test]
+fn from_tool_output() {
+    // As produced by acorn, with locations turned on
+    let estree = json(r#"{
+        "type": "Program", "start": 0, "end": 23, "sourceType": "script",
+        "loc": {"start": {"line": 1, "column": 0}, "end": {"line": 2, "column": 9}},
+        "body": [
+            {"type": "VariableDeclaration", "start": 0, "end": 13, "kind": "var",
+             "loc": {"start": {"line": 1, "column": 0}, "end": {"line": 1, "column": 13}},
+             "declarations": [
+                {"type": "VariableDeclarator", "start": 4, "end": 9,
+                 "loc": {"start": {"line": 1, "column": 4}, "end": {"line": 1, "column": 9}},
+                 "id": {"type": "Identifier", "start": 4, "end": 5, "name": "a"},
+                 "init": {"type": "Literal", "start": 8, "end": 9, "value": 1, "raw": "1"}},
+                {"type": "VariableDeclarator", "start": 11, "end": 12,
+                 "id": {"type": "Identifier", "start": 11, "end": 12, "name": "b"},
+                 "init": null}]},
+            {"type": "FunctionDeclaration", "start": 14, "end": 23,
+             "id": {"type": "Identifier", "name": "f"},
+             "params": [], "generator": false, "async": false,
+             "body": {"type": "BlockStatement", "body": []}}
+        ]
+    }"#);
+
+    assert_eq!(Program::Script(vec![decl!(var "a" = Float(1.0), "b").with_span(Span::new(0, 0, 1, 1, 13)),
+                                    FnDecl(FnKind::Normal, String::from("f"), vec![], vec![])]),
+               program_from_estree(&estree).unwrap());
+}
+
+#[test]
+fn unsupported_nodes() {
+    let unimplemented = |s: &str| {
+        match exp_from_estree(&json(s)) {
+            Err(JsError::UnimplementedError(msg)) => msg,
+            r => panic!("expected an UnimplementedError, got {:?}", r),
+        }
+    };
+
+    assert_eq!("ESTree MetaProperty",
+               unimplemented(r#"{"type": "MetaProperty",
+                                 "meta": {"type": "Identifier", "name": "new"},
+                                 "property": {"type": "Identifier", "name": "target"}}"#));
+    assert_eq!("ESTree operator `|>`",
+               unimplemented(r#"{"type": "LogicalExpression", "operator": "|>",
+                                 "left": {"type": "Literal", "value": "a"},
+                                 "right": {"type": "Identifier", "name": "o"}}"#));
+
+    assert!(matches!(stmt_from_estree(&json(r#"{"type": "VariableDeclaration", "kind": "using",
+                                                  "declarations": []}"#)),
+                     Err(JsError::UnimplementedError(_))));
+    assert!(matches!(stmt_from_estree(&json(r#"{"type": "ForOfStatement", "await": true,
+                                                  "left": {"type": "Identifier", "name": "x"},
+                                                  "right": {"type": "Identifier", "name": "y"},
+                                                  "body": {"type": "EmptyStatement"}}"#)),
+                     Err(JsError::UnimplementedError(_))));
+    assert!(matches!(exp_from_estree(&json(r#"{"type": "BinaryExpression", "operator": "+"}"#)),
+                     Err(JsError::ParseError(_))));
+    assert!(matches!(exp_from_estree(&json(r#"{"name": "x"}"#)), Err(JsError::ParseError(_))));
+}
+
+#[test]
+fn seqs_are_spliced() {
+    let stmts = vec![seq!(Break(None), seq!(Continue(None), Empty)),
+                     For(Box::new(While(Bool(true), vec![])), Bool(true), Undefined, vec![])];
+
+    assert_eq!(Program::Script(vec![Break(None), Continue(None), Empty, While(Bool(true), vec![]),
+                                    For(Box::new(Empty), Bool(true), Undefined, vec![])]),
+               program_from_estree(&program_to_estree(&Program::Script(stmts))).unwrap());
+}
+
+#[test]
+fn comments() {
+    let comment = |text: &str, block, lines_before| {
+        ast::Comment { text: String::from(text), block: block, lines_before: lines_before }
+    };
+    let leading = ast::Comments { leading: vec![comment("* Doc. ", true, 1)],
+                                  trailing: vec![comment(" a", false, 0)],
+                                  lines_before: 1 };
+    let exp = ast::Comments { leading: vec![comment(" one ", true, 0)], ..Default::default() };
+    let s = Decl(ast::DeclKind::Let, vec![(Pattern::from("a"), Some(Float(1.0).with_comments(exp)))])
+        .with_comments(leading);
+
+    assert_eq!(json(r#"{"type": "Literal", "value": 1.0,
+                         "leadingComments": [{"type": "CommentBlock", "value": " one "}]}"#),
+               stmt_to_estree(&s)["declarations"][0]["init"]);
+    assert_eq!(json(r#"[{"type": "CommentLine", "value": " a"}]"#),
+               stmt_to_estree(&s)["trailingComments"]);
+
+    round_trip(vec![s, Empty.with_comments(ast::Comments { leading: vec![comment(" end", false, 1)],
+                                                           lines_before: 1,
+                                                           ..Default::default() })]);
+}
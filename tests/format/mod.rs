@@ -1,7 +1,9 @@
+use jsrs_common::ast::{self, ArrowBody, ClassKey, ClassMember, Exp, FnKind, ImportSpec, MemberKind,
+                       Pattern, Program, Prop, PropKey, TemplatePart};
 use jsrs_common::ast::BinOp::*;
 use jsrs_common::ast::Exp::*;
 use jsrs_common::ast::Stmt::*;
-use std::f64::NAN;
+use std::f64::{NAN, NEG_INFINITY};
 
 macro_rules! format_exp {
     ($e1:expr, $o:expr, $e2:expr) => { &format!("{}", exp!($e1, $o, $e2)) }
@@ -68,6 +70,25 @@ fn constants() {
     assert_eq!("true", format!("{}", Bool(true)));
 }
 
+#[test]
+fn numbers() {
+    // Numbers are written the way JavaScript's `toString` writes them
+    assert_eq!("1e+300", format!("{}", Float(1e300)));
+    assert_eq!("-1.5e+300", format!("{}", Float(-1.5e300)));
+    assert_eq!("5e-324", format!("{}", Float(5e-324)));
+    assert_eq!("1.7976931348623157e+308", format!("{}", Float(::std::f64::MAX)));
+    assert_eq!("1e+21", format!("{}", Float(1e21)));
+    assert_eq!("100000000000000000000", format!("{}", Float(1e20)));
+    assert_eq!("123456789012345680000", format!("{}", Float(123456789012345678901.0)));
+    assert_eq!("0.000001", format!("{}", Float(1e-6)));
+    assert_eq!("1.5e-7", format!("{}", Float(1.5e-7)));
+    assert_eq!("0.1", format!("{}", Float(0.1)));
+    assert_eq!("0.30000000000000004", format!("{}", Float(0.1 + 0.2)));
+    assert_eq!("0", format!("{}", Float(0.0)));
+    assert_eq!("-0", format!("{}", Float(-0.0)));
+    assert_eq!("-Infinity", format!("{}", Float(NEG_INFINITY)));
+}
+
 #[test]
 fn vars() {
     assert_eq!("x", format!("{}", var!("x")));
@@ -132,7 +153,7 @@ fn multi_binop_exprs_with_grouping() {
     assert_eq!("+(++(((((a || b) && c) == d) + e) * f))",
         &format!("{}", Pos(Box::new(pre_inc!(
             exp!(exp!(exp!(exp!(exp!(var!("a"), Or, var!("b")), And, var!("c")), Eql, var!("d")), Plus, var!("e")), Star, var!("f")))))));
-    assert_eq!("(_Ok - h2o) * 17 / -3.25",
+    assert_eq!("(_Ok - h2o) * (17 / -3.25)",
         format_exp!(exp!(var!("_Ok"), Minus, var!("h2o")), Star, exp!(Float(17.0), Slash, Float(-3.25))));
     assert_eq!("(-10 + 18.5 - 17) * -3.25",
         format_exp!(exp!(exp!(Float(-10.0), Plus, Float(18.5)), Minus, Float(17.0)), Star, Float(-3.25)));
@@ -170,7 +191,7 @@ fn obj_literal() {
     assert_eq!("{}", format_obj!());
     assert_eq!("{\n  x: 3\n}", format_obj! { "x" => Float(3.0) });
     assert_eq!("{\n  x: 3,\n  y: NaN\n}", format_obj! { "x" => Float(3.0), "y" => Float(NAN) });
-    assert_eq!("{\n  x: 3,\n  y: {\n    z: NaN\n}\n}",
+    assert_eq!("{\n  x: 3,\n  y: {\n    z: NaN\n  }\n}",
         format_obj! {
             "x" => Float(3.0),
             "y" => obj! { "z" => Float(NAN) }
@@ -230,3 +251,489 @@ fn seq_stmts() {
             decl!("NUM", exp!(exp!(Float(-34.5), Slash, var!("_l4")), Plus, Float(8.0))),
             assign!(var!("thing2"), exp!(exp!(var!("r"), Star, Float(-51.0)), Plus, Float(3.5))))));
 }
+
+#[test]
+fn if_stmts() {
+    assert_eq!("if (x) {\n  y;\n}\n", &format!("{}", If(var!("x"), vec![BareExp(var!("y"))], vec![])));
+    assert_eq!("if (x) {\n  y;\n} else {\n  z;\n}\n",
+        &format!("{}", If(var!("x"), vec![BareExp(var!("y"))], vec![BareExp(var!("z"))])));
+    assert_eq!("if (x) {\n} else if (y) {\n  z;\n} else {\n  w;\n}\n",
+        &format!("{}", If(var!("x"), vec![],
+            vec![If(var!("y"), vec![BareExp(var!("z"))], vec![BareExp(var!("w"))])])));
+    assert_eq!("if (a) {\n  if (b) {\n    return c;\n  }\n}\n",
+        &format!("{}", If(var!("a"), vec![If(var!("b"), vec![Ret(var!("c"))], vec![])], vec![])));
+}
+
+#[test]
+fn loop_stmts() {
+    assert_eq!("while (x < 10) {\n  x++;\n  break;\n  continue;\n}\n",
+        &format!("{}", While(exp!(var!("x"), Lt, Float(10.0)),
+            vec![BareExp(post_inc!(var!("x"))), Break(None), Continue(None)])));
+    assert_eq!("for (var i = 0; i < n; i++) {\n  f(i);\n}\n",
+        &format!("{}", For(Box::new(decl!("i", Float(0.0))), exp!(var!("i"), Lt, var!("n")),
+            post_inc!(var!("i")), vec![BareExp(call!(var!("f"), vec![var!("i")]))])));
+    assert_eq!("for (i = 0; true;) {\n}\n",
+        &format!("{}", For(Box::new(assign!(var!("i"), Float(0.0))), Bool(true), Undefined, vec![])));
+    assert_eq!("for (; x; x--) {\n}\n",
+        &format!("{}", For(Box::new(Empty), var!("x"), post_dec!(var!("x")), vec![])));
+}
+
+#[test]
+fn try_stmts() {
+    assert_eq!("try {\n  f();\n} catch (e) {\n  g(e);\n}\n",
+        &format!("{}", Try(vec![BareExp(call!(var!("f"), Vec::new()))], Some(Pattern::from("e")),
+            vec![BareExp(call!(var!("g"), vec![var!("e")]))], vec![])));
+    assert_eq!("try {\n} finally {\n  h();\n}\n",
+        &format!("{}", Try(vec![], None, vec![], vec![BareExp(call!(var!("h"), Vec::new()))])));
+    assert_eq!("try {\n} catch {\n}\n", &format!("{}", Try(vec![], None, vec![], vec![])));
+    assert_eq!("try {\n} catch (e) {\n} finally {\n  x;\n}\n",
+        &format!("{}", Try(vec![], Some(Pattern::from("e")), vec![], vec![BareExp(var!("x"))])));
+}
+
+#[test]
+fn simple_stmts() {
+    assert_eq!("throw new Error(\"oops\");\n",
+        &format!("{}", Throw(Box::new(new_obj!(var!("Error"), Str(String::from("oops")))))));
+    assert_eq!("var x;\n", &format!("{}", decl!(var "x")));
+    assert_eq!("return x;\n", &format!("{}", Ret(var!("x"))));
+    assert_eq!("return;\n", &format!("{}", Ret(Undefined)));
+    assert_eq!(";\n", &format!("{}", Empty));
+    assert_eq!("function f() {\n  break;\n  var y;\n  throw x;\n}",
+        &format!("{}", defun! { "f", () seq!(Break(None), seq!(decl!(var "y"), Throw(Box::new(var!("x"))))) }));
+}
+
+#[test]
+fn ambiguous_stmt_starts() {
+    assert_eq!("({\n  x: 3\n});\n", format_bare_exp!(obj! { "x" => Float(3.0) }));
+    assert_eq!("(function() {\n}());\n", format_bare_exp!(call!(defun! { () Empty }, Vec::<jsrs_common::ast::Exp>::new())));
+    assert_eq!("({}.x) = 1;\n", &format!("{}", assign!(instance_var!(obj!(), "x"), Float(1.0))));
+}
+
+#[test]
+fn operand_grouping() {
+    assert_eq!("a + (b - c)", format_exp!(var!("a"), Plus, exp!(var!("b"), Minus, var!("c"))));
+    assert_eq!("a * (b / c)", format_exp!(var!("a"), Star, exp!(var!("b"), Slash, var!("c"))));
+    assert_eq!("a ** b ** c", format_exp!(var!("a"), Exponent, exp!(var!("b"), Exponent, var!("c"))));
+    assert_eq!("(a ** b) ** c", format_exp!(exp!(var!("a"), Exponent, var!("b")), Exponent, var!("c")));
+    assert_eq!("(-2) ** 2", format_exp!(Float(-2.0), Exponent, Float(2.0)));
+    assert_eq!("- -x", &format!("{}", Neg(Box::new(neg_var!("x")))));
+    assert_eq!("+ +x", &format!("{}", Pos(Box::new(pos_var!("x")))));
+    assert_eq!("-(--x)", &format!("{}", Neg(Box::new(pre_dec!(var!("x"))))));
+    assert_eq!("typeof (a + b)", &format!("{}", TypeOf(Box::new(exp!(var!("a"), Plus, var!("b"))))));
+    assert_eq!("(a || b)(c)", format_call!(exp!(var!("a"), Or, var!("b")), vec![var!("c")]));
+    assert_eq!("(a + b).c", format_instance_var!(exp!(var!("a"), Plus, var!("b")), "c"));
+    assert_eq!("(1).toString", format_instance_var!(Float(1.0), "toString"));
+    assert_eq!("new (f())()", format_new_obj!(call!(var!("f"), Vec::<jsrs_common::ast::Exp>::new())));
+    assert_eq!("-Infinity", &format!("{}", Float(-1.0 / 0.0)));
+}
+
+#[test]
+fn str_literals() {
+    assert_eq!("\"abc\"", &format!("{}", Str(String::from("abc"))));
+    assert_eq!(r#""say \"hi\"""#, &format!("{}", Str(String::from("say \"hi\""))));
+    assert_eq!(r#""it's""#, &format!("{}", Str(String::from("it's"))));
+    assert_eq!(r#""C:\\dir\\""#, &format!("{}", Str(String::from("C:\\dir\\"))));
+    assert_eq!(r#""a\nb\r\tc\b\f\v""#, &format!("{}", Str(String::from("a\nb\r\tc\u{8}\u{c}\u{b}"))));
+    assert_eq!(r#""\0a\x001""#, &format!("{}", Str(String::from("\0a\u{0}1"))));
+    assert_eq!(r#""\x1B[0m\x7F""#, &format!("{}", Str(String::from("\u{1b}[0m\u{7f}"))));
+    assert_eq!(r#""\u2028\u2029""#, &format!("{}", Str(String::from("\u{2028}\u{2029}"))));
+    assert_eq!("\"café\"", &format!("{}", Str(String::from("café"))));
+    assert_eq!(r#""\uD83D\uDE00!""#, &format!("{}", Str(String::from("\u{1F600}!"))));
+}
+
+#[test]
+fn obj_literal_keys() {
+    assert_eq!("{\n  $x_1: 1,\n  \"my-key\": 2,\n  \"1a\": 3,\n  \"\": 4,\n  \"a\\\"b\": 5,\n  ünï: 6,\n  \"a²\": 7\n}",
+        format_obj! {
+            "$x_1" => Float(1.0),
+            "my-key" => Float(2.0),
+            "1a" => Float(3.0),
+            "" => Float(4.0),
+            "a\"b" => Float(5.0),
+            "ünï" => Float(6.0),
+            "a²" => Float(7.0)
+        });
+}
+
+#[test]
+fn conditional_comma_exps() {
+    let cond = |test, e1, e2| Conditional(Box::new(test), Box::new(e1), Box::new(e2));
+    let comma = |e1, e2| Comma(Box::new(e1), Box::new(e2));
+
+    assert_eq!("a ? b : c", &format!("{}", cond(var!("a"), var!("b"), var!("c"))));
+    assert_eq!("a || b ? c : d ? e : f",
+        &format!("{}", cond(exp!(var!("a"), Or, var!("b")), var!("c"), cond(var!("d"), var!("e"), var!("f")))));
+    assert_eq!("(a ? b : c) ? d : e",
+        &format!("{}", cond(cond(var!("a"), var!("b"), var!("c")), var!("d"), var!("e"))));
+    assert_eq!("(a ? b : c) + 1", format_exp!(cond(var!("a"), var!("b"), var!("c")), Plus, Float(1.0)));
+    assert_eq!("a ? (b, c) : d", &format!("{}", cond(var!("a"), comma(var!("b"), var!("c")), var!("d"))));
+    assert_eq!("a, b, c", &format!("{}", comma(comma(var!("a"), var!("b")), var!("c"))));
+    assert_eq!("a, (b, c)", &format!("{}", comma(var!("a"), comma(var!("b"), var!("c")))));
+    assert_eq!("f((a, b), c)", format_call!(var!("f"), vec![comma(var!("a"), var!("b")), var!("c")]));
+    assert_eq!("[(a, b)]", &format!("{}", Array(vec![Some(Box::new(comma(var!("a"), var!("b"))))])));
+    assert_eq!("x = (a, b);\n", &format!("{}", assign!(var!("x"), comma(var!("a"), var!("b")))));
+    assert_eq!("a, b;\n", format_bare_exp!(comma(var!("a"), var!("b"))));
+}
+
+#[test]
+fn void_delete_in_exps() {
+    assert_eq!("void 0", &format!("{}", Void(Box::new(Float(0.0)))));
+    assert_eq!("delete o.p", &format!("{}", Delete(Box::new(instance_var!(var!("o"), "p")))));
+    assert_eq!("void (a, b)", &format!("{}", Void(Box::new(Comma(Box::new(var!("a")), Box::new(var!("b")))))));
+    assert_eq!("!(\"k\" in o)", &format!("{}", LogNot(Box::new(exp!(Str(String::from("k")), In, var!("o"))))));
+    assert_eq!("k in o === true", format_exp!(exp!(var!("k"), In, var!("o")), EqlStrict, Bool(true)));
+    assert_eq!("for (var x = (\"k\" in o); x;) {\n}\n",
+        &format!("{}", For(Box::new(decl!("x", exp!(Str(String::from("k")), In, var!("o")))), var!("x"), Undefined, vec![])));
+    assert_eq!("for (i = (a in b) + 1; c in d;) {\n}\n",
+        &format!("{}", For(Box::new(assign!(var!("i"), exp!(exp!(var!("a"), In, var!("b")), Plus, Float(1.0)))),
+                           exp!(var!("c"), In, var!("d")), Undefined, vec![])));
+}
+
+#[test]
+fn assign_exps() {
+    use jsrs_common::ast::AssignOp;
+
+    let assign = |o, lhs: Exp, rhs| AssignExp(o, Box::new(Pattern::from(lhs)), Box::new(rhs));
+
+    assert_eq!("x += 1", &format!("{}", assign(AssignOp::Plus, var!("x"), Float(1.0))));
+    assert_eq!("a = b = 0",
+        &format!("{}", assign(AssignOp::Assign, var!("a"), assign(AssignOp::Assign, var!("b"), Float(0.0)))));
+    assert_eq!("(a = b) >>>= 0",
+        &format!("{}", assign(AssignOp::ShiftRightUnsigned, assign(AssignOp::Assign, var!("a"), var!("b")), Float(0.0))));
+    assert_eq!("x ??= y ? 1 : 2",
+        &format!("{}", assign(AssignOp::Coalesce, var!("x"),
+                              Conditional(Box::new(var!("y")), Box::new(Float(1.0)), Box::new(Float(2.0))))));
+    assert_eq!("a ? b = 1 : c &&= 2",
+        &format!("{}", Conditional(Box::new(var!("a")), Box::new(assign(AssignOp::Assign, var!("b"), Float(1.0))),
+                                   Box::new(assign(AssignOp::And, var!("c"), Float(2.0))))));
+    assert_eq!("(a = b) ? c : d",
+        &format!("{}", Conditional(Box::new(assign(AssignOp::Assign, var!("a"), var!("b"))),
+                                   Box::new(var!("c")), Box::new(var!("d")))));
+    assert_eq!("(x **= 2) + 1", format_exp!(assign(AssignOp::Exponent, var!("x"), Float(2.0)), Plus, Float(1.0)));
+    assert_eq!("f(x |= 1, (a, b))",
+        format_call!(var!("f"), vec![assign(AssignOp::BitOr, var!("x"), Float(1.0)),
+                                     Comma(Box::new(var!("a")), Box::new(var!("b")))]));
+    assert_eq!("while (m = next()) {\n}\n",
+        &format!("{}", While(assign(AssignOp::Assign, var!("m"), call!(var!("next"), Vec::<jsrs_common::ast::Exp>::new())), vec![])));
+    assert_eq!("({}.x = 1);\n",
+        format_bare_exp!(assign(AssignOp::Assign, instance_var!(obj!(), "x"), Float(1.0))));
+}
+
+#[test]
+fn lexical_decls() {
+    assert_eq!("let a = 1, b;\n", &format!("{}", decl!(let "a" = Float(1.0), "b")));
+    assert_eq!("const c = (x, y);\n",
+        &format!("{}", decl!(const "c" = Comma(Box::new(var!("x")), Box::new(var!("y"))))));
+    assert_eq!("var a, b = 2;\n", &format!("{}", decl!(var "a", "b" = Float(2.0))));
+    assert_eq!("{\n  let x = 1;\n  {\n  }\n}\n",
+        &format!("{}", Block(vec![decl!(let "x" = Float(1.0)), Block(vec![])])));
+    assert_eq!("for (let i = 0, n = a.length; i < n; i++) {\n}\n",
+        &format!("{}", For(Box::new(decl!(let "i" = Float(0.0), "n" = instance_var!(var!("a"), "length"))),
+                           exp!(var!("i"), Lt, var!("n")), post_inc!(var!("i")), vec![])));
+    assert_eq!("(let[0]);\n", format_bare_exp!(KeyAccessor(Box::new(var!("let")), Box::new(Float(0.0)))));
+}
+
+#[test]
+fn iteration_and_labeled_stmts() {
+    assert_eq!("do {\n  x--;\n} while (x > 0);\n",
+        &format!("{}", DoWhile(vec![BareExp(post_dec!(var!("x")))], exp!(var!("x"), Gt, Float(0.0)))));
+    assert_eq!("for (const k in o) {\n  f(k);\n}\n",
+        &format!("{}", ForIn(Box::new(decl!(const "k")), var!("o"),
+                             vec![BareExp(call!(var!("f"), vec![var!("k")]))])));
+    assert_eq!("for (o.k in a, b) {\n}\n",
+        &format!("{}", ForIn(Box::new(BareExp(instance_var!(var!("o"), "k"))),
+                             Comma(Box::new(var!("a")), Box::new(var!("b"))), vec![])));
+    assert_eq!("for (x of (a, b)) {\n}\n",
+        &format!("{}", ForOf(Box::new(BareExp(var!("x"))),
+                             Comma(Box::new(var!("a")), Box::new(var!("b"))), vec![])));
+    assert_eq!("outer: while (true) {\n  for (let x of xs) {\n    continue outer;\n  }\n  break;\n}\n",
+        &format!("{}", Labeled(String::from("outer"), Box::new(While(Bool(true), vec![
+            ForOf(Box::new(decl!(let "x")), var!("xs"), vec![Continue(Some(String::from("outer")))]),
+            Break(None)])))));
+    assert_eq!("a: {\n  break a;\n}\n",
+        &format!("{}", Labeled(String::from("a"), Box::new(Block(vec![Break(Some(String::from("a")))])))));
+}
+
+#[test]
+fn switch_stmt() {
+    assert_eq!("switch (x) {\n  case 1:\n  case 2:\n    f();\n    break;\n  default:\n    g();\n  case a, b:\n}\n",
+        &format!("{}", Switch(var!("x"), vec![
+            (Some(Float(1.0)), vec![]),
+            (Some(Float(2.0)), vec![BareExp(call!(var!("f"), vec![])), Break(None)]),
+            (None, vec![BareExp(call!(var!("g"), vec![]))]),
+            (Some(Comma(Box::new(var!("a")), Box::new(var!("b")))), vec![]),
+        ])));
+    assert_eq!("switch (x) {\n}\n", &format!("{}", Switch(var!("x"), vec![])));
+}
+
+#[test]
+fn arrow_fns() {
+    let arrow = |params: Vec<&str>, body| Arrow(FnKind::Normal, params.into_iter().map(Pattern::from).collect(), body);
+    let exp_body = |e| ArrowBody::Exp(Box::new(e));
+
+    assert_eq!("x => x + 1", &format!("{}", arrow(vec!["x"], exp_body(exp!(var!("x"), Plus, Float(1.0))))));
+    assert_eq!("(a, b) => {\n  f();\n  return a;\n}",
+        &format!("{}", arrow(vec!["a", "b"], ArrowBody::Block(vec![BareExp(call!(var!("f"), vec![])),
+                                                                   Ret(var!("a"))]))));
+    assert_eq!("() => ({})", &format!("{}", arrow(vec![], exp_body(obj! {}))));
+    assert_eq!("() => ({}.x)", &format!("{}", arrow(vec![], exp_body(instance_var!(obj! {}, "x")))));
+    assert_eq!("x => (a, x)",
+        &format!("{}", arrow(vec!["x"], exp_body(Comma(Box::new(var!("a")), Box::new(var!("x")))))));
+    assert_eq!("() => {\n}", &format!("{}", arrow(vec![], ArrowBody::Block(vec![]))));
+
+    // A block stays a block, even if all it does is return a value
+    assert_eq!("x => {\n  return y => {\n    return x;\n  };\n}", &format!("{}", arrow(vec!["x"], ArrowBody::Block(vec![
+        Ret(arrow(vec!["y"], ArrowBody::Block(vec![Empty, Ret(var!("x"))])))]))));
+    assert_eq!("() => {\n  return;\n}",
+        &format!("{}", arrow(vec![], ArrowBody::Block(vec![Ret(Undefined)]))));
+
+    assert_eq!("(x => x)(1)", format_call!(arrow(vec!["x"], exp_body(var!("x"))), vec![Float(1.0)]));
+    assert_eq!("f(x => x, 1)", format_call!(var!("f"), vec![arrow(vec!["x"], exp_body(var!("x"))), Float(1.0)]));
+    assert_eq!("a || (() => b)", format_exp!(var!("a"), Or, arrow(vec![], exp_body(var!("b")))));
+}
+
+#[test]
+fn destructuring_patterns() {
+    let default = |p, e| Pattern::Default(Box::new(p), e);
+    let rest = |p| Pattern::Rest(Box::new(p));
+    let obj_pattern = |props: Vec<(&str, Pattern)>, rest: Option<Pattern>| {
+        Pattern::Object(props.into_iter().map(|(k, p)| (String::from(k), p)).collect(), rest.map(Box::new))
+    };
+
+    let f = Defun(FnKind::Normal, Some(String::from("f")), vec![default(Pattern::from("a"), Float(1.0)), rest(Pattern::from("rest"))],
+                  vec![]);
+    assert_eq!("function f(a = 1, ...rest) {\n}", &format!("{}", f));
+
+    let g = Defun(FnKind::Normal, Some(String::from("g")),
+                  vec![obj_pattern(vec![("x", Pattern::from("x")), ("y", Pattern::from("z"))], None),
+                       Pattern::Array(vec![Some(Pattern::from("first"))])],
+                  vec![]);
+    assert_eq!("function g({x, y: z}, [first]) {\n}", &format!("{}", g));
+
+    assert_eq!("({a = 1, \"b-c\": [, d, ,], ...e}) => a",
+        &format!("{}", Arrow(FnKind::Normal, vec![obj_pattern(vec![("a", default(Pattern::from("a"), Float(1.0))),
+                                                   ("b-c", Pattern::Array(vec![None, Some(Pattern::from("d")), None]))],
+                                              Some(Pattern::from("e")))],
+                             ArrowBody::Exp(Box::new(var!("a"))))));
+    assert_eq!("const [k, v] = entry;\n",
+        &format!("{}", decl!(const (Pattern::Array(vec![Some(Pattern::from("k")), Some(Pattern::from("v"))])) = var!("entry"))));
+
+    // In assignments the targets are expressions
+    let swap = Pattern::Array(vec![Some(Pattern::from(var!("a"))), Some(Pattern::from(instance_var!(var!("o"), "b")))]);
+    assert_eq!("[a, o.b] = [o.b, a];\n",
+        &format!("{}", assign!(swap, Array(vec![Some(Box::new(instance_var!(var!("o"), "b"))), Some(Box::new(var!("a")))]))));
+    assert_eq!("({a, b: o.c} = x);\n",
+        &format!("{}", assign!(obj_pattern(vec![("a", Pattern::from(var!("a"))),
+                                                 ("b", Pattern::from(instance_var!(var!("o"), "c")))], None),
+                               var!("x"))));
+    assert_eq!("({a} = x);\n",
+        format_bare_exp!(AssignExp(jsrs_common::ast::AssignOp::Assign,
+                                   Box::new(obj_pattern(vec![("a", Pattern::from(var!("a")))], None)),
+                                   Box::new(var!("x")))));
+}
+
+#[test]
+fn spread_and_holes() {
+    let spread = |e| Spread(Box::new(e));
+    let elems = |elems: Vec<Option<Exp>>| Array(elems.into_iter().map(|e| e.map(Box::new)).collect());
+
+    assert_eq!("[1, , 3]", &format!("{}", elems(vec![Some(Float(1.0)), None, Some(Float(3.0))])));
+    assert_eq!("[, ,]", &format!("{}", elems(vec![None, None])));
+    assert_eq!("[...xs, ...(a, b)]",
+        &format!("{}", elems(vec![Some(spread(var!("xs"))), Some(spread(Comma(Box::new(var!("a")), Box::new(var!("b")))))])));
+    assert_eq!("f(...args)", format_call!(var!("f"), vec![spread(var!("args"))]));
+    assert_eq!("new C(...a, b)", &format!("{}", NewObject(Box::new(var!("C")),
+                                                        vec![Box::new(spread(var!("a"))), Box::new(var!("b"))])));
+    assert_eq!("{\n  ...base,\n  x: 1\n}",
+        &format!("{}", Object(vec![Prop::Spread(Box::new(var!("base"))),
+                                   Prop::Value(String::from("x"), Box::new(Float(1.0)))])));
+}
+
+#[test]
+fn object_props() {
+    let prop = |name: &str, e| Prop::Value(String::from(name), Box::new(e));
+
+    assert_eq!("{\n  0: a,\n  \"1.5\": b,\n  \"01\": c,\n  \"-1\": d\n}",
+        &format!("{}", Object(vec![prop("0", var!("a")), prop("1.5", var!("b")), prop("01", var!("c")),
+                                   prop("-1", var!("d"))])));
+    assert_eq!("{\n  100000000000000000000: a,\n  \"1000000000000000000000\": b,\n  \"0.0000001\": c\n}",
+        &format!("{}", Object(vec![prop("100000000000000000000", var!("a")),
+                                   prop("1000000000000000000000", var!("b")), prop("0.0000001", var!("c"))])));
+    assert_eq!("{\n  [(a, b)]: 1,\n  x\n}",
+        &format!("{}", Object(vec![Prop::Computed(Box::new(Comma(Box::new(var!("a")), Box::new(var!("b")))),
+                                                  Box::new(Float(1.0))),
+                                   Prop::Shorthand(String::from("x"))])));
+    assert_eq!("{\n  m(a, b) {\n    return a;\n  },\n  \"a-b\"() {\n  }\n}",
+        &format!("{}", Object(vec![Prop::Method(FnKind::Normal, PropKey::Name(String::from("m")), vec![Pattern::from("a"), Pattern::from("b")],
+                                                vec![Ret(var!("a"))]),
+                                   Prop::Method(FnKind::Normal, PropKey::Name(String::from("a-b")), vec![], vec![])])));
+    assert_eq!("{\n  get a() {\n    return 1;\n  },\n  set a(v) {\n  }\n}",
+        &format!("{}", Object(vec![Prop::Get(PropKey::Name(String::from("a")), vec![Ret(Float(1.0))]),
+                                   Prop::Set(PropKey::Name(String::from("a")), Pattern::from("v"), vec![])])));
+}
+
+#[test]
+fn template_literals() {
+    let template = |parts: Vec<&str>, exps: Vec<Exp>| {
+        ast::Template { quasis: parts.into_iter().map(TemplatePart::new).collect(),
+                        exps: exps.into_iter().map(Box::new).collect() }
+    };
+
+    assert_eq!("`Hello ${name}!`", &format!("{}", Template(template(vec!["Hello ", "!"], vec![var!("name")]))));
+    assert_eq!("`\\` \\${a} $ \\\\ \\r\n`",
+        &format!("{}", Template(template(vec!["` ${a} $ \\ \r\n"], vec![]))));
+    assert_eq!("`${a, b}${c}`",
+        &format!("{}", Template(template(vec!["", "", ""], vec![Comma(Box::new(var!("a")), Box::new(var!("b"))),
+                                                                 var!("c")]))));
+    assert_eq!("tag`a${b}c`",
+        &format!("{}", TaggedTemplate(Box::new(var!("tag")), template(vec!["a", "c"], vec![var!("b")]))));
+    assert_eq!("(a || b)`x`",
+        &format!("{}", TaggedTemplate(Box::new(exp!(var!("a"), Or, var!("b"))), template(vec!["x"], vec![]))));
+
+    // The raw text is printed as it is, even where it isn't a valid string escape
+    let raw = ast::Template { quasis: vec![TemplatePart { cooked: None, raw: String::from("\\unicode") }],
+                              exps: vec![] };
+    assert_eq!("String.raw`\\unicode`", &format!("{}", TaggedTemplate(Box::new(instance_var!(var!("String"), "raw")), raw)));
+    assert_eq!("new (f()`x`)()",
+        &format!("{}", NewObject(Box::new(TaggedTemplate(Box::new(call!(var!("f"), vec![])), template(vec!["x"], vec![]))),
+                                 vec![])));
+}
+
+#[test]
+fn regexp_literals() {
+    let re = |pattern: &str, flags: &str| RegExp(String::from(pattern), String::from(flags));
+
+    assert_eq!("/ab+c/gi", &format!("{}", re("ab+c", "gi")));
+    assert_eq!("/(?:)/", &format!("{}", re("", "")));
+    assert_eq!("a / /b/", format_exp!(var!("a"), Slash, re("b", "")));
+    assert_eq!("/a/.test(s)", format_call!(instance_var!(re("a", ""), "test"), vec![var!("s")]));
+}
+
+#[test]
+fn classes() {
+    let member = |is_static, key, kind| ClassMember { is_static: is_static, key: key, kind: kind };
+    let name = |s: &str| ClassKey::Name(String::from(s));
+
+    let class = ast::Class {
+        name: Some(String::from("A")),
+        extends: Some(Box::new(var!("B"))),
+        members: vec![
+            member(false, name("constructor"),
+                   MemberKind::Method(FnKind::Normal, vec![], vec![BareExp(call!(Super, vec![]))])),
+            member(true, name("s"), MemberKind::Method(FnKind::Normal, vec![Pattern::from("x")], vec![])),
+            member(false, name("g"), MemberKind::Get(vec![Ret(instance_var!(Super, "g"))])),
+            member(false, ClassKey::Private(String::from("priv")), MemberKind::Field(Some(Float(1.0)))),
+            member(true, ClassKey::Computed(Box::new(var!("k"))), MemberKind::Field(None)),
+            member(false, name("v"), MemberKind::Set(Pattern::from("v"),
+                                                    vec![assign!(instance_var!(This, "#priv"), var!("v"))])),
+        ],
+    };
+
+    assert_eq!("class A extends B {\n  constructor() {\n    super();\n  }\n  static s(x) {\n  }\n  \
+                get g() {\n    return super.g;\n  }\n  #priv = 1;\n  static [k];\n  \
+                set v(v) {\n    this.#priv = v;\n  }\n}\n",
+               &format!("{}", ClassDecl(class.clone())));
+    assert!(class.constructor().is_some());
+
+    let anon = ast::Class { name: None, extends: Some(Box::new(call!(var!("mixin"), vec![]))), members: vec![] };
+    assert_eq!("(class extends mixin() {});\n", &format!("{}", BareExp(Class(Box::new(anon.clone())))));
+    assert_eq!("class extends (a, b) {}",
+               &format!("{}", Class(Box::new(ast::Class { extends: Some(Box::new(Comma(Box::new(var!("a")), Box::new(var!("b"))))),
+                                                          ..anon }))));
+}
+
+#[test]
+fn optional_chains_and_coalescing() {
+    let opt = |e| Optional(Box::new(e));
+    let chain = |e| Chain(Box::new(e));
+
+    assert_eq!("a?.b.c", &format!("{}", chain(instance_var!(instance_var!(opt(var!("a")), "b"), "c"))));
+    assert_eq!("(a?.b).c", &format!("{}", instance_var!(chain(instance_var!(opt(var!("a")), "b")), "c")));
+    assert_eq!("a?.[k]?.()", &format!("{}", chain(call!(opt(KeyAccessor(Box::new(opt(var!("a"))), Box::new(var!("k")))), vec![]))));
+    assert_eq!("(a?.b)()", format_call!(chain(instance_var!(opt(var!("a")), "b")), vec![]));
+    assert_eq!("new (a?.b)()", &format!("{}", NewObject(Box::new(chain(instance_var!(opt(var!("a")), "b"))), vec![])));
+    assert_eq!("(a + b)?.c", &format!("{}", chain(instance_var!(opt(exp!(var!("a"), Plus, var!("b"))), "c"))));
+
+    assert_eq!("a ?? b ?? c", format_exp!(exp!(var!("a"), Coalesce, var!("b")), Coalesce, var!("c")));
+    assert_eq!("a ?? (b ?? c)", format_exp!(var!("a"), Coalesce, exp!(var!("b"), Coalesce, var!("c"))));
+    assert_eq!("(a || b) ?? c", format_exp!(exp!(var!("a"), Or, var!("b")), Coalesce, var!("c")));
+    assert_eq!("a ?? (b && c)", format_exp!(var!("a"), Coalesce, exp!(var!("b"), And, var!("c"))));
+    assert_eq!("(a ?? b) || c", format_exp!(exp!(var!("a"), Coalesce, var!("b")), Or, var!("c")));
+    assert_eq!("a && (b ?? c)", format_exp!(var!("a"), And, exp!(var!("b"), Coalesce, var!("c"))));
+    assert_eq!("a ?? b ? c : d", &format!("{}", Conditional(Box::new(exp!(var!("a"), Coalesce, var!("b"))),
+                                                           Box::new(var!("c")), Box::new(var!("d")))));
+    assert_eq!("a ?? b | c", format_exp!(var!("a"), Coalesce, exp!(var!("b"), BitOr, var!("c"))));
+}
+
+#[test]
+fn async_fns_and_generators() {
+    let boxed = |e| Box::new(e);
+
+    let g = Defun(FnKind::AsyncGenerator, Some(String::from("g")), vec![Pattern::from("x")],
+                  vec![BareExp(Yield(Some(boxed(var!("x"))))),
+                       BareExp(YieldAll(boxed(call!(var!("other"), vec![])))),
+                       BareExp(Yield(None))]);
+    assert_eq!("async function* g(x) {\n  yield x;\n  yield* other();\n  yield;\n}", &format!("{}", g));
+    assert_eq!("(async function() {\n});\n", &format!("{}", BareExp(Defun(FnKind::Async, None, vec![], vec![]))));
+    assert_eq!("async x => await x",
+               &format!("{}", Arrow(FnKind::Async, vec![Pattern::from("x")], ArrowBody::Exp(boxed(Await(boxed(var!("x"))))))));
+
+    assert_eq!("await (a + b)", &format!("{}", Await(boxed(exp!(var!("a"), Plus, var!("b"))))));
+    assert_eq!("await a + b", format_exp!(Await(boxed(var!("a"))), Plus, var!("b")));
+    assert_eq!("(await a) ** 2", format_exp!(Await(boxed(var!("a"))), Exponent, Float(2.0)));
+    assert_eq!("(yield a) + 1", format_exp!(Yield(Some(boxed(var!("a")))), Plus, Float(1.0)));
+    assert_eq!("(yield) || a", format_exp!(Yield(None), Or, var!("a")));
+    assert_eq!("yield (a, b)", &format!("{}", Yield(Some(boxed(Comma(boxed(var!("a")), boxed(var!("b"))))))));
+    assert_eq!("yield yield* a", &format!("{}", Yield(Some(boxed(YieldAll(boxed(var!("a"))))))));
+
+    assert_eq!("{\n  async m() {\n  },\n  *g() {\n  },\n  async *\"a-b\"() {\n  }\n}",
+        &format!("{}", Object(vec![Prop::Method(FnKind::Async, PropKey::Name(String::from("m")), vec![], vec![]),
+                                   Prop::Method(FnKind::Generator, PropKey::Name(String::from("g")), vec![], vec![]),
+                                   Prop::Method(FnKind::AsyncGenerator, PropKey::Name(String::from("a-b")), vec![], vec![])])));
+
+    // An async method named `constructor` is an ordinary method rather than the constructor
+    let class = ast::Class {
+        name: Some(String::from("A")),
+        extends: None,
+        members: vec![ClassMember { is_static: false, key: ClassKey::Name(String::from("constructor")),
+                                    kind: MemberKind::Method(FnKind::Async, vec![], vec![]) },
+                      ClassMember { is_static: true, key: ClassKey::Computed(boxed(var!("k"))),
+                                    kind: MemberKind::Method(FnKind::Generator, vec![], vec![]) }],
+    };
+    assert_eq!("class A {\n  async constructor() {\n  }\n  static *[k]() {\n  }\n}\n",
+               &format!("{}", ClassDecl(class.clone())));
+    assert!(class.constructor().is_none());
+}
+
+#[test]
+fn modules() {
+    let s = |s: &str| String::from(s);
+    let module = Program::Module(vec![
+        Import(vec![ImportSpec::Default(s("a")), ImportSpec::Named(s("b"), s("b")), ImportSpec::Named(s("c-d"), s("c"))],
+               s("./m.js")),
+        Import(vec![ImportSpec::Default(s("a")), ImportSpec::Namespace(s("ns"))], s("./ns.js")),
+        Import(vec![], s("./m.js")),
+        Export(Box::new(decl!(const "x" = Float(1.0)))),
+        ExportNames(vec![(s("a"), s("default")), (s("x"), s("x"))], None),
+        ExportNames(vec![(s("y"), s("z w"))], Some(s("./other.js"))),
+        seq!(ExportAll(None, s("./all.js")), ExportAll(Some(s("all")), s("./all.js"))),
+        ExportDefault(Defun(FnKind::Normal, None, vec![], vec![])),
+    ]);
+
+    assert_eq!("import a, {b, \"c-d\" as c} from \"./m.js\";\nimport a, * as ns from \"./ns.js\";\n\
+                import \"./m.js\";\nexport const x = 1;\nexport {a as default, x};\n\
+                export {y as \"z w\"} from \"./other.js\";\nexport * from \"./all.js\";\n\
+                export * as all from \"./all.js\";\nexport default function() {\n}\n",
+               &format!("{}", module));
+    assert_eq!(vec!["./m.js", "./ns.js", "./other.js", "./all.js"], module.requested_modules());
+    assert!(module.is_module());
+
+    // Only a function or class is printed as a declaration
+    assert_eq!("export default (function() {\n}.call(a));\n",
+               &format!("{}", ExportDefault(call!(instance_var!(Defun(FnKind::Normal, None, vec![], vec![]), "call"),
+                                                  vec![var!("a")]))));
+    assert_eq!("export default (a, b);\n", &format!("{}", ExportDefault(Comma(Box::new(var!("a")), Box::new(var!("b"))))));
+    assert_eq!("export default {};\n", &format!("{}", ExportDefault(obj! {})));
+    assert_eq!("export default class {}\n",
+               &format!("{}", ExportDefault(Class(Box::new(ast::Class { name: None, extends: None, members: vec![] })))));
+}
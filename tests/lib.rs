@@ -1,4 +1,12 @@
 #[macro_use] extern crate jsrs_common;
+#[macro_use] extern crate matches;
+extern crate serde_json;
 
+mod codegen;
+mod estree;
 mod format;
+mod parser;
 mod precedence;
+mod source_map;
+mod span;
+mod visit;
@@ -0,0 +1,422 @@
+use jsrs_common::ast::BinOp::*;
+use jsrs_common::ast::Exp::*;
+use jsrs_common::ast::{self, ArrowBody, AssignOp, ClassKey, ClassMember, DeclKind, FnKind,
+                       ImportSpec, MemberKind, Pattern, Program, Prop, PropKey, Stmt,
+                       TemplatePart};
+use jsrs_common::ast::Stmt::*;
+use jsrs_common::codegen::{CodeGen, CodeGenOptions};
+use jsrs_common::js_error::JsError;
+use jsrs_common::parser::*;
+use jsrs_common::span::Span;
+
+fn module(source: &str) -> Program {
+    let options = ParseOptions { module: true, ..ParseOptions::default() };
+    Parser::new(source, options).parse_program().unwrap()
+}
+
+fn error(source: &str) -> String {
+    match parse_Stmt(source) {
+        Err(JsError::SyntaxError(msg)) => msg,
+        result => panic!("expected a syntax error from {:?}, got {:?}", source, result),
+    }
+}
+
+fn recover(source: &str) -> (Program, Vec<String>) {
+    let (program, errors) = Parser::new(source, ParseOptions::default()).parse_program_recovering();
+
+    let errors = errors.into_iter()
+        .map(|e| match e {
+            JsError::SyntaxError(msg) => msg,
+            e => panic!("expected a syntax error from {:?}, got {:?}", source, e),
+        })
+        .collect();
+
+    (program, errors)
+}
+
+// Printing the AST and parsing the result should give back the same AST.
+fn round_trip_with(source: &str, options: ParseOptions) {
+    let program = Parser::new(source, options.clone()).parse_program().unwrap();
+    let printed = format!("{}", program);
+    assert_eq!(program, Parser::new(&printed, options).parse_program().unwrap(), "{}", printed);
+}
+
+fn round_trip(source: &str) {
+    round_trip_with(source, ParseOptions::default());
+}
+
+fn minified_round_trip(source: &str) {
+    let program = Parser::new(source, ParseOptions::default()).parse_program().unwrap();
+    let mut gen = CodeGen::new(CodeGenOptions::minified());
+    gen.stmts(program.stmts());
+    let printed = gen.finish();
+    assert_eq!(program, Parser::new(&printed, ParseOptions::default()).parse_program().unwrap(),
+               "{}", printed);
+}
+
+#[test]
+fn exps() {
+    assert_eq!(exp!(var!("a"), Plus, exp!(var!("b"), Star, Float(2.0))), parse_exp!("a + b * 2"));
+    assert_eq!(exp!(exp!(var!("a"), Minus, var!("b")), Minus, var!("c")), parse_exp!("a - b - c"));
+    assert_eq!(exp!(var!("a"), Exponent, exp!(var!("b"), Exponent, var!("c"))),
+               parse_exp!("a ** b ** c"));
+    assert_eq!(exp!(Neg(Box::new(var!("a"))), Exponent, Float(2.0)), parse_exp!("(-a) ** 2"));
+    assert_eq!(exp!(exp!(var!("a"), Or, var!("b")), Coalesce, var!("c")),
+               parse_exp!("(a || b) ?? c"));
+    assert_eq!(Conditional(Box::new(var!("a")), Box::new(exp!(var!("b"), In, var!("c"))),
+                           Box::new(Float(255.0))),
+               parse_exp!("a ? b in c : 0xff"));
+    assert_eq!(Comma(Box::new(Comma(Box::new(var!("a")), Box::new(var!("b")))),
+                     Box::new(var!("undefined"))),
+               parse_exp!("a, b, undefined"));
+    assert_eq!(call!(instance_var!(This, "#f"), vec![Spread(Box::new(var!("xs")))]),
+               parse_exp!("this.#f(...xs)"));
+    assert_eq!(NewObject(Box::new(instance_var!(var!("a"), "B")), vec![]), parse_exp!("new a.B"));
+    assert_eq!(post_inc!(KeyAccessor(Box::new(var!("a")), Box::new(Str(String::from("b"))))),
+               parse_exp!("a['b']++"));
+    assert_eq!(Chain(Box::new(instance_var!(Optional(Box::new(var!("a"))), "b"))),
+               parse_exp!("a?.b"));
+    assert_eq!(instance_var!(Chain(Box::new(call!(Optional(Box::new(var!("f"))), vec![]))), "c"),
+               parse_exp!("(f?.()).c"));
+    assert_eq!(TypeOf(Box::new(LogNot(Box::new(Void(Box::new(Float(0.5))))))),
+               parse_exp!("typeof !void .5"));
+    // These are only names, which can be shadowed
+    assert_eq!(Defun(FnKind::Normal, None, vec![Pattern::from("undefined")],
+                     vec![Ret(exp!(var!("NaN"), Plus, var!("Infinity")))]),
+               parse_exp!("function(undefined) { return NaN + Infinity; }"));
+    assert_eq!(Object(vec![Prop::Value(String::from("a"), Box::new(Float(1.0))),
+                           Prop::Value(String::from("16"), Box::new(Null)),
+                           Prop::Shorthand(String::from("b")),
+                           Prop::Computed(Box::new(var!("c")), Box::new(Bool(true))),
+                           Prop::Get(PropKey::Name(String::from("get")), vec![]),
+                           Prop::Method(FnKind::Async, PropKey::Name(String::from("m")), vec![], vec![]),
+                           Prop::Spread(Box::new(var!("d")))]),
+               parse_exp!("{a: 1, 0x10: null, b, [c]: true, get get() {}, async m() {}, ...d}"));
+    let iterator = instance_var!(var!("Symbol"), "iterator");
+    assert_eq!(Object(vec![Prop::Method(FnKind::Generator, PropKey::Computed(Box::new(iterator)),
+                                        vec![], vec![]),
+                           Prop::Get(PropKey::Computed(Box::new(var!("k"))), vec![]),
+                           Prop::Set(PropKey::Computed(Box::new(var!("k"))), Pattern::from("v"),
+                                     vec![])]),
+               parse_exp!("{*[Symbol.iterator]() {}, get [k]() {}, set [k](v) {}}"));
+}
+
+#[test]
+fn regexes_and_templates() {
+    assert_eq!(exp!(exp!(var!("a"), Slash, var!("b")), Slash, var!("c")), parse_exp!("a / b / c"));
+    assert_eq!(call!(instance_var!(RegExp(String::from("a\\/[/]"), String::from("gi")), "test"),
+                     vec![var!("s")]),
+               parse_exp!("/a\\/[/]/gi.test(s)"));
+    assert_eq!(Template(ast::Template {
+                   quasis: vec![TemplatePart::new("a "),
+                                TemplatePart {
+                                    cooked: Some(String::from("\n")),
+                                    raw: String::from("\\n"),
+                                }],
+                   exps: vec![Box::new(Object(vec![]))],
+               }),
+               parse_exp!("`a ${{}}\\n`"));
+    assert_eq!(TaggedTemplate(Box::new(var!("tag")), ast::Template {
+                   quasis: vec![TemplatePart { cooked: None, raw: String::from("\\unicode") }],
+                   exps: vec![],
+               }),
+               parse_exp!("tag`\\unicode`"));
+}
+
+#[test]
+fn stmts() {
+    assert_eq!(seq!(assign!(var!("a"), Float(1.0)),
+                    seq!(BareExp(AssignExp(AssignOp::Plus, Box::new(Pattern::from(var!("a"))),
+                                           Box::new(Float(2.0)))),
+                         Decl(DeclKind::Let, vec![(Pattern::from("b"), None),
+                                                  (Pattern::from("c"), Some(Float(3.0)))]))),
+               parse_stmt!("a = 1; a += 2\nlet b, c = 3"));
+    assert_eq!(FnDecl(FnKind::Generator, String::from("f"), vec![Pattern::from("x")],
+                      vec![BareExp(Yield(Some(Box::new(var!("x")))))]),
+               parse_stmt!("function* f(x) { yield x }"));
+    // A declaration is hoisted, so it's printed back as one
+    let program = Parser::new("f(); function f() { return 1; }", ParseOptions::default());
+    assert_eq!("f();\nfunction f() {\n  return 1;\n}\n",
+               format!("{}", program.parse_program().unwrap()));
+    assert_eq!(For(Box::new(Empty), Bool(true), Undefined, vec![Break(None)]),
+               parse_stmt!("for (;;) break;"));
+    assert_eq!(For(Box::new(Decl(DeclKind::Var, vec![(Pattern::from("i"), Some(Float(0.0)))])),
+                   exp!(var!("i"), Lt, var!("n")), post_inc!(var!("i")), vec![Continue(None)]),
+               parse_stmt!("for (var i = 0; i < n; i++) { continue }"));
+    assert_eq!(ForIn(Box::new(BareExp(instance_var!(var!("o"), "k"))), var!("o"), vec![]),
+               parse_stmt!("for (o.k in o);"));
+    let x = Decl(DeclKind::Const, vec![(Pattern::from("x"), None)]);
+    assert_eq!(Labeled(String::from("outer"),
+                       Box::new(ForOf(Box::new(x), var!("xs"),
+                                      vec![Continue(Some(String::from("outer")))]))),
+               parse_stmt!("outer: for (const x of xs) continue outer;"));
+    assert_eq!(If(var!("a"), vec![BareExp(var!("b"))], vec![If(var!("c"), vec![], vec![])]),
+               parse_stmt!("if (a) b; else if (c) {}"));
+    assert_eq!(seq!(DoWhile(vec![], var!("a")), BareExp(var!("b"))),
+               parse_stmt!("do {} while (a) b"));
+    assert_eq!(Try(vec![], None, vec![Throw(Box::new(var!("e")))], vec![]),
+               parse_stmt!("try {} catch { throw e }"));
+    assert_eq!(Switch(var!("x"), vec![(Some(Float(1.0)), vec![]), (None, vec![Break(None)])]),
+               parse_stmt!("switch (x) { case 1: default: break; }"));
+    assert_eq!(Empty, parse_stmt!("// nothing\n"));
+}
+
+#[test]
+fn automatic_semicolons() {
+    assert_eq!(seq!(BareExp(var!("a")), BareExp(pre_inc!(var!("b")))), parse_stmt!("a\n++b"));
+    assert_eq!(BareExp(call!(var!("a"), vec![var!("b")])), parse_stmt!("a\n(b)"));
+
+    let f = Defun(FnKind::Normal, None, vec![], vec![Ret(Undefined), BareExp(var!("x"))]);
+    assert_eq!(BareExp(f), parse_stmt!("(function() { return\nx })"));
+    assert_eq!("Unexpected identifier 'b' (at 1:3)", error("a b"));
+}
+
+#[test]
+fn patterns_and_arrows() {
+    assert_eq!(Arrow(FnKind::Normal, vec![Pattern::from("x")],
+                     ArrowBody::Exp(Box::new(exp!(var!("x"), Plus, Float(1.0))))),
+               parse_exp!("x => x + 1"));
+    assert_eq!(Arrow(FnKind::Async,
+                     vec![Pattern::Object(vec![(String::from("a"),
+                                                Pattern::Default(Box::new(Pattern::from("a")),
+                                                                 Float(1.0)))],
+                                          None),
+                          Pattern::Rest(Box::new(Pattern::from("b")))],
+                     ArrowBody::Block(vec![])),
+               parse_exp!("async ({a = 1}, ...b) => {}"));
+    assert_eq!(call!(var!("async"), vec![var!("a")]), parse_exp!("async(a)"));
+    let rest = Pattern::Rest(Box::new(Pattern::from(var!("b"))));
+    assert_eq!(Stmt::Assign(Pattern::Array(vec![Some(Pattern::from(var!("a"))), None, Some(rest)]),
+                            var!("c")),
+               parse_stmt!("[a, , ...b] = c"));
+    let x = Pattern::Default(Box::new(Pattern::from(var!("x"))), Float(0.0));
+    assert_eq!(Stmt::Assign(Pattern::Object(vec![(String::from("x"), x)], None), var!("o")),
+               parse_stmt!("({x = 0} = o)"));
+    assert_eq!(Decl(DeclKind::Const,
+                    vec![(Pattern::Object(vec![(String::from("a"),
+                                                Pattern::Array(vec![Some(Pattern::from("b"))]))],
+                                          Some(Box::new(Pattern::from("c")))),
+                          Some(var!("o")))]),
+               parse_stmt!("const {a: [b], ...c} = o"));
+}
+
+#[test]
+fn classes() {
+    let class = ast::Class {
+        name: Some(String::from("A")),
+        extends: Some(Box::new(var!("B"))),
+        members: vec![
+            ClassMember {
+                is_static: false,
+                key: ClassKey::Private(String::from("n")),
+                kind: MemberKind::Field(Some(Float(0.0))),
+            },
+            ClassMember {
+                is_static: false,
+                key: ClassKey::Name(String::from("constructor")),
+                kind: MemberKind::Method(FnKind::Normal, vec![],
+                                         vec![BareExp(call!(Super, vec![]))]),
+            },
+            ClassMember {
+                is_static: true,
+                key: ClassKey::Computed(Box::new(var!("k"))),
+                kind: MemberKind::Set(Pattern::from("v"), vec![]),
+            },
+        ],
+    };
+
+    assert_eq!(ClassDecl(class),
+               parse_stmt!("class A extends B {\n  #n = 0\n  constructor() { super() };\n  \
+                            static set [k](v) {}\n}"));
+}
+
+#[test]
+fn modules() {
+    let program = module("import a, {b as c, \"d e\" as f} from \"m\";\nexport default class {}\n\
+                          export {a as \"x y\"};\nexport * as ns from \"n\";\nawait a;");
+
+    assert_eq!(Program::Module(vec![
+        Import(vec![ImportSpec::Default(String::from("a")),
+                    ImportSpec::Named(String::from("b"), String::from("c")),
+                    ImportSpec::Named(String::from("d e"), String::from("f"))],
+               String::from("m")),
+        ExportDefault(Class(Box::new(ast::Class { name: None, extends: None, members: vec![] }))),
+        ExportNames(vec![(String::from("a"), String::from("x y"))], None),
+        ExportAll(Some(String::from("ns")), String::from("n")),
+        BareExp(Await(Box::new(var!("a")))),
+    ]), program);
+    assert_eq!(vec!["m", "n"], program.requested_modules());
+
+    assert_eq!("Cannot use import statement outside a module (at 1:1)", error("import a from 'm'"));
+}
+
+#[test]
+fn spans() {
+    let options = ParseOptions { spans: true, file: 2, ..ParseOptions::default() };
+    let program = Parser::new("let x =\n  a + b;", options).parse_program().unwrap();
+
+    assert_eq!(Some(&Span::new(2, 0, 1, 1, 16)), program.stmts()[0].span());
+
+    match *program.stmts()[0].node() {
+        Decl(_, ref decls) => {
+            let init = decls[0].1.as_ref().unwrap();
+            assert_eq!(Some(&Span::new(2, 10, 2, 3, 5)), init.span());
+            assert!(matches!(*init.node(), BinExp(_, Plus, _)));
+        }
+        ref s => panic!("expected a declaration, got {:?}", s),
+    }
+}
+
+#[test]
+fn errors() {
+    assert_eq!("Unexpected end of input (at 1:7)", error("f(a, b"));
+    assert_eq!("Unexpected token ')' (at 2:5)", error("x;\nf(a))"));
+    assert_eq!("Invalid left-hand side in assignment (at 1:1)", error("a + b = c"));
+    assert_eq!("Invalid shorthand property initializer (at 1:5)", error("({a = 1})"));
+    assert_eq!("Unexpected token '??' (at 1:8)", error("a || b ?? c"));
+    assert_eq!("Unary operator used immediately before exponentiation expression (at 1:4)",
+               error("-a ** 2"));
+    assert_eq!("Illegal return statement (at 1:1)", error("return 1"));
+    assert_eq!("Illegal break statement (at 1:8)", error("if (a) break;"));
+    assert_eq!("Undefined label 'b' (at 1:16)", error("a: while (x) { continue b }"));
+    assert_eq!("Missing initializer in const declaration (at 1:7)", error("const a;"));
+    assert_eq!("Unexpected token 'if' (at 1:5)", error("var if = 1"));
+    assert_eq!("Unexpected reserved word 'yield' (at 1:21)", error("function* g() { var yield; }"));
+    assert_eq!("Unterminated regular expression (at 2:1)", error("a;\n/b\n/"));
+    assert_eq!("Invalid escape sequence in template (at 1:1)", error("`\\u{`"));
+}
+
+#[test]
+fn recovery() {
+    let (program, errors) = recover("var a = ;\nf(1, 2;\nlet b = 2;");
+    assert_eq!(Program::Script(vec![decl!("a", ast::Exp::Error), Stmt::Error,
+                                    decl!(let "b" = Float(2.0))]),
+               program);
+    assert_eq!(vec!["Unexpected token ';' (at 1:9)", "Unexpected token ';' (at 2:7)"], errors);
+    assert_eq!("var a = /* error */;\n/* error */\nlet b = 2;\n", format!("{}", program));
+
+    // Characters that can't be read are left out, and missing semicolons are inserted
+    let (program, errors) = recover("a = 1 @ 2; b c");
+    assert_eq!(Program::Script(vec![assign!(var!("a"), Float(1.0)), BareExp(Float(2.0)),
+                                    BareExp(var!("b")), BareExp(var!("c"))]),
+               program);
+    assert_eq!(vec!["Invalid or unexpected token (at 1:7)", "Unexpected number (at 1:9)",
+                    "Unexpected identifier 'c' (at 1:14)"],
+               errors);
+
+    // A function that's never closed keeps what's in it
+    let (program, errors) = recover("function f() {\n  x = (1 + ;\n  return 2");
+    let f = FnDecl(FnKind::Normal, String::from("f"), vec![], vec![Stmt::Error, Ret(Float(2.0))]);
+    assert_eq!(Program::Script(vec![f]), program);
+    assert_eq!(vec!["Unexpected token ';' (at 2:12)", "Unexpected end of input (at 3:11)"],
+               errors);
+
+    // Errors that don't change how the rest is read leave the statement in the AST
+    let (program, errors) = recover("return 1;\nconst c;\nswitch (x) { default: default: }");
+    assert_eq!(Program::Script(vec![Ret(Float(1.0)), decl!(const "c"),
+                                    Switch(var!("x"), vec![(None, vec![]), (None, vec![])])]),
+               program);
+    assert_eq!(vec!["Illegal return statement (at 1:1)",
+                    "Missing initializer in const declaration (at 2:7)",
+                    "More than one default clause in switch statement (at 3:23)"],
+               errors);
+
+    // Stray closing brackets are skipped
+    let (program, errors) = recover("}) a; ]");
+    assert_eq!(Program::Script(vec![Stmt::Error, Stmt::Error, BareExp(var!("a")), Stmt::Error]),
+               program);
+    assert_eq!(vec!["Unexpected token '}' (at 1:1)", "Unexpected token ')' (at 1:2)",
+                    "Unexpected token ']' (at 1:7)"],
+               errors);
+
+    let (program, errors) = recover("if (a) b(); else c();");
+    assert_eq!(parse_Stmt("if (a) b(); else c();").unwrap(), program.stmts()[0]);
+    assert!(errors.is_empty());
+}
+
+#[test]
+fn comments() {
+    let options = ParseOptions { comments: true, ..ParseOptions::default() };
+    let comment = |text: &str, block, lines_before| {
+        ast::Comment { text: String::from(text), block: block, lines_before: lines_before }
+    };
+
+    let source = "/** Doc. */\nlet a = /* one */ 1; // a\n\nb;\n{\n  // empty\n}\n";
+    let leading = ast::Comments { leading: vec![comment("* Doc. ", true, 0)],
+                                  trailing: vec![comment(" a", false, 0)],
+                                  lines_before: 1 };
+    let one = ast::Comments { leading: vec![comment(" one ", true, 0)], ..Default::default() };
+    let blank_line = ast::Comments { lines_before: 2, ..Default::default() };
+    let dangling = ast::Comments { leading: vec![comment(" empty", false, 1)], ..Default::default() };
+
+    assert_eq!(Program::Script(vec![
+                   Decl(DeclKind::Let, vec![(Pattern::from("a"), Some(Float(1.0).with_comments(one)))])
+                       .with_comments(leading),
+                   BareExp(var!("b")).with_comments(blank_line),
+                   Block(vec![Empty.with_comments(dangling)]),
+               ]),
+               Parser::new(source, options.clone()).parse_program().unwrap());
+
+    // The comments and blank lines are printed back where they were
+    let source = "/**\n * Adds.\n */\nvar add = function add(a, b) {\n  // Sum\n  return a + b; // done\n\
+                  };\n\nif (a) {\n  /* empty */\n} else {\n  b(/* c */ c);\n}\n// end\n";
+    let program = Parser::new(source, options.clone()).parse_program().unwrap();
+    assert_eq!(source, format!("{}", program));
+
+    // Without the option, they're skipped as before
+    assert_eq!(Parser::new(source, ParseOptions::default()).parse_program().unwrap(),
+               Parser::new("var add = function add(a, b) { return a + b; }; if (a) {} else { b(c); }",
+                           ParseOptions::default()).parse_program().unwrap());
+}
+
+#[test]
+fn round_trips() {
+    round_trip("var a = 1, b = [1, , 3], c = {x: 1, 'y z': [2], [k]: f(...g)};");
+    round_trip("o = {async *[Symbol.asyncIterator]() {}, get [a + b]() { return 1; }, set [k](v) {}};");
+    round_trip("function* f(a, {b, c: [d] = []}, ...e) { yield* g(); return a ?? (b || c); }");
+    round_trip("async function f() { for (const x of await y) x; for (let [k, v] of o) await k; }");
+    round_trip("a: for (var i = 0, n = a.length; i < n; ++i) { if (a[i] in o) continue a; }");
+    round_trip("class A extends (B, C) { static #x = 1; get y() { return this.#x; } *[k]() {} }");
+    round_trip("x = y => ({...y, z: `a${y.b}c`}); x?.y?.[z]?.(w); new (f())(); new new A()();");
+    round_trip("try { throw new Error('a'); } catch (e) { (-1) ** e; } finally { delete a.b; }");
+    round_trip("switch (a) { case 1: case 2: b(); break; default: c = d = e; }");
+    round_trip("do x--; while (x > 0 && !/[a-z]+/i.test(s))");
+    round_trip("(function() {})(); (class {}); ({}).a; (async () => {})``;");
+    round_trip("f = (a, b) => { return a + b; }; g = x => { return; }; h = () => ({});");
+    round_trip("n = [1e300, 5e-324, 1e21, 1e-7, 0.000001, 123.456, 1e21.toFixed];");
+}
+
+#[test]
+fn minified_round_trips() {
+    minified_round_trip("a = /a/ instanceof RegExp; b = /b/g in c; d = /d*/ instanceof e;");
+    minified_round_trip("var x = a / b / c, y = a - -b + +c; typeof x instanceof y;");
+}
+
+#[test]
+fn stmt_round_trips() {
+    round_trip("if (a) b(); else if (c) { d; } else e; if (a) { if (b) c; } else d;");
+    round_trip("while (i--) { if (i % 2) continue; else break; } do {} while (0);");
+    round_trip("for (k in o) ; for (var k in o) {} for (a.b in c, d) {} for (;;) break;");
+    round_trip("for (const [k, v] of o) {} for (x of y) {} for (let i = 0, j; ; ) {}");
+    round_trip("l: { m: while (a) { if (b) break l; continue m; } } n: o: ;");
+    round_trip("try { a(); } catch { b(); } try {} finally {} try {} catch ({message, s: [t]}) {}");
+    round_trip("let x; const [y] = z, {w} = v; var u = 1; ; {} { let a = 1; { a; } }");
+    round_trip("throw a, b; function f() { return; } function* g() { return a, b; }");
+    round_trip("class A {} class B extends A { constructor() { super(); } }");
+    round_trip("switch (a) {} switch (b) { default: }");
+    round_trip_with("// a\nx;\n\n/* b */ y; // c\n",
+                    ParseOptions { comments: true, ..ParseOptions::default() });
+
+    let module = ParseOptions { module: true, ..ParseOptions::default() };
+    round_trip_with("import 'm'; import a from 'm'; import * as ns from 'm'; \
+                     import b, {c, d as e, default as f} from 'm'; import g, * as h from 'm';",
+                    module.clone());
+    round_trip_with("export var a = 1; export let b, c; export function f() {} \
+                     export async function* g() {} export class C {}",
+                    module.clone());
+    round_trip_with("export default a + b; export * from 'm'; export * as ns from 'm'; \
+                     export {a, b as c}; export {d as default, e} from 'm'; export {};",
+                    module.clone());
+    round_trip_with("export default function() {}", module.clone());
+    round_trip_with("export default class {}", module);
+}
@@ -179,3 +179,11 @@ fn or() {
     assert!(Or.precedence() < Le.precedence());
     assert!(Or.precedence() < And.precedence());
 }
+
+#[test]
+fn in_op() {
+    assert!(In.precedence() == InstanceOf.precedence());
+    assert!(In.precedence() == Lt.precedence());
+    assert!(In.precedence() < ShiftLeft.precedence());
+    assert!(In.precedence() > Eql.precedence());
+}
@@ -0,0 +1,143 @@
+use jsrs_common::ast::BinOp::*;
+use jsrs_common::ast::Exp::*;
+use jsrs_common::ast::Program;
+use jsrs_common::ast::Stmt;
+use jsrs_common::ast::Stmt::*;
+use jsrs_common::codegen::{CodeGen, CodeGenOptions};
+use jsrs_common::parser::{ParseOptions, Parser};
+use jsrs_common::source_map::{Mapping, SourceMap};
+use jsrs_common::span::Span;
+
+fn gen(options: CodeGenOptions, stmts: &[Stmt]) -> (String, SourceMap) {
+    let mut gen = CodeGen::new(options);
+    gen.stmts(stmts);
+    gen.finish_with_source_map()
+}
+
+fn at(line: usize, column: usize) -> Span {
+    Span::new(0, 0, line, column, 1)
+}
+
+// x=a+b;
+// while(c)x++
+fn program() -> Vec<Stmt> {
+    let sum = exp!(var!("a").with_span(at(1, 3)), Plus, var!("b").with_span(at(1, 5)));
+
+    vec![
+        assign!(var!("x").with_span(at(1, 1)), sum.with_span(at(1, 3))).with_span(at(1, 1)),
+        While(var!("c").with_span(at(2, 7)),
+              vec![BareExp(PostInc(Box::new(var!("x").with_span(at(2, 9))))).with_span(at(2, 9))])
+            .with_span(at(2, 1)),
+    ]
+}
+
+#[test]
+fn token_mappings() {
+    let (code, map) = gen(CodeGenOptions::default(), &program());
+    assert_eq!("x = a + b;\nwhile (c) {\n  x++;\n}\n", code);
+
+    let m = |line, column, orig_line, orig_column| {
+        Mapping { generated_line: line, generated_column: column,
+                  original: Some((0, orig_line, orig_column)) }
+    };
+
+    assert_eq!(&[m(0, 0, 0, 0), m(0, 4, 0, 2), m(0, 8, 0, 4), m(0, 9, 0, 0),
+                 m(1, 0, 1, 0), m(1, 7, 1, 6), m(1, 8, 1, 0),
+                 m(2, 2, 1, 8),
+                 m(3, 0, 1, 0)],
+               map.mappings());
+}
+
+#[test]
+fn minified_mappings() {
+    let (code, map) = gen(CodeGenOptions::minified(), &program());
+    assert_eq!("x=a+b;while(c){x++;}", code);
+    assert_eq!("AAAA,EAAE,EAAE,CAAJ,CACA,MAAM,CAAN,EAAQ,IAAR", map.encode_mappings());
+}
+
+#[test]
+fn unspanned_code() {
+    let stmts = vec![BareExp(call!(var!("f"), vec![var!("a").with_span(at(3, 4))])),
+                     BareExp(var!("g"))];
+    let (code, map) = gen(CodeGenOptions::default(), &stmts);
+
+    assert_eq!("f(a);\ng;\n", code);
+    assert_eq!(&[Mapping { generated_line: 0, generated_column: 2, original: Some((0, 2, 3)) },
+                 Mapping { generated_line: 0, generated_column: 3, original: None },
+                 Mapping { generated_line: 1, generated_column: 0, original: None }],
+               map.mappings());
+}
+
+#[test]
+fn utf16_columns() {
+    // U+10400 is a letter outside the BMP, so it takes two UTF-16 code units
+    let stmts = vec![BareExp(var!("\u{10400}")), BareExp(var!("x").with_span(at(2, 1)))];
+    let (code, map) = gen(CodeGenOptions::minified(), &stmts);
+
+    assert_eq!("\u{10400};x;", code);
+    assert_eq!(3, map.mappings()[0].generated_column);
+}
+
+#[test]
+fn unsourced_positions() {
+    // Without the source text, tokens go to the start of their node, at the column the lexer
+    // counted in UTF-16 code units
+    let source = "s='\u{10400}'+ d";
+    let options = ParseOptions { spans: true, ..ParseOptions::default() };
+    let stmts = match Parser::new(source, options).parse_program().unwrap() {
+        Program::Script(stmts) | Program::Module(stmts) => stmts,
+    };
+
+    let (code, map) = gen(CodeGenOptions::minified(), &stmts);
+    assert_eq!("s=\"\\uD801\\uDC00\"+d;", code);
+    assert_eq!(Some((0, 0, 8)), map.mappings().iter().find(|m| m.generated_column == 17)
+                                    .unwrap().original);
+}
+
+#[test]
+fn json() {
+    let e = exp!(var!("a"), Plus, var!("b").with_span(Span::new(1, 40, 3, 2, 1)));
+    let mut gen = CodeGen::new(CodeGenOptions::minified());
+    gen.exp(&e);
+
+    let (_, mut map) = gen.finish_with_source_map();
+    map.file = Some(String::from("out.js"));
+    map.sources = vec![String::from("a.js")];
+    map.sources_content = vec![Some(String::from("a\n"))];
+
+    assert_eq!("{\"file\":\"out.js\",\"mappings\":\"ECEC\",\"names\":[],\"sources\":[\"a.js\",\"\"],\
+                \"sourcesContent\":[\"a\\n\"],\"version\":3}",
+               map.to_json());
+}
+
+#[test]
+fn source_positions() {
+    // U+10400 takes two UTF-16 code units, which the columns after it on its line count
+    let source = "x=a+b;\nwhile(c)x++\ns='\u{10400}'+ d";
+    let options = ParseOptions { spans: true, ..ParseOptions::default() };
+    let stmts = match Parser::new(source, options).parse_program().unwrap() {
+        Program::Script(stmts) | Program::Module(stmts) => stmts,
+    };
+
+    let mut gen = CodeGen::new(CodeGenOptions::default())
+        .with_sources(vec![String::from(source)]);
+    gen.stmts(&stmts);
+    let (code, map) = gen.finish_with_source_map();
+    assert_eq!("x = a + b;\nwhile (c) {\n  x++;\n}\ns = \"\\uD801\\uDC00\" + d;\n", code);
+
+    let m = |line, column, orig_line, orig_column| {
+        Mapping { generated_line: line, generated_column: column,
+                  original: Some((0, orig_line, orig_column)) }
+    };
+
+    // Tokens that aren't in the source, like the braces of the loop, go to the start of their
+    // node
+    assert_eq!(&[m(0, 0, 0, 0), m(0, 2, 0, 1), m(0, 4, 0, 2), m(0, 6, 0, 3), m(0, 8, 0, 4),
+                 m(0, 9, 0, 5),
+                 m(1, 0, 1, 0), m(1, 6, 1, 5), m(1, 7, 1, 6), m(1, 8, 1, 7), m(1, 10, 1, 0),
+                 m(2, 2, 1, 8), m(2, 3, 1, 9), m(2, 5, 1, 8),
+                 m(3, 0, 1, 0),
+                 m(4, 0, 2, 0), m(4, 2, 2, 1), m(4, 4, 2, 2), m(4, 19, 2, 6), m(4, 21, 2, 8),
+                 m(4, 22, 2, 0)],
+               map.mappings());
+}
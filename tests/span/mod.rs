@@ -0,0 +1,51 @@
+use jsrs_common::ast::BinOp::*;
+use jsrs_common::ast::Exp;
+use jsrs_common::ast::Exp::*;
+use jsrs_common::ast::Stmt::*;
+use jsrs_common::js_error::JsError;
+use jsrs_common::span::Span;
+
+#[test]
+fn exp_span() {
+    let span = Span::new(0, 8, 1, 9, 5);
+    let e = exp!(var!("x"), Plus, Float(1.0)).with_span(span);
+
+    assert_eq!(Some(&span), e.span());
+    assert!(var!("x").span().is_none());
+    assert!(matches!(*e.node(), BinExp(..)));
+}
+
+#[test]
+fn respan_replaces() {
+    let first = Span::new(0, 0, 1, 1, 1);
+    let second = Span::new(0, 4, 1, 5, 1);
+    let e = var!("x").with_span(first).with_span(second);
+
+    assert_eq!(Some(&second), e.span());
+    assert!(matches!(e, Exp::Spanned(_, ref inner) if inner.span().is_none()));
+}
+
+#[test]
+fn stmt_span() {
+    let span = Span::new(2, 12, 2, 1, 7);
+    let s = BareExp(var!("x")).with_span(span);
+
+    assert_eq!(Some(&span), s.span());
+    assert_eq!(2, s.span().unwrap().file);
+    assert!(matches!(*s.node(), BareExp(_)));
+}
+
+#[test]
+fn spans_do_not_print() {
+    let span = Span::new(0, 0, 1, 1, 9);
+    let e = exp!(exp!(var!("a"), Plus, var!("b")).with_span(span), Star, var!("c"));
+
+    assert_eq!("(a + b) * c", &format!("{}", e));
+    assert_eq!("a + b;\n", &format!("{}", BareExp(exp!(var!("a"), Plus, var!("b"))).with_span(span)));
+}
+
+#[test]
+fn error_location() {
+    let err = JsError::undefined("x").at(&Span::new(0, 40, 3, 14, 1));
+    assert_eq!("ReferenceError: x is not defined (at 3:14)", &format!("{}", err));
+}
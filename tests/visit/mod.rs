@@ -0,0 +1,99 @@
+use jsrs_common::ast::BinOp::*;
+use jsrs_common::ast::Exp::*;
+use jsrs_common::ast::Stmt::*;
+use jsrs_common::ast::{Exp, Pattern, Stmt};
+use jsrs_common::visit::{self, Fold, Visitor, VisitorMut};
+
+struct VarCounter(Vec<String>);
+
+impl Visitor for VarCounter {
+    fn visit_exp(&mut self, e: &Exp) {
+        if let Var(ref name) = *e {
+            self.0.push(name.clone());
+        }
+
+        visit::walk_exp(self, e)
+    }
+}
+
+struct Renamer;
+
+impl VisitorMut for Renamer {
+    fn visit_exp_mut(&mut self, e: &mut Exp) {
+        if let Var(ref mut name) = *e {
+            name.push_str("_");
+        }
+
+        visit::walk_exp_mut(self, e)
+    }
+}
+
+struct ConstFolder;
+
+impl Fold for ConstFolder {
+    fn fold_exp(&mut self, e: Exp) -> Exp {
+        match visit::noop_fold_exp(self, e) {
+            BinExp(e1, Plus, e2) => match (*e1, *e2) {
+                (Float(x), Float(y)) => Float(x + y),
+                (e1, e2) => exp!(e1, Plus, e2),
+            },
+            e => e,
+        }
+    }
+}
+
+fn sample() -> Stmt {
+    let body = vec![
+        If(var!("a"), vec![BareExp(call!(var!("f"), vec![var!("b")]))], vec![Ret(var!("c"))]),
+        Try(vec![Throw(Box::new(var!("d")))], Some(Pattern::from("e")), vec![BareExp(var!("e"))],
+            vec![BareExp(obj! { "k" => var!("g") })]),
+        For(Box::new(decl!("i", Float(0.0))), exp!(var!("i"), Lt, var!("n")), post_inc!(var!("i")),
+            vec![Continue(None)]),
+    ];
+
+    BareExp(defun! { "outer", ("x") seq!(body[0].clone(), seq!(body[1].clone(), body[2].clone())) })
+}
+
+#[test]
+fn visitor_reaches_every_node() {
+    let mut counter = VarCounter(Vec::new());
+    counter.visit_stmt(&sample());
+
+    assert_eq!(vec!["a", "f", "b", "c", "d", "e", "g", "i", "n", "i"], counter.0);
+}
+
+#[test]
+fn visitor_mut_rewrites_in_place() {
+    let mut s = sample();
+    Renamer.visit_stmt_mut(&mut s);
+
+    let mut counter = VarCounter(Vec::new());
+    counter.visit_stmt(&s);
+    assert!(counter.0.iter().all(|name| name.ends_with("_")));
+    assert_eq!(10, counter.0.len());
+}
+
+#[test]
+fn fold_rebuilds_tree() {
+    let e = call!(var!("f"), vec![exp!(exp!(Float(1.0), Plus, Float(2.0)), Plus, Float(3.5))]);
+    assert_eq!("f(6.5)", &format!("{}", ConstFolder.fold_exp(e)));
+
+    let s = Ret(exp!(var!("x"), Plus, exp!(Float(1.0), Plus, Float(1.0))));
+    assert_eq!("return x + 2;\n", &format!("{}", ConstFolder.fold_stmt(s)));
+}
+
+#[test]
+fn visitor_reaches_patterns() {
+    let pattern = |last| {
+        Pattern::Array(vec![Some(Pattern::Default(Box::new(Pattern::from("a")), var!("b"))),
+                            Some(Pattern::Rest(Box::new(last)))])
+    };
+    let s = seq!(decl!(let (pattern(Pattern::from("c"))) = var!("d")),
+                 assign!(pattern(Pattern::from(var!("c"))), var!("e")));
+
+    let mut counter = VarCounter(Vec::new());
+    counter.visit_stmt(&s);
+
+    // Names being declared aren't expressions, but assignment targets are
+    assert_eq!(vec!["b", "d", "b", "c", "e"], counter.0);
+}